@@ -0,0 +1,53 @@
+//! Embeds the migrations of each database the server is built with, with their `down.sql`,
+//! so `radical migrate down` works without the source tree. `embed_migrations!` only keeps `up.sql`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let backends = ["mysql", "postgres", "sqlite"]
+        .iter()
+        .filter(|backend| env::var(format!("CARGO_FEATURE_{}", backend.to_uppercase())).is_ok());
+
+    for backend in backends {
+        embed(backend);
+    }
+}
+
+/// Writes the migrations in `migrations/<backend>` to `migrations_<backend>.rs`.
+fn embed(backend: &str) {
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("migrations")
+        .join(backend);
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut migrations: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    migrations.sort();
+
+    let mut out = String::from("&[\n");
+    for path in migrations {
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        // the same version diesel gives a migration folder, so installs migrated before keep their history.
+        let version: String = name.split('_').next().unwrap().replace('-', "");
+
+        for file in &["up.sql", "down.sql"] {
+            println!("cargo:rerun-if-changed={}", path.join(file).display());
+        }
+
+        out.push_str(&format!(
+            "    EmbeddedMigration {{ version: {:?}, name: {:?}, up: include_str!({:?}), down: include_str!({:?}) }},\n",
+            version,
+            name,
+            path.join("up.sql"),
+            path.join("down.sql"),
+        ));
+    }
+    out.push(']');
+
+    fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join(format!("migrations_{}.rs", backend)), out).unwrap();
+}
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE collection_entries;
+DROP TABLE collections;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS collections (
+    uuid varchar(255) PRIMARY KEY,
+    name varchar(255) NOT NULL UNIQUE,
+    title varchar(500) NOT NULL,
+    fields TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS collection_entries (
+    uuid varchar(255) PRIMARY KEY,
+    collection_uuid varchar(255) NOT NULL,
+    slug varchar(255) NOT NULL,
+    status varchar(32) NOT NULL DEFAULT 'draft',
+    content MEDIUMTEXT NOT NULL,
+    time_created TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    time_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (collection_uuid, slug),
+    FOREIGN KEY (collection_uuid) REFERENCES collections(uuid) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS redirects (
+    uuid varchar(255) PRIMARY KEY,
+    source_path varchar(255) NOT NULL UNIQUE,
+    target varchar(2048),
+    status_code INT NOT NULL DEFAULT 301,
+    hits BIGINT NOT NULL DEFAULT 0,
+    time_created TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
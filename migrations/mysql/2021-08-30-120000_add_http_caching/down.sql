@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE modules DROP COLUMN time_updated;
+
+ALTER TABLE pages
+    DROP COLUMN cache_control,
+    DROP COLUMN time_updated;
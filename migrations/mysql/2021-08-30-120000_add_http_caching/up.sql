@@ -0,0 +1,6 @@
+ALTER TABLE pages
+    ADD COLUMN time_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN cache_control varchar(255);
+
+ALTER TABLE modules
+    ADD COLUMN time_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE module_category DROP COLUMN version;
+
+ALTER TABLE modules DROP COLUMN version;
+
+ALTER TABLE pages DROP COLUMN version;
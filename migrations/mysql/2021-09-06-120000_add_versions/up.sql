@@ -0,0 +1,5 @@
+ALTER TABLE pages ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+ALTER TABLE modules ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+ALTER TABLE module_category ADD COLUMN version INT NOT NULL DEFAULT 1;
@@ -0,0 +1,2 @@
+-- Users that were there before roles keep managing everything.
+ALTER TABLE users ADD COLUMN role varchar(255) NOT NULL DEFAULT 'admin';
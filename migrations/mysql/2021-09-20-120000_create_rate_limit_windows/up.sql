@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS rate_limit_windows (
+    client_key varchar(255) NOT NULL,
+    window_end BIGINT NOT NULL,
+    requests INT NOT NULL,
+    PRIMARY KEY (client_key, window_end)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE collection_entries;
+DROP TABLE collections;
+DROP FUNCTION set_time_updated;
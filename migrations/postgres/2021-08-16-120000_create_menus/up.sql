@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS menus (
+    uuid varchar(255) PRIMARY KEY,
+    name varchar(255) NOT NULL UNIQUE,
+    title varchar(500) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS menu_items (
+    uuid varchar(255) PRIMARY KEY,
+    menu_uuid varchar(255) NOT NULL,
+    parent_uuid varchar(255),
+    position INT NOT NULL DEFAULT 0,
+    title varchar(500) NOT NULL,
+    page_uuid varchar(255),
+    url varchar(2048),
+    FOREIGN KEY (menu_uuid) REFERENCES menus(uuid) ON DELETE CASCADE,
+    FOREIGN KEY (parent_uuid) REFERENCES menu_items(uuid) ON DELETE CASCADE,
+    FOREIGN KEY (page_uuid) REFERENCES pages(uuid) ON DELETE CASCADE
+);
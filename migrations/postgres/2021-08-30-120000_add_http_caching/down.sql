@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER modules_time_updated ON modules;
+
+ALTER TABLE modules DROP COLUMN time_updated;
+
+DROP TRIGGER pages_time_updated ON pages;
+
+ALTER TABLE pages
+    DROP COLUMN cache_control,
+    DROP COLUMN time_updated;
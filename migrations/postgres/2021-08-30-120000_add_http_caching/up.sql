@@ -0,0 +1,12 @@
+ALTER TABLE pages
+    ADD COLUMN time_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN cache_control varchar(255);
+
+CREATE TRIGGER pages_time_updated BEFORE UPDATE ON pages
+    FOR EACH ROW EXECUTE PROCEDURE set_time_updated();
+
+ALTER TABLE modules
+    ADD COLUMN time_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL;
+
+CREATE TRIGGER modules_time_updated BEFORE UPDATE ON modules
+    FOR EACH ROW EXECUTE PROCEDURE set_time_updated();
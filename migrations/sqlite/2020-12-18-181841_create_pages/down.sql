@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE modules;
+DROP TABLE module_category;
+DROP TABLE pages;
+DROP TABLE users;
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER collection_entries_time_updated;
+DROP TABLE collection_entries;
+DROP TABLE collections;
@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS collections (
+    uuid varchar(255) PRIMARY KEY,
+    name varchar(255) NOT NULL UNIQUE,
+    title varchar(500) NOT NULL,
+    fields TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS collection_entries (
+    uuid varchar(255) PRIMARY KEY,
+    collection_uuid varchar(255) NOT NULL,
+    slug varchar(255) NOT NULL,
+    status varchar(32) NOT NULL DEFAULT 'draft',
+    content TEXT NOT NULL,
+    time_created TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    time_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (collection_uuid, slug),
+    FOREIGN KEY (collection_uuid) REFERENCES collections(uuid) ON DELETE CASCADE
+);
+
+-- SQLite has no `ON UPDATE CURRENT_TIMESTAMP`, so tables with a `time_updated` column get a trigger that sets it.
+-- Updates that set `time_updated` themselves are left alone.
+CREATE TRIGGER collection_entries_time_updated AFTER UPDATE ON collection_entries
+    WHEN NEW.time_updated = OLD.time_updated
+BEGIN
+    UPDATE collection_entries SET time_updated = CURRENT_TIMESTAMP WHERE uuid = NEW.uuid;
+END;
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS vocabularies (
+    uuid varchar(255) PRIMARY KEY,
+    name varchar(255) NOT NULL UNIQUE,
+    title varchar(500) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS terms (
+    uuid varchar(255) PRIMARY KEY,
+    vocabulary_uuid varchar(255) NOT NULL,
+    parent_uuid varchar(255),
+    slug varchar(255) NOT NULL,
+    title varchar(500) NOT NULL,
+    UNIQUE (vocabulary_uuid, slug),
+    FOREIGN KEY (vocabulary_uuid) REFERENCES vocabularies(uuid) ON DELETE CASCADE,
+    FOREIGN KEY (parent_uuid) REFERENCES terms(uuid) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS page_terms (
+    page_uuid varchar(255) NOT NULL,
+    term_uuid varchar(255) NOT NULL,
+    PRIMARY KEY (page_uuid, term_uuid),
+    FOREIGN KEY (page_uuid) REFERENCES pages(uuid) ON DELETE CASCADE,
+    FOREIGN KEY (term_uuid) REFERENCES terms(uuid) ON DELETE CASCADE
+);
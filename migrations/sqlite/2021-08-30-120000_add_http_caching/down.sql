@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER modules_time_updated;
+DROP TRIGGER modules_time_created;
+
+ALTER TABLE modules DROP COLUMN time_updated;
+
+DROP TRIGGER pages_time_updated;
+DROP TRIGGER pages_time_created;
+
+ALTER TABLE pages DROP COLUMN cache_control;
+ALTER TABLE pages DROP COLUMN time_updated;
@@ -0,0 +1,30 @@
+-- SQLite can only add a column with a constant default, so existing rows are stamped here
+-- and new rows are stamped by the insert triggers.
+ALTER TABLE pages ADD COLUMN time_updated TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE pages ADD COLUMN cache_control varchar(255);
+UPDATE pages SET time_updated = CURRENT_TIMESTAMP;
+
+CREATE TRIGGER pages_time_created AFTER INSERT ON pages
+BEGIN
+    UPDATE pages SET time_updated = CURRENT_TIMESTAMP WHERE uuid = NEW.uuid;
+END;
+
+CREATE TRIGGER pages_time_updated AFTER UPDATE ON pages
+    WHEN NEW.time_updated = OLD.time_updated
+BEGIN
+    UPDATE pages SET time_updated = CURRENT_TIMESTAMP WHERE uuid = NEW.uuid;
+END;
+
+ALTER TABLE modules ADD COLUMN time_updated TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE modules SET time_updated = CURRENT_TIMESTAMP;
+
+CREATE TRIGGER modules_time_created AFTER INSERT ON modules
+BEGIN
+    UPDATE modules SET time_updated = CURRENT_TIMESTAMP WHERE uuid = NEW.uuid;
+END;
+
+CREATE TRIGGER modules_time_updated AFTER UPDATE ON modules
+    WHEN NEW.time_updated = OLD.time_updated
+BEGIN
+    UPDATE modules SET time_updated = CURRENT_TIMESTAMP WHERE uuid = NEW.uuid;
+END;
@@ -0,0 +1,70 @@
+/// Builds the `App` with every router, middleware and piece of shared state.
+/// `main` runs it in the `HttpServer` and the tests run it against their own database, so both serve the same app.
+///
+/// It's a macro because the type of a wrapped `App` can't be written down for a function to return.
+macro_rules! app {
+    ($settings:expr, $pool:expr, $templates:expr, $page_cache:expr, $repositories:expr, $rate_limits:expr) => {{
+        use actix_files as fs;
+        use actix_web::middleware::Logger;
+        use actix_web::{web, App};
+
+        use $crate::controllers::system_controllers::{healthz, readyz};
+        use $crate::routers::batch_routers::BatchRouter;
+        use $crate::routers::cache_routers::CacheRouter;
+        use $crate::routers::category_routers::CategoryRouter;
+        use $crate::routers::collection_routers::CollectionRouter;
+        use $crate::routers::menu_routers::MenuRouter;
+        use $crate::routers::module_routers::ModuleRouter;
+        use $crate::routers::page_routers::PageRouter;
+        use $crate::routers::redirect_routers::RedirectRouter;
+        use $crate::routers::system_routers::SystemRouter;
+        use $crate::routers::taxonomy_routers::TaxonomyRouter;
+        use $crate::routers::user_routers::UserRouter;
+        use $crate::routers::Router;
+        use $crate::services::cors_service::Cors;
+        use $crate::services::errors_service::{json_error, path_error, query_error};
+        use $crate::services::http_cache_service::HttpCache;
+        use $crate::services::rate_limit_service::RateLimit;
+
+        let api_scope = web::scope("/v1")
+            .service(UserRouter::new())
+            .service(PageRouter::new())
+            .service(ModuleRouter::new())
+            .service(CategoryRouter::new())
+            .service(CollectionRouter::new())
+            .service(TaxonomyRouter::new())
+            .service(MenuRouter::new())
+            .service(RedirectRouter::new())
+            .service(CacheRouter::new())
+            .service(BatchRouter::new())
+            .service(SystemRouter::new());
+
+        App::new()
+            .wrap(HttpCache::new($settings.clone()))
+            // probes come every few seconds, so they'd drown out the requests worth logging.
+            .wrap(Logger::new("%a -> %U | %Dms ").exclude("/healthz").exclude("/readyz"))
+            .wrap(RateLimit::new($settings.clone(), $rate_limits.clone()))
+            // the last wrap runs first, so preflights are answered before they're counted
+            // and a `429` gets CORS headers, which lets the page read `Retry-After`.
+            .wrap(Cors::new($settings.clone()))
+            .route("/healthz", web::get().to(healthz))
+            .route("/readyz", web::get().to(readyz))
+            .service(api_scope)
+            .service(fs::Files::new("/assets", "./templates/assets").show_files_listing())
+            .default_service(web::get().to($crate::controllers::page_controllers::display_page))
+            .data($pool.clone())
+            .app_data($settings.clone())
+            .app_data($templates.clone())
+            .app_data($page_cache.clone())
+            .app_data($repositories.content.clone())
+            .app_data($repositories.collections.clone())
+            .app_data($repositories.taxonomy.clone())
+            .app_data($repositories.menus.clone())
+            .app_data($repositories.redirects.clone())
+            .app_data($repositories.users.clone())
+            // malformed bodies, query strings and paths get the same problem documents as every other error.
+            .app_data(web::JsonConfig::default().error_handler(json_error))
+            .app_data(web::QueryConfig::default().error_handler(query_error))
+            .app_data(web::PathConfig::default().error_handler(path_error))
+    }};
+}
@@ -0,0 +1,249 @@
+//! The `radical` command line. Without a command it starts the server, as `radical serve` does.
+//! Every other command runs against the configured database and exits, so installs can be managed from scripts and containers.
+
+pub mod user;
+
+use actix_web::client::Client;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+use crate::models::config_models::LocalConfig;
+use crate::models::{establish_database_connection, DbPool};
+use crate::services::config_service::ConfigError;
+use crate::services::errors_service::CustomHttpError;
+use crate::services::{migration_service, template_service, transfer_service};
+
+#[derive(StructOpt)]
+#[structopt(name = "radical", about = "A headless CMS.")]
+pub struct Cli {
+    /// TOML file to read settings from, under `APP_` environment variables. Defaults to `radical.toml` if it exists.
+    #[structopt(long, global = true, env = "APP_CONFIG", parse(from_os_str))]
+    pub config: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub serve: ServeArgs,
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Clone)]
+pub struct ServeArgs {
+    /// Fixture sets to load before starting, as `root,demo`. Takes the place of `APP_SEED`.
+    #[structopt(long)]
+    pub seed: Option<String>,
+    /// Starts without running pending migrations, for installs that run `radical migrate` themselves.
+    #[structopt(long)]
+    pub no_migrate: bool,
+}
+
+#[derive(StructOpt)]
+pub enum Command {
+    /// Runs pending migrations, loads fixture sets and starts the server.
+    Serve(ServeArgs),
+    /// Runs or reverts migrations.
+    Migrate(MigrateCommand),
+    /// Manages users.
+    User(UserCommand),
+    /// Writes every page, module, collection, taxonomy, menu and redirect as JSON. Users are left out.
+    Export {
+        /// File to write to, instead of standard output.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Adds everything in a file written by `export`. Nothing is added if any of it is already there.
+    Import { file: PathBuf },
+    /// Manages the page cache of a running server.
+    Cache(CacheCommand),
+    /// Compiles every template and reports the ones that don't compile.
+    CheckTemplates,
+}
+
+#[derive(StructOpt)]
+pub enum MigrateCommand {
+    /// Runs every pending migration.
+    Up,
+    /// Reverts the latest migration.
+    Down,
+    /// Lists every migration and whether it ran.
+    Status,
+}
+
+#[derive(StructOpt)]
+pub enum UserCommand {
+    /// Adds a user. The password is read from standard input.
+    Create {
+        username: String,
+        /// `admin` or `editor`. Only admins can add and delete users through the API.
+        #[structopt(long, default_value = "admin")]
+        role: String,
+    },
+    /// Sets a new password, read from standard input, and logs the user out.
+    ResetPassword { username: String },
+    /// Makes the user an `admin` or an `editor`.
+    SetRole { username: String, role: String },
+}
+
+#[derive(StructOpt)]
+pub enum CacheCommand {
+    /// Empties the page cache, through `DELETE /v1/cache`.
+    Clear {
+        /// The server's address. Defaults to `APP_BIND_ADDRESS` and `APP_BIND_PORT`.
+        #[structopt(long)]
+        url: Option<String>,
+        /// A token from logging in, sent as the `Authorization` header.
+        #[structopt(long, env = "RADICAL_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+}
+
+/// Runs any command but `serve`.
+pub async fn run(command: Command, conf: &LocalConfig) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Serve(_) => unreachable!("`serve` is run by main"),
+        Command::Migrate(command) => migrate(command, &pool(conf)?),
+        Command::User(command) => manage_user(command, &pool(conf)?),
+        Command::Export { output } => {
+            let db = pool(conf)?.get()?;
+            let export = transfer_service::export(&db)?;
+            let json = serde_json::to_string_pretty(&export)?;
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json)?;
+                    eprintln!("Exported {} rows to {}.", export.rows(), path.display());
+                }
+                None => println!("{}", json),
+            }
+
+            Ok(())
+        }
+        Command::Import { file } => {
+            let export = serde_json::from_str(&std::fs::read_to_string(&file)?)
+                .map_err(|e| format!("{} isn't an export: {}", file.display(), e))?;
+            let db = pool(conf)?.get()?;
+            let rows = transfer_service::import(&export, &db)?;
+
+            println!("Imported {} rows from {}.", rows, file.display());
+            println!("Run `radical cache clear` if a server is running, so it stops serving cached pages.");
+            Ok(())
+        }
+        Command::Cache(CacheCommand::Clear { url, token }) => {
+            let url = url.unwrap_or_else(|| server_url(conf));
+            let res = Client::default()
+                .delete(format!("{}/v1/cache", url.trim_end_matches('/')))
+                .header("Authorization", token)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to reach the server at {}: {}", url, e))?;
+
+            if !res.status().is_success() {
+                return Err(format!("The server at {} answered {}.", url, res.status()).into());
+            }
+
+            println!("Cleared the page cache.");
+            Ok(())
+        }
+        Command::CheckTemplates => match template_service::check() {
+            Ok(count) => {
+                println!("All {} templates compiled.", count);
+                Ok(())
+            }
+            Err(errors) => {
+                for error in &errors {
+                    eprintln!("{}", error);
+                }
+
+                Err(format!("{} templates failed to compile.", errors.len()).into())
+            }
+        },
+    }
+}
+
+fn pool(conf: &LocalConfig) -> Result<DbPool, ConfigError> {
+    establish_database_connection(conf.clone())
+}
+
+/// The address the server listens on, as seen from the same machine.
+fn server_url(conf: &LocalConfig) -> String {
+    let host = match conf.bind_address.as_str() {
+        "0.0.0.0" | "::" => "127.0.0.1",
+        address => address,
+    };
+
+    format!("http://{}:{}", host, conf.bind_port)
+}
+
+fn migrate(command: MigrateCommand, pool: &DbPool) -> Result<(), Box<dyn Error>> {
+    let db = pool.get()?;
+
+    match command {
+        MigrateCommand::Up => {
+            let ran = migration_service::run(&db)?;
+            for migration in &ran {
+                println!("Ran {}.", migration.name);
+            }
+
+            if ran.is_empty() {
+                println!("No migrations are pending.");
+            }
+        }
+        MigrateCommand::Down => match migration_service::revert_latest(&db)? {
+            Some(migration) => println!("Reverted {}.", migration.name),
+            None => println!("No migrations have run."),
+        },
+        MigrateCommand::Status => {
+            for status in migration_service::status(&db)? {
+                let state = if status.applied { "applied" } else { "pending" };
+                println!("{}  {}", state, status.migration.name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn manage_user(command: UserCommand, pool: &DbPool) -> Result<(), Box<dyn Error>> {
+    let db = pool.get()?;
+
+    match command {
+        UserCommand::Create { username, role } => {
+            let password = read_password()?;
+            user::create(&username, &password, &role, &db).map_err(|e| describe(e, &username))?;
+
+            println!("Created {} as {}.", username, role);
+        }
+        UserCommand::ResetPassword { username } => {
+            let password = read_password()?;
+            user::reset_password(&username, &password, &db).map_err(|e| describe(e, &username))?;
+
+            println!("Reset the password of {} and logged them out.", username);
+        }
+        UserCommand::SetRole { username, role } => {
+            user::set_role(&username, &role, &db).map_err(|e| describe(e, &username))?;
+
+            println!("{} is now {}.", username, role);
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(e: CustomHttpError, username: &str) -> String {
+    match e {
+        CustomHttpError::NotFound => format!("There is no user named {}.", username),
+        e => e.describe(),
+    }
+}
+
+/// Reads a password from the first line of standard input, so scripts can pipe it in
+/// rather than pass it where other processes can see it.
+fn read_password() -> std::io::Result<String> {
+    eprint!("Password: ");
+    std::io::stderr().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
@@ -0,0 +1,55 @@
+use uuid::Uuid;
+use validator::ValidationErrors;
+
+use crate::models::user_models::{MutUser, User};
+use crate::models::{DbConnection, Model};
+use crate::services::auth_service::encrypt_password;
+use crate::services::errors_service::CustomHttpError;
+use crate::services::validation_service::{validate, validate_role};
+
+/// Adds a user with `password`, checked by the same rules as users created through the API.
+pub fn create(username: &str, password: &str, role: &str, db: &DbConnection) -> Result<(), CustomHttpError> {
+    let mut new_user = MutUser {
+        uuid: Some(Uuid::new_v4().to_string()),
+        username: username.to_string(),
+        password: Some(password.to_string()),
+        token: None,
+        role: Some(role.to_string()),
+    };
+
+    validate(&new_user, None, db)?;
+    new_user.password = Some(encrypt_password(&password.to_string())?);
+
+    User::create(&new_user, db)?;
+    Ok(())
+}
+
+/// Replaces the user's password, which logs them out everywhere.
+pub fn reset_password(username: &str, password: &str, db: &DbConnection) -> Result<(), CustomHttpError> {
+    let user = User::read_one(username.to_string(), db)?;
+    let changed = MutUser {
+        uuid: Some(user.uuid),
+        username: user.username,
+        password: Some(password.to_string()),
+        token: None,
+        role: None,
+    };
+
+    validate(&changed, Some(username), db)?;
+    User::reset_password(username, &encrypt_password(&password.to_string())?, db)?;
+
+    Ok(())
+}
+
+pub fn set_role(username: &str, role: &str, db: &DbConnection) -> Result<(), CustomHttpError> {
+    validate_role(role).map_err(|e| {
+        let mut errors = ValidationErrors::new();
+        errors.add("role", e);
+        errors
+    })?;
+
+    match User::set_role(username, role, db)? {
+        0 => Err(CustomHttpError::NotFound),
+        _ => Ok(()),
+    }
+}
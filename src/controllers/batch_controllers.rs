@@ -0,0 +1,235 @@
+use actix_web::{error::ResponseError, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::models::batch_models::{BatchAction, BatchFailure, BatchOperation, BatchRequest, BatchResult};
+use crate::models::module_models::{MutCategory, MutModule};
+use crate::models::page_models::MutPage;
+use crate::repositories::{run_repository, Repository};
+
+use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
+use crate::services::errors_service::{CustomHttpError, FieldError, PROBLEM_CONTENT_TYPE};
+
+/// Most operations a single batch can hold, so one request can't hold a transaction open for too long.
+const MAX_OPERATIONS: usize = 100;
+
+/// Runs every operation in one transaction.
+/// Either all of them are applied and their results are returned in order,
+/// or the first failure rolls back the whole batch and is returned with its position.
+pub async fn run_batch(
+    batch: web::Json<BatchRequest>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
+    _: Claims
+) -> Result<HttpResponse, CustomHttpError> {
+    if batch.operations.len() > MAX_OPERATIONS {
+        return Err(CustomHttpError::Invalid(vec![FieldError::new(
+            "operations",
+            "too_many",
+            format!("A batch can hold at most {} operations.", MAX_OPERATIONS),
+        )]));
+    }
+
+    let operations = batch.into_inner().operations;
+    // menus and page listings show pages' urls and titles on other pages too.
+    let pages_changed = operations.iter().any(|operation| {
+        matches!(
+            operation,
+            BatchOperation::Page(BatchAction::Update { .. } | BatchAction::Delete { .. })
+        )
+    });
+
+    let outcome = run_repository(repository, move |repository| {
+        let mut results = Vec::with_capacity(operations.len());
+        let mut changed_pages = Vec::new();
+        let mut failed = None;
+
+        let committed = repository.transaction(&mut |repository| {
+            for (i, operation) in operations.iter().enumerate() {
+                // rows created earlier in the batch are only known by their position until now.
+                let applied = operation
+                    .resolve(&results)
+                    .and_then(|operation| apply(&operation, &mut changed_pages, repository));
+
+                match applied {
+                    Ok(result) => results.push(result),
+                    Err(e) => {
+                        failed = Some(i);
+                        return Err(e);
+                    }
+                }
+            }
+
+            Ok(())
+        });
+
+        // an error without a failed operation happened while committing, so it isn't the client's.
+        match (committed, failed) {
+            (Ok(()), _) => Ok(Ok((results, changed_pages))),
+            (Err(e), Some(i)) => Ok(Err((i, e))),
+            (Err(e), None) => Err(e),
+        }
+    })
+    .await?;
+
+    match outcome {
+        Ok((results, changed_pages)) => {
+            if pages_changed {
+                cache.clear();
+            } else {
+                for page_uuid in changed_pages {
+                    cache.invalidate_page(&page_uuid);
+                }
+            }
+
+            Ok(HttpResponse::Ok().json(results))
+        }
+        Err((operation, e)) => Ok(HttpResponse::build(e.status_code())
+            .content_type(PROBLEM_CONTENT_TYPE)
+            .json(BatchFailure {
+                problem: e.problem(),
+                operation,
+            })),
+    }
+}
+
+/// Applies one operation with the same writes its own endpoint makes.
+/// The uuids of pages whose rendered html is now stale are added to `changed_pages`.
+fn apply(
+    operation: &BatchOperation,
+    changed_pages: &mut Vec<String>,
+    repository: &dyn Repository,
+) -> Result<BatchResult, CustomHttpError> {
+    match operation {
+        BatchOperation::Page(action) => apply_page(action, changed_pages, repository),
+        BatchOperation::Module(action) => apply_module(action, changed_pages, repository),
+        BatchOperation::Category(action) => apply_category(action, changed_pages, repository),
+    }
+}
+
+fn apply_page(
+    action: &BatchAction<MutPage>,
+    changed_pages: &mut Vec<String>,
+    repository: &dyn Repository,
+) -> Result<BatchResult, CustomHttpError> {
+    match action {
+        BatchAction::Create { data } => {
+            let mut page = data.clone();
+            let uuid = Uuid::new_v4().to_string();
+            page.uuid = Some(uuid.clone());
+
+            repository.create_page(&page)?;
+
+            let new_page = repository.read_page(&uuid)?;
+            Ok(created(uuid, new_page.version))
+        }
+        BatchAction::Update { id, version, data } => {
+            let expected_version = version.ok_or(CustomHttpError::PreconditionRequired)?;
+            let (_, version) = repository.update_page(id, Some(expected_version), data)?;
+            changed_pages.push(id.clone());
+
+            Ok(updated(id.clone(), version))
+        }
+        BatchAction::Delete { id, version } => {
+            let expected_version = version.ok_or(CustomHttpError::PreconditionRequired)?;
+            repository.delete_page(id, Some(expected_version))?;
+            changed_pages.push(id.clone());
+
+            Ok(deleted(id.clone()))
+        }
+    }
+}
+
+fn apply_module(
+    action: &BatchAction<MutModule>,
+    changed_pages: &mut Vec<String>,
+    repository: &dyn Repository,
+) -> Result<BatchResult, CustomHttpError> {
+    match action {
+        BatchAction::Create { data } => {
+            let mut module = data.clone();
+            let uuid = Uuid::new_v4().to_string();
+            module.uuid = Some(uuid.clone());
+
+            repository.create_module(&module)?;
+            changed_pages.push(module.page_uuid);
+
+            let new_module = repository.read_module(&uuid)?;
+            Ok(created(uuid, new_module.version))
+        }
+        BatchAction::Update { id, version, data } => {
+            let expected_version = version.ok_or(CustomHttpError::PreconditionRequired)?;
+            let (old_module, version) = repository.update_module(id, Some(expected_version), data)?;
+            changed_pages.push(old_module.page_uuid);
+            changed_pages.push(data.page_uuid.clone());
+
+            Ok(updated(id.clone(), version))
+        }
+        BatchAction::Delete { id, version } => {
+            let expected_version = version.ok_or(CustomHttpError::PreconditionRequired)?;
+            let (old_module, _) = repository.delete_module(id, Some(expected_version))?;
+            changed_pages.push(old_module.page_uuid);
+
+            Ok(deleted(id.clone()))
+        }
+    }
+}
+
+fn apply_category(
+    action: &BatchAction<MutCategory>,
+    changed_pages: &mut Vec<String>,
+    repository: &dyn Repository,
+) -> Result<BatchResult, CustomHttpError> {
+    match action {
+        BatchAction::Create { data } => {
+            let mut category = data.clone();
+            let uuid = Uuid::new_v4().to_string();
+            category.uuid = Some(uuid.clone());
+
+            repository.create_category(&category)?;
+            changed_pages.push(category.page_uuid);
+
+            let new_category = repository.read_category(&uuid)?;
+            Ok(created(uuid, new_category.version))
+        }
+        BatchAction::Update { id, version, data } => {
+            let expected_version = version.ok_or(CustomHttpError::PreconditionRequired)?;
+            let (old_category, version) = repository.update_category(id, Some(expected_version), data)?;
+            changed_pages.push(old_category.page_uuid);
+            changed_pages.push(data.page_uuid.clone());
+
+            Ok(updated(id.clone(), version))
+        }
+        BatchAction::Delete { id, version } => {
+            let expected_version = version.ok_or(CustomHttpError::PreconditionRequired)?;
+            let (old_category, _) = repository.delete_category(id, Some(expected_version))?;
+            changed_pages.push(old_category.page_uuid);
+
+            Ok(deleted(id.clone()))
+        }
+    }
+}
+
+fn created(uuid: String, version: i32) -> BatchResult {
+    BatchResult {
+        status: 201,
+        uuid,
+        version: Some(version),
+    }
+}
+
+fn updated(uuid: String, version: i32) -> BatchResult {
+    BatchResult {
+        status: 200,
+        uuid,
+        version: Some(version),
+    }
+}
+
+fn deleted(uuid: String) -> BatchResult {
+    BatchResult {
+        status: 200,
+        uuid,
+        version: None,
+    }
+}
@@ -0,0 +1,21 @@
+use actix_web::{web, HttpResponse};
+
+use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
+use crate::services::errors_service::CustomHttpError;
+
+pub async fn get_cache_stats(
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    Ok(HttpResponse::Ok().json(cache.stats()))
+}
+
+pub async fn clear_cache(
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(cache.stats()))
+}
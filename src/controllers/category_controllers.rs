@@ -1,58 +1,76 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use uuid::Uuid;
 
-use crate::models::module_models::{ModuleCategory, MutCategory};
-use crate::models::{pool_handler, Model, MySQLPool};
+use crate::models::module_models::MutCategory;
+use crate::repositories::{run_repository, Repository};
 use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
 use crate::services::errors_service::CustomHttpError;
+use crate::services::http_cache_service::{if_match, version_tag};
 
 pub async fn create_category(
     new: web::Json<MutCategory>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
     let mut uuid_new = new.clone();
     uuid_new.uuid = Some(Uuid::new_v4().to_string());
 
-    ModuleCategory::create(&uuid_new, &mysql_pool)?;
+    let category = uuid_new.clone();
+    run_repository(repository, move |repository| repository.create_category(&category)).await?;
+    cache.invalidate_page(&uuid_new.page_uuid);
 
     Ok(HttpResponse::Created().json(uuid_new))
 }
 
 pub async fn update_category(
+    req: HttpRequest,
     updated_category: web::Json<MutCategory>,
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
+    let expected_version = if_match(&req)?;
+    let category = updated_category.clone();
+
+    let (old_category, version) =
+        run_repository(repository, move |repository| repository.update_category(&id, expected_version, &category))
+            .await?;
 
-    ModuleCategory::update(id.clone(), &updated_category, &mysql_pool)?;
+    cache.invalidate_page(&old_category.page_uuid);
+    cache.invalidate_page(&updated_category.page_uuid);
 
-    Ok(HttpResponse::Ok().json(updated_category.0))
+    Ok(HttpResponse::Ok()
+        .set(version_tag(version))
+        .json(updated_category.0))
 }
 
 pub async fn get_category(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    let res = ModuleCategory::read_one(id.clone(), &mysql_pool)?;
+    let res = run_repository(repository, move |repository| repository.read_category(&id)).await?;
 
-    Ok(HttpResponse::Ok().json(res))
+    Ok(HttpResponse::Ok()
+        .set(version_tag(res.version))
+        .json(res))
 }
 
 pub async fn delete_category(
+    req: HttpRequest,
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
+    let expected_version = if_match(&req)?;
+
+    let (old_category, res) =
+        run_repository(repository, move |repository| repository.delete_category(&id, expected_version)).await?;
 
-    let res = ModuleCategory::delete(id.clone(), &mysql_pool)?;
+    cache.invalidate_page(&old_category.page_uuid);
 
     Ok(HttpResponse::Ok().json(res))
 }
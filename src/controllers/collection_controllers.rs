@@ -0,0 +1,168 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::models::collection_models::{MutCollection, MutCollectionEntry};
+use crate::models::pagination::ListQuery;
+use crate::repositories::{run_repository, CollectionRepository};
+use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
+use crate::services::errors_service::CustomHttpError;
+use crate::services::pagination_service::paginated_response;
+
+/// Filters of an entry listing, next to the paging and sort of its `ListQuery`.
+#[derive(Deserialize)]
+pub struct EntryParams {
+    pub status: Option<String>,
+}
+
+pub async fn create_collection(
+    new: web::Json<MutCollection>,
+    collections: web::Data<dyn CollectionRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.clone();
+    uuid_new.uuid = Some(Uuid::new_v4().to_string());
+
+    let collection = uuid_new.clone();
+    run_repository(collections, move |collections| collections.create_collection(&collection)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Created().json(uuid_new))
+}
+
+pub async fn get_collections(collections: web::Data<dyn CollectionRepository>) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(collections, |collections| collections.list_collections()).await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn get_collection(
+    collection_type: web::Path<String>,
+    collections: web::Data<dyn CollectionRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let collection =
+        run_repository(collections, move |collections| collections.read_collection(&collection_type)).await?;
+
+    Ok(HttpResponse::Ok().json(collection))
+}
+
+pub async fn update_collection(
+    updated_collection: web::Json<MutCollection>,
+    collection_type: web::Path<String>,
+    collections: web::Data<dyn CollectionRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    // the uuid is the primary key, so it's kept rather than taken from the body.
+    let mut collection = updated_collection.into_inner();
+    collection.uuid = None;
+
+    let updated = collection.clone();
+    run_repository(collections, move |collections| {
+        collections.update_collection(&collection_type, &updated)
+    })
+    .await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(collection))
+}
+
+pub async fn delete_collection(
+    collection_type: web::Path<String>,
+    collections: web::Data<dyn CollectionRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let res =
+        run_repository(collections, move |collections| collections.delete_collection(&collection_type)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn create_entry(
+    new: web::Json<MutCollectionEntry>,
+    collection_type: web::Path<String>,
+    collections: web::Data<dyn CollectionRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.into_inner();
+    uuid_new.uuid = Some(Uuid::new_v4().to_string());
+
+    let entry = run_repository(collections, move |collections| {
+        collections.create_entry(&collection_type, &uuid_new)
+    })
+    .await?;
+    cache.clear();
+
+    Ok(HttpResponse::Created().json(entry))
+}
+
+pub async fn get_entries(
+    req: web::HttpRequest,
+    collection_type: web::Path<String>,
+    query: web::Query<ListQuery>,
+    params: web::Query<EntryParams>,
+    collections: web::Data<dyn CollectionRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let list_query = query.clone();
+    let status = params.into_inner().status;
+
+    let entries = run_repository(collections, move |collections| {
+        collections.list_entries(&collection_type, &list_query, status)
+    })
+    .await?;
+
+    Ok(paginated_response(&req, &query, entries))
+}
+
+pub async fn get_entry(
+    path: web::Path<(String, String)>,
+    collections: web::Data<dyn CollectionRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (collection_type, id) = path.into_inner();
+
+    let entry =
+        run_repository(collections, move |collections| collections.read_entry(&collection_type, &id)).await?;
+
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+pub async fn update_entry(
+    updated_entry: web::Json<MutCollectionEntry>,
+    path: web::Path<(String, String)>,
+    collections: web::Data<dyn CollectionRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (collection_type, id) = path.into_inner();
+    // the uuid is the primary key, so it's kept rather than taken from the body.
+    let mut entry = updated_entry.into_inner();
+    entry.uuid = None;
+
+    let entry = run_repository(collections, move |collections| {
+        collections.update_entry(&collection_type, &id, &entry)
+    })
+    .await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+pub async fn delete_entry(
+    path: web::Path<(String, String)>,
+    collections: web::Data<dyn CollectionRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (collection_type, id) = path.into_inner();
+
+    let res =
+        run_repository(collections, move |collections| collections.delete_entry(&collection_type, &id)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
@@ -0,0 +1,112 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::models::menu_models::{MutMenu, MutMenuItem};
+use crate::repositories::{run_repository, MenuRepository};
+use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
+use crate::services::errors_service::CustomHttpError;
+
+pub async fn create_menu(
+    new: web::Json<MutMenu>,
+    menus: web::Data<dyn MenuRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.clone();
+    uuid_new.uuid = Some(Uuid::new_v4().to_string());
+
+    let menu = uuid_new.clone();
+    run_repository(menus, move |menus| menus.create_menu(&menu)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Created().json(uuid_new))
+}
+
+pub async fn get_menus(menus: web::Data<dyn MenuRepository>) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(menus, |menus| menus.list_menus()).await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn get_menu(
+    name: web::Path<String>,
+    menus: web::Data<dyn MenuRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let tree = run_repository(menus, move |menus| menus.read_menu_tree(&name, None)).await?;
+
+    Ok(HttpResponse::Ok().json(tree))
+}
+
+pub async fn update_menu(
+    updated_menu: web::Json<MutMenu>,
+    name: web::Path<String>,
+    menus: web::Data<dyn MenuRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let menu = updated_menu.clone();
+    run_repository(menus, move |menus| menus.update_menu(&name, &menu)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(updated_menu.0))
+}
+
+pub async fn delete_menu(
+    name: web::Path<String>,
+    menus: web::Data<dyn MenuRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(menus, move |menus| menus.delete_menu(&name)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn create_menu_item(
+    new: web::Json<MutMenuItem>,
+    name: web::Path<String>,
+    menus: web::Data<dyn MenuRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.into_inner();
+    uuid_new.uuid = Uuid::new_v4().to_string();
+
+    let item = run_repository(menus, move |menus| menus.create_menu_item(&name, &uuid_new)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Created().json(item))
+}
+
+pub async fn update_menu_item(
+    updated_item: web::Json<MutMenuItem>,
+    path: web::Path<(String, String)>,
+    menus: web::Data<dyn MenuRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (name, id) = path.into_inner();
+    let updated_item = updated_item.into_inner();
+
+    let new_item =
+        run_repository(menus, move |menus| menus.update_menu_item(&name, &id, &updated_item)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(new_item))
+}
+
+pub async fn delete_menu_item(
+    path: web::Path<(String, String)>,
+    menus: web::Data<dyn MenuRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (name, id) = path.into_inner();
+
+    let res = run_repository(menus, move |menus| menus.delete_menu_item(&name, &id)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
@@ -1,4 +1,11 @@
+pub mod batch_controllers;
 pub mod module_controllers;
 pub mod page_controllers;
+pub mod redirect_controllers;
+pub mod cache_controllers;
 pub mod category_controllers;
+pub mod collection_controllers;
+pub mod menu_controllers;
+pub mod system_controllers;
+pub mod taxonomy_controllers;
 pub mod user_controllers;
\ No newline at end of file
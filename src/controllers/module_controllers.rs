@@ -1,77 +1,161 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use uuid::Uuid;
 
-use crate::models::{Model, MySQLPool, pool_handler};
-use crate::models::module_models::{Module, ModuleCategory, MutModule};
+use crate::models::module_models::{Module, MutModule};
+use crate::models::page_models::PageDTO;
+use crate::models::pagination::{ListQuery, Paginated};
+use crate::repositories::{run_repository, Repository};
 
 use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
 use crate::services::errors_service::CustomHttpError;
+use crate::services::fieldset_service::{retain_fields, sparse_items, to_value, FieldsetQuery};
+use crate::services::pagination_service::paginated_response;
+use crate::services::http_cache_service::{if_match, last_modified, version_tag};
+
+/// Relations that can be embedded in a module with `include`.
+const MODULE_INCLUDES: &[&str] = &["page", "category"];
 
 pub async fn create_module(
     new: web::Json<MutModule>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
     let mut uuid_new = new.clone();
     uuid_new.uuid = Some(Uuid::new_v4().to_string());
 
-    Module::create(&uuid_new, &mysql_pool)?;
+    let module = uuid_new.clone();
+    run_repository(repository, move |repository| repository.create_module(&module)).await?;
+    cache.invalidate_page(&uuid_new.page_uuid);
 
     Ok(HttpResponse::Created().json(uuid_new))
 }
 
-pub async fn get_modules(pool: web::Data<MySQLPool>) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-    let modules = Module::read_all(&mysql_pool)?;
-
-    Ok(HttpResponse::Created().json(modules))
+pub async fn get_modules(
+    req: HttpRequest,
+    query: web::Query<ListQuery>,
+    fieldset: web::Query<FieldsetQuery>,
+    repository: web::Data<dyn Repository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    query.validate(Module::SORT_FIELDS)?;
+
+    let list_query = query.clone();
+    let mut modules = run_repository(repository, move |repository| {
+        repository.list_modules(&list_query)
+    })
+    .await?;
+    let items = sparse_items(std::mem::take(&mut modules.items), fieldset.fields.as_deref())?;
+
+    Ok(paginated_response(&req, &query, Paginated {
+        items,
+        total: modules.total,
+        next_cursor: modules.next_cursor,
+    }))
 }
 
 pub async fn get_module(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    query: web::Query<FieldsetQuery>,
+    repository: web::Data<dyn Repository>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    let module = Module::read_one(id.clone(), &mysql_pool)?;
-
-    Ok(HttpResponse::Created().json(module))
+    query.validate(MODULE_INCLUDES)?;
+
+    let include = query.includes(&[]);
+    let with_page = include.iter().any(|relation| relation == "page");
+    let with_category = include.iter().any(|relation| relation == "category");
+
+    let (module, page, category) = run_repository(repository, move |repository| {
+        let module = repository.read_module(&id)?;
+
+        let page: Option<PageDTO> = match with_page {
+            true => Some(repository.read_page(&module.page_uuid)?),
+            false => None,
+        };
+        let category = match (&module.category_uuid, with_category) {
+            (Some(category_uuid), true) => Some(repository.read_category(category_uuid)?),
+            _ => None,
+        };
+
+        Ok((module, page, category))
+    })
+    .await?;
+
+    let mut resource = to_value(&module)?;
+    if with_page {
+        resource["page"] = to_value(page)?;
+    }
+    if with_category {
+        resource["category"] = to_value(category)?;
+    }
+
+    retain_fields(Some(&mut resource), query.fields.as_deref(), &["page", "category"]);
+    retain_fields(resource.get_mut("page"), query.page_fields.as_deref(), &[]);
+    retain_fields(resource.get_mut("category"), query.category_fields.as_deref(), &[]);
+
+    let mut res = HttpResponse::Ok();
+    res.set(last_modified(module.time_updated));
+
+    // included relations change without the module's version changing, so those responses are tagged by content instead.
+    if include.is_empty() {
+        res.set(version_tag(module.version));
+    }
+
+    Ok(res.json(resource))
 }
 
 pub async fn update_module(
+    req: HttpRequest,
     updated_module: web::Json<MutModule>,
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
+    let expected_version = if_match(&req)?;
+    let module = updated_module.clone();
+
+    // the module may be moving to another page, so both pages are invalidated.
+    let (old_module, version) = run_repository(repository, move |repository| {
+        repository.update_module(&id, expected_version, &module)
+    })
+    .await?;
 
-    Module::update(id.clone(), &updated_module, &mysql_pool)?;
+    cache.invalidate_page(&old_module.page_uuid);
+    cache.invalidate_page(&updated_module.page_uuid);
 
-    Ok(HttpResponse::Created().json(updated_module.0))
+    Ok(HttpResponse::Created()
+        .set(version_tag(version))
+        .json(updated_module.0))
 }
 
 pub async fn delete_module(
+    req: HttpRequest,
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
+    let expected_version = if_match(&req)?;
 
-    let res = Module::delete(id.clone(), &mysql_pool)?;
+    let (old_module, res) = run_repository(repository, move |repository| {
+        repository.delete_module(&id, expected_version)
+    })
+    .await?;
+
+    cache.invalidate_page(&old_module.page_uuid);
 
     Ok(HttpResponse::Created().json(res))
 }
 
 pub async fn get_module_category(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>
+    repository: web::Data<dyn Repository>
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    let modules = ModuleCategory::join(id.clone(), &mysql_pool)?;
+    let modules = run_repository(repository, move |repository| {
+        repository.read_category_modules(&id)
+    })
+    .await?;
 
-    Ok(HttpResponse::Created().json(modules))
+    Ok(HttpResponse::Ok().json(modules))
 }
\ No newline at end of file
@@ -1,16 +1,22 @@
-use std::sync::Mutex;
-
-use actix_web::{web, HttpResponse};
-use handlebars::Handlebars;
+use actix_web::{http::header, http::StatusCode, web, HttpResponse};
+use serde_json::Value;
 use uuid::Uuid;
 
-use crate::models::{pool_handler, Model, MySQLPool};
+use crate::models::pagination::{ListQuery, Paginated};
 
 use crate::models::module_models::{FieldsDTO};
 use crate::models::page_models::{PageModuleDisplayDTO,MutPage, Page, PageDTO};
+use crate::repositories::{run_repository, Repository};
 
 use crate::services::auth_service::Claims;
+use crate::services::cache_service::{PageCache, RenderedPage};
+use crate::services::config_service::Settings;
 use crate::services::errors_service::CustomHttpError;
+use crate::services::fieldset_service::{retain_fields, sparse_items, to_value, FieldsetQuery};
+use crate::services::http_cache_service::{if_match, last_modified, version_tag};
+use crate::services::pagination_service::paginated_response;
+use crate::services::render_service::{error_page, render_page};
+use crate::services::template_service::Templates;
 
 fn parse_page(page: (Page, FieldsDTO)) -> Result<PageModuleDisplayDTO, CustomHttpError> {
     let origin_page = page.0;
@@ -34,97 +40,277 @@ fn parse_page(page: (Page, FieldsDTO)) -> Result<PageModuleDisplayDTO, CustomHtt
     Ok(res)
 }
 
+/// Relations that can be embedded in a page with `include`.
+/// Modules and categories go under `fields`, the same as `/pages/{id}/modules`, and terms go under `tags`.
+const PAGE_INCLUDES: &[&str] = &["modules", "categories", "tags"];
+
+/// Reads a page with the relations and attributes asked for in `query`.
+fn page_resource(
+    id: String,
+    query: &FieldsetQuery,
+    default_include: &[&str],
+    repository: &dyn Repository,
+) -> Result<Value, CustomHttpError> {
+    let include = query.includes(default_include);
+    let includes = |relation: &str| include.iter().any(|r| r == relation);
+
+    let mut resource = if includes("modules") || includes("categories") {
+        let mut resource = to_value(repository.read_page_with_modules(&id)?)?;
+
+        if let Some(fields) = resource.get_mut("fields").and_then(Value::as_object_mut) {
+            for relation in &["modules", "categories"] {
+                if !includes(relation) {
+                    fields.remove(*relation);
+                }
+            }
+        }
+
+        resource
+    } else {
+        to_value(repository.read_page(&id)?)?
+    };
+
+    if includes("tags") {
+        resource["tags"] = to_value(repository.read_page_tags(&id)?)?;
+    }
+
+    retain_fields(Some(&mut resource), query.fields.as_deref(), &["fields", "tags"]);
+    retain_fields(resource.pointer_mut("/fields/modules"), query.module_fields.as_deref(), &[]);
+    retain_fields(resource.pointer_mut("/fields/categories"), query.category_fields.as_deref(), &["modules"]);
+    if let Some(categories) = resource.pointer_mut("/fields/categories").and_then(Value::as_array_mut) {
+        for category in categories {
+            retain_fields(category.get_mut("modules"), query.module_fields.as_deref(), &[]);
+        }
+    }
+    retain_fields(resource.get_mut("tags"), query.tag_fields.as_deref(), &[]);
+
+    Ok(resource)
+}
+
+fn page_response(page: RenderedPage) -> HttpResponse {
+    let mut res = HttpResponse::Ok();
+    res.content_type("text/html")
+        .set(last_modified(page.last_modified));
+
+    if let Some(cache_control) = page.cache_control {
+        res.header(header::CACHE_CONTROL, cache_control);
+    }
+
+    res.body(page.body)
+}
+
 pub async fn display_page(
     req: web::HttpRequest,
-    pool: web::Data<MySQLPool>,
-    hb: web::Data<Mutex<Handlebars<'_>>>,
+    repository: web::Data<dyn Repository>,
+    templates: web::Data<Templates>,
+    settings: web::Data<Settings>,
+    cache: web::Data<PageCache>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-    let path = req.path();
-    let page_tuple = Page::read_one_join_on_url(path.to_string(), &mysql_pool);
+    let path = req.path().to_string();
+    let conf = settings.current();
 
-    if let Err(_) = page_tuple {
-        let s = hb.lock().unwrap().render("404", &String::from("")).unwrap();
-        return Ok(HttpResponse::Ok().content_type("text/html").body(s));
+    if let Some(page) = cache.get(&path) {
+        return Ok(page_response(page));
     }
 
-    let pagemodule = parse_page(page_tuple?)?;
-
-    let s = hb
-        .lock()
-        .unwrap()
-        .render(&pagemodule.page_name, &pagemodule)
-        .unwrap();
+    // taken before the read, so a change saved while this renders keeps the render out of the cache.
+    let generation = cache.generation();
+
+    let page_path = path.clone();
+    let page_tuple = run_repository(repository.clone(), move |repository| {
+        Ok(repository.find_page_by_url(&page_path))
+    })
+    .await?;
+
+    if let Err(e) = page_tuple {
+        if !matches!(e, CustomHttpError::NotFound) {
+            return Ok(error_page(templates, StatusCode::INTERNAL_SERVER_ERROR, Some(e.to_string()), &conf).await);
+        }
+
+        // only a missing page is checked for a redirect, so a redirect can never hide a live page.
+        let redirect_path = path.clone();
+        let redirect = run_repository(repository, move |repository| {
+            Ok(repository.follow_redirect(&redirect_path))
+        })
+        .await?;
+
+        return Ok(match redirect {
+            Ok(redirect) if redirect.status_code == 410 => {
+                error_page(templates, StatusCode::GONE, None, &conf).await
+            }
+            Ok(redirect) => {
+                let mut location = redirect.target.unwrap_or_default();
+                if !req.query_string().is_empty() && !location.contains('?') {
+                    location = format!("{}?{}", location, req.query_string());
+                }
+
+                let mut res = match redirect.status_code {
+                    301 => HttpResponse::MovedPermanently(),
+                    _ => HttpResponse::Found(),
+                };
+                res.header("Location", location).finish()
+            }
+            Err(_) => error_page(templates, StatusCode::NOT_FOUND, None, &conf).await,
+        });
+    }
 
-    Ok(HttpResponse::Ok().content_type("text/html").body(s))
+    let pagemodule = parse_page(page_tuple?)?;
+    let page_uuid = pagemodule.uuid.clone();
+    let cache_control = pagemodule.cache_control.clone();
+
+    // the page is as new as the most recently changed of itself and its modules.
+    let modified = pagemodule
+        .fields
+        .values()
+        .chain(pagemodule.array_fields.values().flatten())
+        .map(|module| module.time_updated)
+        .fold(pagemodule.time_updated, |latest, time| latest.max(time));
+
+    match render_page(templates.clone(), pagemodule.page_name.clone(), pagemodule).await {
+        Ok((body, listed_pages)) => {
+            let page = RenderedPage {
+                body,
+                last_modified: modified,
+                cache_control,
+            };
+
+            cache.insert(&path, &page_uuid, &listed_pages, &page, generation);
+            Ok(page_response(page))
+        }
+        Err(e) => {
+            log::error!("Failed to render page `{}`: {}", path, e);
+            Ok(error_page(templates, StatusCode::INTERNAL_SERVER_ERROR, Some(e), &conf).await)
+        }
+    }
 }
 
 pub async fn create_page(
     new: web::Json<MutPage>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
     let mut uuid_new = new.clone();
     uuid_new.uuid = Some(Uuid::new_v4().to_string());
 
-    Page::create(&uuid_new, &mysql_pool)?;
+    let page = uuid_new.clone();
+    run_repository(repository, move |repository| repository.create_page(&page)).await?;
 
     Ok(HttpResponse::Ok().json(uuid_new))
 }
 
-pub async fn get_pages(pool: web::Data<MySQLPool>) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-    let pages: Vec<PageDTO> = Page::read_all(&mysql_pool)?;
+pub async fn get_pages(
+    req: web::HttpRequest,
+    query: web::Query<ListQuery>,
+    fieldset: web::Query<FieldsetQuery>,
+    repository: web::Data<dyn Repository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    query.validate(Page::SORT_FIELDS)?;
+
+    let list_query = query.clone();
+    let mut pages = run_repository(repository, move |repository| {
+        repository.list_pages(&list_query)
+    })
+    .await?;
+    let items = sparse_items(std::mem::take(&mut pages.items), fieldset.fields.as_deref())?;
 
-    Ok(HttpResponse::Ok().json(pages))
+    Ok(paginated_response(&req, &query, Paginated {
+        items,
+        total: pages.total,
+        next_cursor: pages.next_cursor,
+    }))
 
 }
 
 pub async fn get_page(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    query: web::Query<FieldsetQuery>,
+    repository: web::Data<dyn Repository>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
+    query.validate(PAGE_INCLUDES)?;
+
+    let fieldset = query.clone();
+    let (page, resource) = run_repository(repository, move |repository| {
+        let page: PageDTO = repository.read_page(&id)?;
+        let resource = page_resource(id.into_inner(), &fieldset, &[], repository)?;
+
+        Ok((page, resource))
+    })
+    .await?;
 
-    let page: PageDTO = Page::read_one(id.clone(), &mysql_pool)?;
-    Ok(HttpResponse::Ok().json(page))
+    let mut res = HttpResponse::Ok();
+    res.set(last_modified(page.time_updated));
+
+    // included relations change without the page's version changing, so those responses are tagged by content instead.
+    if query.includes(&[]).is_empty() {
+        res.set(version_tag(page.version));
+    }
+
+    Ok(res.json(resource))
 
 }
 
 pub async fn get_page_join_modules(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    query: web::Query<FieldsetQuery>,
+    repository: web::Data<dyn Repository>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
+    query.validate(PAGE_INCLUDES)?;
 
-    let page_vec = Page::read_one_join_on(id.clone(), &mysql_pool)?;
+    let page_vec = run_repository(repository, move |repository| {
+        page_resource(id.into_inner(), &query, &["modules", "categories"], repository)
+    })
+    .await?;
 
     Ok(HttpResponse::Ok().json(page_vec))
 }
 
 pub async fn update_page(
+    req: web::HttpRequest,
     updated_page: web::Json<MutPage>,
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
     _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    Page::update(id.clone(), &updated_page, &mysql_pool)?;
+    let expected_version = if_match(&req)?;
+    let page_id = id.into_inner();
+    let page = updated_page.clone();
+
+    let (old_page, version) = run_repository(repository, move |repository| {
+        repository.update_page(&page_id, expected_version, &page)
+    })
+    .await?;
+
+    // a url or title can also be written into other pages' modules and templates, which aren't tracked.
+    if old_page.page_url != updated_page.page_url || old_page.page_title != updated_page.page_title {
+        cache.clear();
+    } else {
+        cache.invalidate_listed_page(&old_page.uuid);
+    }
 
-    Ok(HttpResponse::Ok().json(updated_page.0))
+    Ok(HttpResponse::Ok()
+        .set(version_tag(version))
+        .json(updated_page.0))
 
 }
 
 pub async fn delete_page(
+    req: web::HttpRequest,
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    repository: web::Data<dyn Repository>,
+    cache: web::Data<PageCache>,
+    _: Claims
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    let res = Page::delete(id.clone(), &mysql_pool)?;
+    let expected_version = if_match(&req)?;
+    let page_id = id.into_inner();
+
+    let deleted_id = page_id.clone();
+    let res = run_repository(repository, move |repository| {
+        repository.delete_page(&deleted_id, expected_version)
+    })
+    .await?;
+    // the page may be linked from menus and page listings on other pages.
+    cache.invalidate_listed_page(&page_id);
 
     Ok(HttpResponse::Ok().json(res))
 }
@@ -0,0 +1,100 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::models::redirect_models::MutRedirect;
+use crate::repositories::{run_repository, RedirectRepository};
+use crate::services::auth_service::Claims;
+use crate::services::errors_service::CustomHttpError;
+
+pub async fn create_redirect(
+    new: web::Json<MutRedirect>,
+    redirects: web::Data<dyn RedirectRepository>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.clone();
+    uuid_new.uuid = Some(Uuid::new_v4().to_string());
+
+    let redirect = uuid_new.clone();
+    run_repository(redirects, move |redirects| redirects.create_redirect(&redirect)).await?;
+
+    Ok(HttpResponse::Created().json(uuid_new))
+}
+
+pub async fn get_redirects(redirects: web::Data<dyn RedirectRepository>) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(redirects, |redirects| redirects.list_redirects()).await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn get_redirect(
+    id: web::Path<String>,
+    redirects: web::Data<dyn RedirectRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let redirect = run_repository(redirects, move |redirects| redirects.read_redirect(&id)).await?;
+
+    Ok(HttpResponse::Ok().json(redirect))
+}
+
+pub async fn update_redirect(
+    updated_redirect: web::Json<MutRedirect>,
+    id: web::Path<String>,
+    redirects: web::Data<dyn RedirectRepository>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    // the uuid is the primary key, so it's kept rather than taken from the body.
+    let mut redirect = updated_redirect.into_inner();
+    redirect.uuid = None;
+
+    let updated = redirect.clone();
+    run_repository(redirects, move |redirects| redirects.update_redirect(&id, &updated)).await?;
+
+    Ok(HttpResponse::Ok().json(redirect))
+}
+
+pub async fn delete_redirect(
+    id: web::Path<String>,
+    redirects: web::Data<dyn RedirectRepository>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(redirects, move |redirects| redirects.delete_redirect(&id)).await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+/// A row of a redirect import. Empty cells are `None`, so an empty `status_code` gets the default.
+#[derive(Deserialize)]
+struct ImportRow {
+    source_path: String,
+    target: Option<String>,
+    #[serde(default)]
+    status_code: Option<i32>,
+}
+
+/// Imports redirects from a CSV body with the header `source_path,target,status_code`.
+/// `status_code` defaults to 301. Existing redirects with the same source path are replaced.
+pub async fn import_redirects(
+    body: String,
+    redirects: web::Data<dyn RedirectRepository>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+
+    let mut new_redirects = Vec::new();
+    for row in reader.deserialize::<ImportRow>() {
+        let row = row.map_err(|e| CustomHttpError::Malformed(e.to_string()))?;
+        let redirect = MutRedirect {
+            uuid: Some(Uuid::new_v4().to_string()),
+            source_path: row.source_path,
+            target: row.target,
+            status_code: row.status_code.unwrap_or_else(MutRedirect::default_status),
+        };
+        new_redirects.push(redirect);
+    }
+
+    let res = run_repository(redirects, move |redirects| redirects.import_redirects(&new_redirects)).await?;
+
+    Ok(HttpResponse::Created().json(res))
+}
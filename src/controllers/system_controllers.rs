@@ -0,0 +1,37 @@
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+
+use crate::models::DbPool;
+use crate::services::auth_service::Claims;
+use crate::services::errors_service::CustomHttpError;
+use crate::services::health_service;
+use crate::services::template_service::Templates;
+
+/// Liveness. Answers as long as the server handles requests at all, without touching the database.
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok()
+        .header(header::CACHE_CONTROL, "no-store")
+        .json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness. `503 Service Unavailable` with the checks that failed, until the server can serve pages.
+pub async fn readyz(pool: web::Data<DbPool>, templates: web::Data<Templates>) -> HttpResponse {
+    let readiness = health_service::readiness(pool.get_ref().clone(), &templates).await;
+
+    match readiness.ready {
+        true => HttpResponse::Ok(),
+        false => HttpResponse::ServiceUnavailable(),
+    }
+    .header(header::CACHE_CONTROL, "no-store")
+    .json(readiness)
+}
+
+pub async fn get_status(
+    pool: web::Data<DbPool>,
+    templates: web::Data<Templates>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    Ok(HttpResponse::Ok()
+        .header(header::CACHE_CONTROL, "no-store")
+        .json(health_service::status(&pool, &templates)))
+}
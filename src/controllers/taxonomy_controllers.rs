@@ -0,0 +1,189 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::models::taxonomy_models::{MutTerm, MutVocabulary, PageTerm};
+use crate::repositories::{run_repository, Repository, TaxonomyRepository};
+use crate::services::auth_service::Claims;
+use crate::services::cache_service::PageCache;
+use crate::services::errors_service::CustomHttpError;
+
+pub async fn create_vocabulary(
+    new: web::Json<MutVocabulary>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.clone();
+    uuid_new.uuid = Some(Uuid::new_v4().to_string());
+
+    let vocabulary = uuid_new.clone();
+    run_repository(taxonomy, move |taxonomy| taxonomy.create_vocabulary(&vocabulary)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Created().json(uuid_new))
+}
+
+pub async fn get_vocabularies(taxonomy: web::Data<dyn TaxonomyRepository>) -> Result<HttpResponse, CustomHttpError> {
+    let vocabularies = run_repository(taxonomy, |taxonomy| taxonomy.list_vocabularies()).await?;
+
+    Ok(HttpResponse::Ok().json(vocabularies))
+}
+
+pub async fn get_vocabulary(
+    name: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let vocabulary = run_repository(taxonomy, move |taxonomy| taxonomy.read_vocabulary(&name)).await?;
+
+    Ok(HttpResponse::Ok().json(vocabulary))
+}
+
+pub async fn get_vocabulary_terms(
+    name: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let tree = run_repository(taxonomy, move |taxonomy| taxonomy.read_vocabulary_terms(&name)).await?;
+
+    Ok(HttpResponse::Ok().json(tree))
+}
+
+pub async fn update_vocabulary(
+    updated_vocabulary: web::Json<MutVocabulary>,
+    name: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    // the uuid is the primary key, so it's kept rather than taken from the body.
+    let mut vocabulary = updated_vocabulary.into_inner();
+    vocabulary.uuid = None;
+
+    let updated = vocabulary.clone();
+    run_repository(taxonomy, move |taxonomy| taxonomy.update_vocabulary(&name, &updated)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(vocabulary))
+}
+
+pub async fn delete_vocabulary(
+    name: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(taxonomy, move |taxonomy| taxonomy.delete_vocabulary(&name)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn create_term(
+    new: web::Json<MutTerm>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut uuid_new = new.clone();
+    uuid_new.uuid = Some(Uuid::new_v4().to_string());
+
+    let term = uuid_new.clone();
+    run_repository(taxonomy, move |taxonomy| taxonomy.create_term(&term)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Created().json(uuid_new))
+}
+
+pub async fn get_term(
+    id: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let term = run_repository(taxonomy, move |taxonomy| taxonomy.read_term(&id)).await?;
+
+    Ok(HttpResponse::Ok().json(term))
+}
+
+pub async fn update_term(
+    updated_term: web::Json<MutTerm>,
+    id: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let mut updated = updated_term.into_inner();
+    updated.uuid = None;
+
+    let term = updated.clone();
+    run_repository(taxonomy, move |taxonomy| taxonomy.update_term(&id, &term)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+pub async fn delete_term(
+    id: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let res = run_repository(taxonomy, move |taxonomy| taxonomy.delete_term(&id)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn get_term_pages(
+    id: web::Path<String>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let pages = run_repository(taxonomy, move |taxonomy| taxonomy.read_term_pages(&id)).await?;
+
+    Ok(HttpResponse::Ok().json(pages))
+}
+
+pub async fn get_page_terms(
+    id: web::Path<String>,
+    repository: web::Data<dyn Repository>,
+) -> Result<HttpResponse, CustomHttpError> {
+    let terms = run_repository(repository, move |repository| repository.read_page_tags(&id)).await?;
+
+    Ok(HttpResponse::Ok().json(terms))
+}
+
+pub async fn assign_page_term(
+    path: web::Path<(String, String)>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (page_uuid, term_uuid) = path.into_inner();
+
+    let page_term = PageTerm {
+        page_uuid,
+        term_uuid,
+    };
+
+    let assigned = page_term.clone();
+    run_repository(taxonomy, move |taxonomy| taxonomy.assign_page_term(&assigned)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(page_term))
+}
+
+pub async fn unassign_page_term(
+    path: web::Path<(String, String)>,
+    taxonomy: web::Data<dyn TaxonomyRepository>,
+    cache: web::Data<PageCache>,
+    _: Claims,
+) -> Result<HttpResponse, CustomHttpError> {
+    let (page_uuid, term_uuid) = path.into_inner();
+
+    let page_term = PageTerm {
+        page_uuid,
+        term_uuid,
+    };
+
+    let res = run_repository(taxonomy, move |taxonomy| taxonomy.unassign_page_term(&page_term)).await?;
+    cache.clear();
+
+    Ok(HttpResponse::Ok().json(res))
+}
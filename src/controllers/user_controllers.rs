@@ -4,36 +4,34 @@ use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use time::{Duration, OffsetDateTime};
 use uuid::Uuid;
 
-use crate::models::user_models::{MutUser, User};
-use crate::models::{pool_handler, Model, MySQLPool};
-use crate::services::auth_service::{authenticate, encrypt, encrypt_password, Claims};
+use crate::models::user_models::{MutUser, User, ADMIN};
+use crate::repositories::{run_repository, UserRepository};
+use crate::services::auth_service::{authenticate, encrypt, require_role, Claims};
 use crate::services::errors_service::CustomHttpError;
 
 pub async fn create_user(
     new: web::Json<MutUser>,
-    pool: web::Data<MySQLPool>,
-    _: Claims,
+    users: web::Data<dyn UserRepository>,
+    claim: Claims,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
     let mut salted_user = new.clone();
-    let encrypted_password = encrypt_password(&salted_user.password.unwrap())?;
-    salted_user.password = Some(encrypted_password);
     salted_user.uuid = Some(Uuid::new_v4().to_string());
 
-    User::create(&salted_user, &mysql_pool)?;
+    run_repository(users, move |users| {
+        require_role(&claim, ADMIN, users)?;
+        users.create_user(&salted_user)
+    })
+    .await?;
 
     Ok(HttpResponse::Created().json(&new.clone()))
 }
 
 pub async fn get_user(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
+    users: web::Data<dyn UserRepository>,
     _: Claims,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    let user: User = User::read_one(id.clone(), &mysql_pool)?;
+    let user: User = run_repository(users, move |users| users.read_user(&id)).await?;
 
     Ok(HttpResponse::Ok().json(&user))
 }
@@ -41,11 +39,9 @@ pub async fn get_user(
 pub async fn update_user(
     id: web::Path<String>,
     new: web::Json<MutUser>,
-    pool: web::Data<MySQLPool>,
+    users: web::Data<dyn UserRepository>,
     claim: Claims,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
     // TODO maybe make this only happen whenever the password changes?
     let mut salted_user = new.clone();
 
@@ -55,9 +51,6 @@ pub async fn update_user(
         return Ok(HttpResponse::Unauthorized().finish());
     }
 
-    let encrypted_password = encrypt_password(&salted_user.password.unwrap())?;
-    salted_user.password = Some(encrypted_password);
-
     let exp_time = chrono::Utc::now() + chrono::Duration::days(10);
 
     // give them a new token just in case they update their username.
@@ -76,31 +69,33 @@ pub async fn update_user(
 
     let user = HttpResponse::Ok().cookie(cookie).json(&new.clone());
     salted_user.token = Some(token_enc);
-    User::update(id.clone(), &salted_user, &mysql_pool)?;
+    run_repository(users, move |users| users.update_user(&id, &salted_user)).await?;
 
     Ok(user)
 }
 
 pub async fn delete_user(
     id: web::Path<String>,
-    pool: web::Data<MySQLPool>,
-    _: Claims,
+    users: web::Data<dyn UserRepository>,
+    claim: Claims,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
-
-    let res = User::delete(id.clone(), &mysql_pool)?;
+    let res = run_repository(users, move |users| {
+        require_role(&claim, ADMIN, users)?;
+        users.delete_user(&id)
+    })
+    .await?;
 
     Ok(HttpResponse::Ok().json(res))
 }
 
 pub async fn login(
     user: web::Json<MutUser>,
-    pool: web::Data<MySQLPool>,
+    users: web::Data<dyn UserRepository>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
     let arg = Argon2::default();
 
-    let read_user = User::read_one(user.username.clone(), &mysql_pool)?;
+    let username = user.username.clone();
+    let read_user = run_repository(users.clone(), move |users| users.read_user(&username)).await?;
 
     let is_default = read_user.username == "root" && read_user.password == "";
 
@@ -118,7 +113,7 @@ pub async fn login(
 
         new_user.token = Some(cookie.value().to_string());
 
-        User::update_with_token(&new_user, &mysql_pool)?;
+        run_repository(users, move |users| users.set_token(&new_user)).await?;
 
         return Ok(cookie_response);
     }
@@ -136,7 +131,8 @@ pub async fn login(
 
             new_user.token = Some(cookie.value().to_string());
 
-            User::update_with_token(&new_user, &mysql_pool)?;
+            let token_user = new_user.into_inner();
+            run_repository(users, move |users| users.set_token(&token_user)).await?;
 
             Ok(cookie_response)
         }
@@ -172,12 +168,11 @@ pub async fn logout() -> Result<HttpResponse, CustomHttpError> {
 
 pub async fn check_login(
     req: HttpRequest,
-    pool: web::Data<MySQLPool>,
+    users: web::Data<dyn UserRepository>,
 ) -> Result<HttpResponse, CustomHttpError> {
-    let mysql_pool = pool_handler(pool)?;
     let auth_header = req.headers().get("authorization");
 
-    let auth_res = authenticate(auth_header.unwrap(), &mysql_pool).await;
+    let auth_res = authenticate(auth_header.unwrap().clone(), users).await;
 
     match auth_res {
         Ok(_) => Ok(HttpResponse::Ok().finish()),
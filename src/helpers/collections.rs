@@ -0,0 +1,76 @@
+use handlebars::{
+    to_json, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+
+use crate::models::collection_models::{Collection, CollectionEntry, EntryQuery};
+use crate::models::{Model, DbPool};
+
+/// Hash parameters that are options rather than content filters.
+const RESERVED_PARAMS: [&str; 3] = ["status", "sort", "limit"];
+
+/// Returns the entries of a collection so they can be looped over with `#each`.
+///
+/// `{{#each (entries "blog" status="published" sort="-time_created" limit=5)}}`
+///
+/// Any other hash parameter filters on the entry's content, e.g. `featured=true`.
+/// Like `getarray`, an unknown collection renders as an empty array.
+pub struct EntriesHelper {
+    pool: DbPool,
+}
+
+impl HelperDef for EntriesHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<Option<ScopedJson<'reg, 'rc>>, RenderError> {
+        let collection_name = h
+            .param(0)
+            .ok_or(RenderError::new(
+                "No collection name provided to helper function.",
+            ))?
+            .render();
+
+        let mut query = EntryQuery {
+            status: h.hash_get("status").map(|s| s.render()),
+            sort: h.hash_get("sort").map(|s| s.render()),
+            limit: h
+                .hash_get("limit")
+                .and_then(|l| l.value().as_u64())
+                .map(|l| l as usize),
+            ..EntryQuery::default()
+        };
+
+        for (key, value) in h.hash() {
+            if !RESERVED_PARAMS.contains(key) {
+                query.filters.insert(key.to_string(), value.value().clone());
+            }
+        }
+
+        let entries = (|| -> Result<Vec<CollectionEntry>, RenderError> {
+            let db = self.pool.get().or(Err(RenderError::new(
+                "Unable to get a database connection.",
+            )))?;
+            let collection = Collection::read_one(collection_name.clone(), &db).or(Err(
+                RenderError::new(format!("Collection `{}` does not exist.", collection_name)),
+            ))?;
+
+            CollectionEntry::query(&collection, &query, &db)
+                .map(|entries| entries.items)
+                .or(Err(RenderError::new("Unable to read collection entries.")))
+        })();
+
+        let empty_array: Vec<Value> = Vec::new();
+        Ok(Some(match entries {
+            Ok(entries) => to_json(entries).into(),
+            Err(_) => to_json(empty_array).into(),
+        }))
+    }
+}
+
+pub fn register_helpers(handlebars: &mut Handlebars<'_>, pool: DbPool) {
+    handlebars.register_helper("entries", Box::new(EntriesHelper { pool }));
+}
@@ -1,9 +1,7 @@
-use actix_web::web::Data;
 use handlebars::{
     to_json, Context, Handlebars, Helper, HelperDef, JsonRender, Output, RenderContext,
     RenderError, ScopedJson,
 };
-use std::sync::Mutex;
 
 fn get(
     h: &Helper,
@@ -85,13 +83,7 @@ impl HelperDef for ArrayHelper {
 
 pub static ARRAY_HELPER: ArrayHelper = ArrayHelper;
 
-pub fn register_helpers(handlebars: Data<Mutex<Handlebars<'_>>>) {
-    handlebars
-        .lock()
-        .unwrap()
-        .register_helper("get", Box::new(get));
-    handlebars
-        .lock()
-        .unwrap()
-        .register_helper("getarray", Box::new(ARRAY_HELPER));
+pub fn register_helpers(handlebars: &mut Handlebars<'_>) {
+    handlebars.register_helper("get", Box::new(get));
+    handlebars.register_helper("getarray", Box::new(ARRAY_HELPER));
 }
@@ -0,0 +1,71 @@
+use handlebars::{
+    to_json, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+
+use crate::models::menu_models::{Menu, MenuItemDTO};
+use crate::models::{Model, DbPool};
+use crate::services::render_service::listed_page;
+
+/// Returns the items of a menu as a tree, marking the item for the current `page_url` as `active`.
+///
+/// `{{#each (menu "main")}}<a href="{{this.url}}" {{#if this.active}}class="active"{{/if}}>{{this.title}}</a>{{/each}}`
+///
+/// Nested items are under `children`. Like `getarray`, an unknown menu renders as an empty array.
+pub struct MenuHelper {
+    pool: DbPool,
+}
+
+impl HelperDef for MenuHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<Option<ScopedJson<'reg, 'rc>>, RenderError> {
+        let menu_name = h
+            .param(0)
+            .ok_or(RenderError::new(
+                "No menu name provided to helper function.",
+            ))?
+            .render();
+
+        let current_url = ctx.data().get("page_url").and_then(|u| u.as_str());
+
+        let items = (|| -> Result<Vec<MenuItemDTO>, RenderError> {
+            let db = self.pool.get().or(Err(RenderError::new(
+                "Unable to get a database connection.",
+            )))?;
+            let menu = Menu::read_one(menu_name.clone(), &db).or(Err(RenderError::new(
+                format!("Menu `{}` does not exist.", menu_name),
+            )))?;
+
+            let tree = menu
+                .read_tree(current_url, &db)
+                .or(Err(RenderError::new("Unable to read menu items.")))?;
+
+            list_pages(&tree.items);
+            Ok(tree.items)
+        })();
+
+        let empty_array: Vec<Value> = Vec::new();
+        Ok(Some(match items {
+            Ok(items) => to_json(items).into(),
+            Err(_) => to_json(empty_array).into(),
+        }))
+    }
+}
+
+fn list_pages(items: &[MenuItemDTO]) {
+    for item in items {
+        if let Some(page_uuid) = &item.page_uuid {
+            listed_page(page_uuid);
+        }
+        list_pages(&item.children);
+    }
+}
+
+pub fn register_helpers(handlebars: &mut Handlebars<'_>, pool: DbPool) {
+    handlebars.register_helper("menu", Box::new(MenuHelper { pool }));
+}
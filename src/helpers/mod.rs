@@ -1,2 +1,5 @@
 // These are the helpers for Handlebars.
-pub mod default;
\ No newline at end of file
+pub mod collections;
+pub mod default;
+pub mod menus;
+pub mod taxonomy;
\ No newline at end of file
@@ -0,0 +1,70 @@
+use handlebars::{
+    to_json, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+
+use crate::models::page_models::PageDTO;
+use crate::models::taxonomy_models::{Term, Vocabulary};
+use crate::models::{Model, DbPool};
+use crate::services::render_service::listed_page;
+
+/// Returns every page tagged with a term, or with any term below it.
+///
+/// `{{#each (pages_by_term "topics" "rust")}}<a href="{{this.page_url}}">{{this.page_title}}</a>{{/each}}`
+///
+/// Like `getarray`, an unknown vocabulary or term renders as an empty array.
+pub struct PagesByTermHelper {
+    pool: DbPool,
+}
+
+impl HelperDef for PagesByTermHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<Option<ScopedJson<'reg, 'rc>>, RenderError> {
+        let vocabulary_name = h
+            .param(0)
+            .ok_or(RenderError::new(
+                "No vocabulary name provided to helper function.",
+            ))?
+            .render();
+        let term_slug = h
+            .param(1)
+            .ok_or(RenderError::new("No term provided to helper function."))?
+            .render();
+
+        let pages = (|| -> Result<Vec<PageDTO>, RenderError> {
+            let db = self.pool.get().or(Err(RenderError::new(
+                "Unable to get a database connection.",
+            )))?;
+            let vocabulary = Vocabulary::read_one(vocabulary_name.clone(), &db).or(Err(
+                RenderError::new(format!("Vocabulary `{}` does not exist.", vocabulary_name)),
+            ))?;
+            let term = Term::read_one_by_slug(&vocabulary, term_slug.clone(), &db).or(Err(
+                RenderError::new(format!("Term `{}` does not exist.", term_slug)),
+            ))?;
+
+            let pages = term
+                .read_pages(&db)
+                .or(Err(RenderError::new("Unable to read pages for term.")))?;
+
+            for page in &pages {
+                listed_page(&page.uuid);
+            }
+            Ok(pages)
+        })();
+
+        let empty_array: Vec<Value> = Vec::new();
+        Ok(Some(match pages {
+            Ok(pages) => to_json(pages).into(),
+            Err(_) => to_json(empty_array).into(),
+        }))
+    }
+}
+
+pub fn register_helpers(handlebars: &mut Handlebars<'_>, pool: DbPool) {
+    handlebars.register_helper("pages_by_term", Box::new(PagesByTermHelper { pool }));
+}
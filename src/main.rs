@@ -1,82 +1,156 @@
-use actix_cors::Cors;
-use actix_ratelimit::{MemoryStore, MemoryStoreActor, RateLimiter};
-use actix_web::middleware::Logger;
-use actix_web::{web, App, HttpServer};
-use diesel::mysql::MysqlConnection;
-use diesel::{Connection};
-use handlebars::Handlebars;
-use std::sync::Mutex;
-use std::time::Duration;
-use envy;
+use actix_web::{web, HttpServer};
+use std::path::PathBuf;
 use dotenv::dotenv;
-use diesel_migrations::{run_pending_migrations};
-
-use actix_files as fs;
+use structopt::StructOpt;
 
+#[macro_use]
+mod app;
+mod cli;
 mod controllers;
 mod helpers;
 mod services;
 mod models;
+mod repositories;
 mod routers;
 mod schema;
 mod watch;
+#[cfg(all(test, feature = "sqlite"))]
+mod tests;
 
-use routers::module_routers::ModuleRouter;
-use routers::page_routers::PageRouter;
-
+use cli::{Cli, Command, ServeArgs};
 use models::config_models::LocalConfig;
-use routers::category_routers::CategoryRouter;
-
-use crate::routers::Router;
-use crate::routers::user_routers::UserRouter;
+use services::cache_service::PageCache;
+use services::config_service::{self, Settings};
+use services::{health_service, rate_limit_service};
+use services::{migration_service, seed_service};
+use services::template_service::Templates;
+use repositories::Repositories;
 
 #[macro_use]
 extern crate diesel;
-#[macro_use]
-extern crate diesel_migrations;
 
 /// The main function is replaced by actix_web::main.
 /// This allows main to be async and register the HttpServer.
-/// All routes are defined here.
+/// Commands other than `serve` are in `cli`.
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    embed_migrations!();
+    let args = Cli::from_args();
 
-    // if the program is running in release mode
+    // a `.env` is only read in debug builds, and only if there is one.
     if cfg!(debug_assertions) {
-        dotenv().unwrap();
+        dotenv().ok();
     }
 
-    let conf = envy::prefixed("APP_").from_env::<LocalConfig>().unwrap();
-    let pool = models::establish_database_connection(conf.clone()).unwrap();
+    // `--config` is read before `.env` is, so a file named by `APP_CONFIG` in `.env` is read here.
+    let config = args.config.or_else(|| std::env::var_os("APP_CONFIG").map(PathBuf::from));
 
-    match run_pending_migrations(&MysqlConnection::establish(&models::format_connection_string(conf.clone())).unwrap()) {
-        Ok(_) => println!("Ran migrations."),
-        Err(_) => println!("Migrations not ran.")
+    let conf = match config_service::load(config.as_deref()) {
+        Ok(conf) => conf,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     };
 
-    std::env::set_var("RUST_LOG", "actix_web=info");
-    env_logger::init();
+    match args.command {
+        None => serve(conf, config, args.serve).await,
+        Some(Command::Serve(serve_args)) => serve(conf, config, serve_args).await,
+        Some(command) => {
+            if let Err(e) = cli::run(command, &conf).await {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            Ok(())
+        }
+    }
+}
 
-    let handlebars = Handlebars::new();
+/// Starts the server. All routes are defined in `app!`.
+/// `config` is the file the config was read from, which is read again on `SIGHUP`.
+async fn serve(conf: LocalConfig, config: Option<PathBuf>, args: ServeArgs) -> std::io::Result<()> {
+    health_service::mark_started();
 
-    // web::Data is Arc, so we can safely clone it and send it between our watcher and the server.
-    let handlebars_ref = web::Data::new(Mutex::new(handlebars));
-    let hb = handlebars_ref.clone();
+    if let Err(e) = config_service::validate_for_serving(&conf) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
-    hb.lock()
-        .unwrap()
-        .register_templates_directory(".hbs", "./templates")
-        .unwrap();
+    // tokens are signed with the key in the environment, so a key from the config file is put there.
+    std::env::set_var("APP_JWT_KEY", &conf.jwt_key);
 
-    // Registers all default handlebars functions.
-    helpers::default::register_helpers(handlebars_ref.clone());
+    let pool = match models::establish_database_connection(conf.clone()) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // run on a pooled connection, since an in-memory database is only seen by the pool's own connections.
+    let db = match pool.get() {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("Failed to connect to the database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if !args.no_migrate {
+        match migration_service::run(&db) {
+            Ok(_) => println!("Ran migrations."),
+            Err(_) => println!("Migrations not ran.")
+        };
+    }
+
+    // `--seed root,demo` loads fixture sets the same as `APP_SEED`, and takes its place.
+    if let Some(seed) = args.seed.or_else(|| conf.seed.clone()) {
+        match seed_service::seed(&seed_service::fixture_names(&seed), &db) {
+            Ok(report) => println!("Seeded {}: {}.", seed, report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    drop(db);
+
+    // actix reads the blocking pool size from the environment when the pool is first used.
+    if let Some(blocking_threads) = conf.blocking_threads {
+        std::env::set_var("ACTIX_THREADPOOL", blocking_threads.to_string());
+    }
+
+    std::env::set_var("RUST_LOG", "actix_web=info");
+    env_logger::init();
+
+    // Compiled templates and helpers. Renders share the current registry and the watcher swaps in a new one on change.
+    let templates = match Templates::load(pool.clone()) {
+        Ok(templates) => web::Data::new(templates),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let watcher_templates = templates.clone();
+
+    // Rendered pages, shared between the server and the watcher so template changes clear it.
+    let page_cache = web::Data::new(PageCache::from_config(&conf));
+    let watcher_cache = page_cache.clone();
 
     // Registers the fs watcher that updates the templates in memory every time a template is changed.
     // This is what enables hot reload.
-    std::thread::spawn(|| watch::watch(hb));
+    std::thread::spawn(|| watch::watch(watcher_templates, watcher_cache));
+
+    // the controllers read and write through these, rather than through the pool directly.
+    let repositories = Repositories::diesel(pool.clone());
+
+    // requests counted by client, shared by every worker, and by every server with `rate_limit_store = "database"`.
+    let rate_limits = rate_limit_service::store(&conf, &pool);
+
+    let settings = web::Data::new(Settings::new(conf.clone(), config));
 
-    let store = MemoryStore::new();
+    #[cfg(unix)]
+    actix_web::rt::spawn(reload_on_hangup(settings.clone(), page_cache.clone()));
 
     let server_url = &format!(
         "{}:{}",
@@ -84,35 +158,50 @@ async fn main() -> std::io::Result<()> {
         &conf.bind_port
     );
 
-    let http_server = HttpServer::new(move || {
-        let cors = Cors::permissive();
-
-        let api_scope = web::scope("/v1")
-            .service(UserRouter::new())
-            .service(PageRouter::new())
-            .service(ModuleRouter::new())
-            .service(CategoryRouter::new());
-
-        let rate_limiting = RateLimiter::new(
-            MemoryStoreActor::from(store.clone()).start())
-                .with_interval(Duration::from_secs(60))
-                .with_max_requests(usize::from(conf.max_req));
-
-        App::new()
-            .wrap(cors)
-            .wrap(Logger::new("%a -> %U | %Dms "))
-            .wrap(rate_limiting)
-            .service(api_scope)
-            .service(fs::Files::new("/assets", "./templates/assets").show_files_listing())
-            .default_service(web::get().to(controllers::page_controllers::display_page))
-            .data(pool.clone())
-            .app_data(handlebars_ref.clone())
+    let workers = conf.workers;
+
+    let mut http_server = HttpServer::new(move || {
+        app!(settings, pool, templates, page_cache, repositories, rate_limits)
     })
-    .bind(server_url)?
-    .workers(2)
-    .run();
+    .bind(server_url)?;
+
+    // defaults to the number of logical cpus.
+    if let Some(workers) = workers {
+        http_server = http_server.workers(workers);
+    }
+
+    let http_server = http_server.run();
 
     println!("🚀 Server is running 🚀");
 
     http_server.await
 }
+
+/// Reloads the config on `SIGHUP`. The settings in `config_service::RELOADABLE` apply to the next request,
+/// and the rest wait for a restart. A config that doesn't load leaves the current one in place.
+#[cfg(unix)]
+async fn reload_on_hangup(settings: web::Data<Settings>, page_cache: web::Data<PageCache>) {
+    use actix_web::rt::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            eprintln!("Config reload on SIGHUP isn't available: {}", e);
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        match settings.reload() {
+            Ok(needs_restart) => {
+                page_cache.configure(&settings.current());
+                println!("Reloaded the config.");
+
+                if !needs_restart.is_empty() {
+                    println!("Restart the server to apply the change to {}.", needs_restart.join(", "));
+                }
+            }
+            Err(e) => eprintln!("Config not reloaded, keeping the current one: {}", e),
+        }
+    }
+}
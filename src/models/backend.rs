@@ -0,0 +1,325 @@
+//! The databases the server can talk to, each compiled in with its feature: `mysql` (default), `postgres` and `sqlite`.
+//! Which one it talks to is picked at startup by the scheme of `database_url`.
+//! Everything else uses `DbConnection`, which holds a connection to any of them,
+//! and runs queries with this module's `RunQueryDsl`, so a query has to work on every database the server is built with.
+
+use diesel::connection::{SimpleConnection, TransactionManager};
+use diesel::dsl::Limit;
+use diesel::query_dsl::methods::{ExecuteDsl, LimitDsl};
+use diesel::query_dsl::LoadQuery;
+use diesel::r2d2::{Error, ManageConnection};
+use diesel::result::{ConnectionError, ConnectionResult, QueryResult};
+use diesel::Connection;
+
+#[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
+compile_error!("At least one database has to be enabled with the `mysql`, `postgres` or `sqlite` feature.");
+
+/// A database the server is built with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    #[cfg(feature = "mysql")]
+    Mysql,
+    #[cfg(feature = "postgres")]
+    Postgres,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl Backend {
+    /// Every database this server was built with.
+    pub const ALL: &'static [Backend] = &[
+        #[cfg(feature = "mysql")]
+        Backend::Mysql,
+        #[cfg(feature = "postgres")]
+        Backend::Postgres,
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite,
+    ];
+
+    /// The database `db_url` points at, if the server was built with it.
+    pub fn for_url(db_url: &str) -> Option<Backend> {
+        Self::ALL.iter().copied().find(|backend| backend.accepts_url(db_url))
+    }
+
+    /// Used in messages about the configured database.
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql => "MySQL",
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => "Postgres",
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => "SQLite",
+        }
+    }
+
+    /// The schemes a url for this database can start with.
+    /// SQLite takes a file path, `:memory:` or a `file:` uri rather than a url with a scheme.
+    pub fn url_schemes(self) -> &'static [&'static str] {
+        match self {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql => &["mysql://"],
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => &["postgres://", "postgresql://"],
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => &[],
+        }
+    }
+
+    /// Whether `db_url` points at this kind of database.
+    pub fn accepts_url(self, db_url: &str) -> bool {
+        let schemes = self.url_schemes();
+        match schemes.is_empty() {
+            true => !db_url.contains("://"),
+            false => schemes.iter().any(|scheme| db_url.starts_with(scheme)),
+        }
+    }
+
+    /// Whether a database error says the table queried doesn't exist.
+    pub fn is_missing_table(self, message: &str) -> bool {
+        let missing = match self {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql => "doesn't exist",
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => "does not exist",
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => "no such table",
+        };
+
+        message.contains(missing)
+    }
+
+    /// Limits how long a statement on a connection may run, in milliseconds.
+    /// SQLite can't stop a running statement, so there it's how long to wait on a locked database instead.
+    pub fn query_timeout_statement(self, milliseconds: u64) -> String {
+        match self {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql => format!("SET SESSION MAX_EXECUTION_TIME = {}", milliseconds),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => format!("SET statement_timeout = {}", milliseconds),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => format!("PRAGMA busy_timeout = {}", milliseconds),
+        }
+    }
+
+    /// Run on every new connection before the pool hands it out.
+    /// SQLite leaves foreign keys off unless each connection turns them on, and the cascading deletes rely on them.
+    /// It also fails straight away on a locked database unless it's told to wait.
+    pub fn connection_setup(self) -> Option<&'static str> {
+        match self {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql => None,
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => None,
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => Some("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;"),
+        }
+    }
+
+    /// Whether the database lives inside the server rather than behind a database server.
+    pub fn is_embedded(self) -> bool {
+        match self {
+            #[cfg(feature = "mysql")]
+            Backend::Mysql => false,
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => false,
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => true,
+        }
+    }
+}
+
+/// A connection to one of the databases the server is built with.
+pub enum DbConnection {
+    #[cfg(feature = "mysql")]
+    Mysql(diesel::MysqlConnection),
+    #[cfg(feature = "postgres")]
+    Postgres(diesel::PgConnection),
+    #[cfg(feature = "sqlite")]
+    Sqlite(diesel::SqliteConnection),
+}
+
+/// Runs `$body` with `$conn` bound to the connection `$db` holds, whichever database it's to.
+/// The body is compiled once for each database, so it can use diesel's own `RunQueryDsl` and boxed queries.
+macro_rules! on_connection {
+    ($db:expr, |$conn:ident| $body:expr) => {{
+        let db: &$crate::models::backend::DbConnection = $db;
+        match db {
+            #[cfg(feature = "mysql")]
+            $crate::models::backend::DbConnection::Mysql($conn) => $body,
+            #[cfg(feature = "postgres")]
+            $crate::models::backend::DbConnection::Postgres($conn) => $body,
+            #[cfg(feature = "sqlite")]
+            $crate::models::backend::DbConnection::Sqlite($conn) => $body,
+        }
+    }};
+}
+
+pub(crate) use on_connection;
+
+impl DbConnection {
+    /// Connects to the database at `db_url`, picking which kind it is by the url.
+    pub fn establish(db_url: &str) -> ConnectionResult<Self> {
+        match Backend::for_url(db_url) {
+            #[cfg(feature = "mysql")]
+            Some(Backend::Mysql) => diesel::MysqlConnection::establish(db_url).map(DbConnection::Mysql),
+            #[cfg(feature = "postgres")]
+            Some(Backend::Postgres) => diesel::PgConnection::establish(db_url).map(DbConnection::Postgres),
+            #[cfg(feature = "sqlite")]
+            Some(Backend::Sqlite) => diesel::SqliteConnection::establish(db_url).map(DbConnection::Sqlite),
+            None => Err(ConnectionError::InvalidConnectionUrl(
+                "the url isn't for any of the databases this server was built with".to_string(),
+            )),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            #[cfg(feature = "mysql")]
+            DbConnection::Mysql(_) => Backend::Mysql,
+            #[cfg(feature = "postgres")]
+            DbConnection::Postgres(_) => Backend::Postgres,
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(_) => Backend::Sqlite,
+        }
+    }
+
+    /// Runs `f` in a transaction, which is committed if it returns `Ok`, as diesel's `Connection::transaction` does.
+    pub fn transaction<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<diesel::result::Error>,
+    {
+        on_connection!(self, |conn| conn.transaction(f))
+    }
+
+    /// Opens a transaction, or a savepoint inside the one already open, that stays open until it's committed or rolled back.
+    pub fn begin_transaction(&self) -> QueryResult<()> {
+        on_connection!(self, |conn| conn.transaction_manager().begin_transaction(conn))
+    }
+
+    pub fn commit_transaction(&self) -> QueryResult<()> {
+        on_connection!(self, |conn| conn.transaction_manager().commit_transaction(conn))
+    }
+
+    pub fn rollback_transaction(&self) -> QueryResult<()> {
+        on_connection!(self, |conn| conn.transaction_manager().rollback_transaction(conn))
+    }
+}
+
+impl SimpleConnection for DbConnection {
+    fn batch_execute(&self, query: &str) -> QueryResult<()> {
+        on_connection!(self, |conn| conn.batch_execute(query))
+    }
+}
+
+/// Opens the pool's connections to the database at `db_url`, like diesel's `ConnectionManager` does for one kind of database.
+#[derive(Debug)]
+pub struct DbConnectionManager {
+    db_url: String,
+}
+
+impl DbConnectionManager {
+    pub fn new(db_url: &str) -> Self {
+        DbConnectionManager {
+            db_url: db_url.to_string(),
+        }
+    }
+}
+
+impl ManageConnection for DbConnectionManager {
+    type Connection = DbConnection;
+    type Error = Error;
+
+    fn connect(&self) -> Result<DbConnection, Error> {
+        DbConnection::establish(&self.db_url).map_err(Error::ConnectionError)
+    }
+
+    fn is_valid(&self, conn: &mut DbConnection) -> Result<(), Error> {
+        conn.batch_execute("SELECT 1").map_err(Error::QueryError)
+    }
+
+    fn has_broken(&self, _conn: &mut DbConnection) -> bool {
+        false
+    }
+}
+
+/// Generates the bounds `RunQueryDsl` puts on a query for one database.
+/// Without that database compiled in, every query meets them.
+macro_rules! query_bounds {
+    ($feature:literal, $connection:ty, $execute:ident, $load:ident) => {
+        #[cfg(feature = $feature)]
+        pub trait $execute: ExecuteDsl<$connection> {}
+        #[cfg(feature = $feature)]
+        impl<T: ExecuteDsl<$connection>> $execute for T {}
+        #[cfg(not(feature = $feature))]
+        pub trait $execute {}
+        #[cfg(not(feature = $feature))]
+        impl<T> $execute for T {}
+
+        #[cfg(feature = $feature)]
+        pub trait $load<U>: LoadQuery<$connection, U> {}
+        #[cfg(feature = $feature)]
+        impl<T: LoadQuery<$connection, U>, U> $load<U> for T {}
+        #[cfg(not(feature = $feature))]
+        pub trait $load<U> {}
+        #[cfg(not(feature = $feature))]
+        impl<T, U> $load<U> for T {}
+    };
+}
+
+query_bounds!("mysql", diesel::MysqlConnection, MysqlExecute, MysqlLoad);
+query_bounds!("postgres", diesel::PgConnection, PgExecute, PgLoad);
+query_bounds!("sqlite", diesel::SqliteConnection, SqliteExecute, SqliteLoad);
+
+/// A statement that runs on every database the server is built with.
+pub trait Execute: MysqlExecute + PgExecute + SqliteExecute {}
+impl<T: MysqlExecute + PgExecute + SqliteExecute> Execute for T {}
+
+/// A query that loads `U`s on every database the server is built with.
+pub trait Load<U>: MysqlLoad<U> + PgLoad<U> + SqliteLoad<U> {}
+impl<T: MysqlLoad<U> + PgLoad<U> + SqliteLoad<U>, U> Load<U> for T {}
+
+/// Diesel's `RunQueryDsl`, for a `DbConnection` rather than one kind of connection.
+/// Modules import it by name, which hides the one `diesel::prelude::*` brings in, so queries read the same as with diesel's.
+pub trait RunQueryDsl: Sized {
+    fn execute(self, db: &DbConnection) -> QueryResult<usize>
+    where
+        Self: Execute,
+    {
+        on_connection!(db, |conn| ExecuteDsl::execute(self, conn))
+    }
+
+    fn load<U>(self, db: &DbConnection) -> QueryResult<Vec<U>>
+    where
+        Self: Load<U>,
+    {
+        load(self, db)
+    }
+
+    fn get_result<U>(self, db: &DbConnection) -> QueryResult<U>
+    where
+        Self: Load<U>,
+    {
+        first_or_not_found(load(self, db))
+    }
+
+    fn first<U>(self, db: &DbConnection) -> QueryResult<U>
+    where
+        Self: LimitDsl,
+        Limit<Self>: Load<U>,
+    {
+        first_or_not_found(load(LimitDsl::limit(self, 1), db))
+    }
+}
+
+// the types diesel runs queries from, which it implements its `RunQueryDsl` for on any connection.
+impl<T: diesel::RunQueryDsl<DbConnection>> RunQueryDsl for T {}
+
+fn load<T: Load<U>, U>(query: T, db: &DbConnection) -> QueryResult<Vec<U>> {
+    on_connection!(db, |conn| LoadQuery::internal_load(query, conn))
+}
+
+fn first_or_not_found<U>(rows: QueryResult<Vec<U>>) -> QueryResult<U> {
+    rows?.into_iter().next().ok_or(diesel::result::Error::NotFound)
+}
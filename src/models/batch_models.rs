@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use super::module_models::{MutCategory, MutModule};
+use super::page_models::MutPage;
+use crate::services::errors_service::{CustomHttpError, FieldError, Problem};
+
+/// Starts a value that stands for the uuid of an earlier operation in the same batch, such as `$ref:0`.
+const REF_PREFIX: &str = "$ref:";
+
+/// One write in a batch, tagged by the kind of resource it changes.
+///
+/// ```json
+/// { "resource": "module", "op": "update", "id": "...", "version": 3, "data": { ... } }
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(tag = "resource", rename_all = "snake_case")]
+pub enum BatchOperation {
+    Page(BatchAction<MutPage>),
+    Module(BatchAction<MutModule>),
+    Category(BatchAction<MutCategory>),
+}
+
+/// `version` is the `ETag` the resource was read with. Like `If-Match`, it's required on updates and deletes.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchAction<T> {
+    Create {
+        data: T,
+    },
+    Update {
+        id: String,
+        version: Option<i32>,
+        data: T,
+    },
+    Delete {
+        id: String,
+        version: Option<i32>,
+    },
+}
+
+/// Fields that can hold the uuid of another row, and so a `$ref` to a row created earlier in the batch.
+pub trait References: Sized {
+    fn resolve(self, results: &[BatchResult]) -> Result<Self, CustomHttpError>;
+}
+
+impl References for MutPage {
+    fn resolve(self, _: &[BatchResult]) -> Result<Self, CustomHttpError> {
+        Ok(self)
+    }
+}
+
+impl References for MutModule {
+    fn resolve(mut self, results: &[BatchResult]) -> Result<Self, CustomHttpError> {
+        self.page_uuid = resolve_ref("data.page_uuid", self.page_uuid, results)?;
+        self.category_uuid = match self.category_uuid {
+            Some(category_uuid) => Some(resolve_ref("data.category_uuid", category_uuid, results)?),
+            None => None,
+        };
+
+        Ok(self)
+    }
+}
+
+impl References for MutCategory {
+    fn resolve(mut self, results: &[BatchResult]) -> Result<Self, CustomHttpError> {
+        self.page_uuid = resolve_ref("data.page_uuid", self.page_uuid, results)?;
+
+        Ok(self)
+    }
+}
+
+impl BatchOperation {
+    /// Replaces every `$ref:<index>` with the uuid of that operation, from the results of the ones applied so far.
+    pub fn resolve(&self, results: &[BatchResult]) -> Result<Self, CustomHttpError> {
+        Ok(match self.clone() {
+            BatchOperation::Page(action) => BatchOperation::Page(action.resolve(results)?),
+            BatchOperation::Module(action) => BatchOperation::Module(action.resolve(results)?),
+            BatchOperation::Category(action) => BatchOperation::Category(action.resolve(results)?),
+        })
+    }
+}
+
+impl<T: References> BatchAction<T> {
+    fn resolve(self, results: &[BatchResult]) -> Result<Self, CustomHttpError> {
+        Ok(match self {
+            BatchAction::Create { data } => BatchAction::Create {
+                data: data.resolve(results)?,
+            },
+            BatchAction::Update { id, version, data } => BatchAction::Update {
+                id: resolve_ref("id", id, results)?,
+                version,
+                data: data.resolve(results)?,
+            },
+            BatchAction::Delete { id, version } => BatchAction::Delete {
+                id: resolve_ref("id", id, results)?,
+                version,
+            },
+        })
+    }
+}
+
+/// The uuid `value` refers to, or `value` itself if it isn't a `$ref`.
+fn resolve_ref(field: &str, value: String, results: &[BatchResult]) -> Result<String, CustomHttpError> {
+    let index = match value.strip_prefix(REF_PREFIX) {
+        Some(index) => index,
+        None => return Ok(value),
+    };
+
+    match index.parse::<usize>().ok().and_then(|index| results.get(index)) {
+        Some(result) => Ok(result.uuid.clone()),
+        None => Err(CustomHttpError::Invalid(vec![FieldError::new(
+            field,
+            "invalid_ref",
+            format!("`{}` isn't the position of an earlier operation.", value),
+        )])),
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BatchRequest {
+    pub operations: Vec<BatchOperation>,
+}
+
+/// The outcome of one operation, in the same position as the operation in the request.
+#[derive(Serialize, Debug)]
+pub struct BatchResult {
+    pub status: u16,
+    pub uuid: String,
+    /// The new version after a create or update, to use as the next `If-Match`.
+    pub version: Option<i32>,
+}
+
+/// Sent back instead of the results when an operation fails and the batch is rolled back.
+/// It's the failed operation's problem document with its position added.
+#[derive(Serialize, Debug)]
+pub struct BatchFailure {
+    #[serde(flatten)]
+    pub problem: Problem,
+    /// Position of the failed operation in the request.
+    pub operation: usize,
+}
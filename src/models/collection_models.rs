@@ -0,0 +1,523 @@
+use chrono::NaiveDateTime;
+use diesel::backend::Backend;
+use diesel::prelude::*;
+use diesel::serialize::ToSql;
+use diesel::sql_types::{HasSqlType, Text};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use validator::Validate;
+
+use super::pagination::{encode_cursor, format_time, parse_time, ListQuery, Paginated};
+use super::backend::on_connection;
+use super::{json_text, DbConnection, Model, RunQueryDsl};
+use crate::services::errors_service::FieldError;
+use crate::services::validation_service::{validate_collection_fields, validate_slug};
+use crate::schema::collection_entries;
+use crate::schema::collections;
+
+/// Statuses an entry is allowed to be in.
+pub const ENTRY_STATUSES: [&str; 3] = ["draft", "published", "archived"];
+
+#[derive(Identifiable, Debug, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone)]
+#[primary_key(uuid)]
+#[table_name = "collections"]
+pub struct Collection {
+    pub uuid: String,
+    /// The type used in the url, such as `blog` in `/v1/collections/blog/entries`.
+    pub name: String,
+    pub title: String,
+    /// JSON array of `CollectionField` describing the content of every entry.
+    #[serde(with = "json_text")]
+    pub fields: String,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone, Validate)]
+#[table_name = "collections"]
+pub struct MutCollection {
+    pub uuid: Option<String>,
+    #[validate(custom = "validate_slug")]
+    pub name: String,
+    pub title: String,
+    #[serde(with = "json_text")]
+    #[validate(custom = "validate_collection_fields")]
+    pub fields: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Text,
+    Number,
+    Boolean,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CollectionField {
+    pub name: String,
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(
+    Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone,
+)]
+#[belongs_to(Collection, foreign_key = "collection_uuid")]
+#[primary_key(uuid)]
+#[table_name = "collection_entries"]
+pub struct CollectionEntry {
+    pub uuid: String,
+    pub collection_uuid: String,
+    pub slug: String,
+    pub status: String,
+    /// JSON object whose keys are the collection's fields.
+    #[serde(with = "json_text")]
+    pub content: String,
+    pub time_created: NaiveDateTime,
+    pub time_updated: NaiveDateTime,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone)]
+#[table_name = "collection_entries"]
+pub struct MutCollectionEntry {
+    pub uuid: Option<String>,
+    /// Taken from the url rather than the body.
+    #[serde(default)]
+    pub collection_uuid: String,
+    pub slug: String,
+    pub status: String,
+    #[serde(with = "json_text")]
+    pub content: String,
+}
+
+/// Options used when listing the entries of a collection.
+/// `filters` match against the top level keys of an entry's content.
+#[derive(Debug, Default, Clone)]
+pub struct EntryQuery {
+    pub status: Option<String>,
+    pub filters: Map<String, Value>,
+    /// Name of the field to sort on. A leading `-` sorts descending. Newest first by default.
+    pub sort: Option<String>,
+    /// Every entry when `None`.
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// The sort value and uuid of the last entry of the previous page. Takes precedence over `offset`.
+    pub cursor: Option<(String, String)>,
+}
+
+impl EntryQuery {
+    /// The paging and sort of a listing, with its `status` filter.
+    pub fn from_list(query: &ListQuery, status: Option<String>) -> Self {
+        Self {
+            status,
+            sort: query.sort.clone(),
+            limit: Some(query.limit() as usize),
+            offset: query.offset() as usize,
+            cursor: query.cursor(),
+            ..Self::default()
+        }
+    }
+
+    /// The field to sort on and whether it's descending.
+    fn sort(&self) -> (&str, bool) {
+        match self.sort.as_deref() {
+            Some(sort) => match sort.strip_prefix('-') {
+                Some(field) => (field, true),
+                None => (sort, false),
+            },
+            None => ("time_created", true),
+        }
+    }
+}
+
+impl Collection {
+    pub fn field_definitions(&self) -> Vec<CollectionField> {
+        serde_json::from_str(&self.fields).unwrap_or_default()
+    }
+
+    /// What its entries can be sorted on: their columns, then the collection's fields.
+    pub fn sort_fields(&self) -> Vec<String> {
+        CollectionEntry::SORT_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .chain(self.field_definitions().into_iter().map(|field| field.name))
+            .collect()
+    }
+
+    /// Checks an entry's content against the field definitions of this collection.
+    /// Returns the first problem found. Problems with a content field are reported as `content.<field>`.
+    pub fn validate_entry(&self, entry: &MutCollectionEntry) -> Result<(), FieldError> {
+        if !ENTRY_STATUSES.contains(&entry.status.as_str()) {
+            return Err(FieldError::new(
+                "status",
+                "invalid",
+                format!("Status `{}` is not one of {:?}.", entry.status, ENTRY_STATUSES),
+            ));
+        }
+
+        let content: Value = serde_json::from_str(&entry.content)
+            .map_err(|e| FieldError::new("content", "invalid", e.to_string()))?;
+        let content = content.as_object().ok_or_else(|| {
+            FieldError::new("content", "invalid", "Entry content must be an object.")
+        })?;
+        let fields = self.field_definitions();
+
+        for key in content.keys() {
+            if !fields.iter().any(|f| &f.name == key) {
+                return Err(FieldError::new(
+                    &format!("content.{}", key),
+                    "unknown_field",
+                    format!("Field `{}` does not exist on `{}`.", key, self.name),
+                ));
+            }
+        }
+
+        for field in fields {
+            let matches = match (content.get(&field.name), field.field_type) {
+                (None, _) | (Some(Value::Null), _) => !field.required,
+                (Some(Value::String(_)), FieldType::Text) => true,
+                (Some(Value::Number(_)), FieldType::Number) => true,
+                (Some(Value::Bool(_)), FieldType::Boolean) => true,
+                _ => false,
+            };
+
+            if !matches {
+                return Err(FieldError::new(
+                    &format!("content.{}", field.name),
+                    "invalid",
+                    format!(
+                        "Field `{}` must be a {:?}{}.",
+                        field.name,
+                        field.field_type,
+                        if field.required {
+                            " and is required"
+                        } else {
+                            ""
+                        }
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Model<Self, MutCollection, String> for Collection {
+    fn create(new: &MutCollection, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(collections::table)
+            .values(new)
+            .execute(db)
+    }
+
+    fn read_one(_name: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use collections::dsl::name;
+
+        collections::table.filter(name.eq(_name)).first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        collections::table.load::<Self>(db)
+    }
+
+    fn update(
+        _name: String,
+        new: &MutCollection,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use collections::dsl::name;
+
+        diesel::update(collections::table.filter(name.eq(_name)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_name: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use collections::dsl::name;
+
+        diesel::delete(collections::table.filter(name.eq(_name))).execute(db)
+    }
+}
+
+impl Model<Self, MutCollectionEntry, String> for CollectionEntry {
+    fn create(
+        new: &MutCollectionEntry,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(collection_entries::table)
+            .values(new)
+            .execute(db)
+    }
+
+    fn read_one(_id: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use collection_entries::dsl::uuid;
+
+        collection_entries::table
+            .filter(uuid.eq(_id))
+            .first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        collection_entries::table.load::<Self>(db)
+    }
+
+    fn update(
+        _id: String,
+        new: &MutCollectionEntry,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use collection_entries::dsl::uuid;
+
+        diesel::update(collection_entries::table.filter(uuid.eq(_id)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use collection_entries::dsl::uuid;
+
+        diesel::delete(collection_entries::table.filter(uuid.eq(_id))).execute(db)
+    }
+}
+
+impl CollectionEntry {
+    /// Columns entries can be sorted on by the database. Any other sort is on a content field.
+    pub const SORT_COLUMNS: &'static [&'static str] = &["time_created", "time_updated", "slug", "status"];
+
+    /// Reads a single entry, making sure it belongs to the given collection.
+    pub fn read_one_in(
+        collection: &Collection,
+        _id: String,
+        db: &DbConnection,
+    ) -> Result<Self, diesel::result::Error> {
+        use collection_entries::dsl::uuid;
+
+        Self::belonging_to(collection)
+            .filter(uuid.eq(_id))
+            .first::<Self>(db)
+    }
+
+    /// Lists the entries of a collection, applying the filters, sort and paging of the query.
+    ///
+    /// Sorting on a column without content filters is left to the database, which only returns one page.
+    /// Content is stored as JSON text, so content filters and content sorts read every entry of the collection.
+    pub fn query(
+        collection: &Collection,
+        query: &EntryQuery,
+        db: &DbConnection,
+    ) -> Result<Paginated<Self>, diesel::result::Error> {
+        let (field, desc) = query.sort();
+
+        if query.filters.is_empty() && Self::SORT_COLUMNS.contains(&field) {
+            Self::query_columns(collection, query, field, desc, db)
+        } else {
+            Self::query_content(collection, query, field, desc, db)
+        }
+    }
+
+    fn query_columns(
+        collection: &Collection,
+        query: &EntryQuery,
+        field: &str,
+        desc: bool,
+        db: &DbConnection,
+    ) -> Result<Paginated<Self>, diesel::result::Error> {
+        let text_cursor = query.cursor.clone();
+        let time_cursor = query
+            .cursor
+            .clone()
+            .and_then(|(value, id)| Some((parse_time(&value)?, id)));
+
+        // a boxed query is for one kind of database, so it's built and run on the connection itself.
+        let (total, rows) = on_connection!(db, |db| {
+            let total = diesel::RunQueryDsl::get_result::<i64>(Self::filtered(collection, query).count(), db)?;
+
+            let statement = Self::filtered(collection, query);
+            let mut statement = match field {
+                "slug" => sort_and_seek!(
+                    statement,
+                    collection_entries::slug,
+                    collection_entries::uuid,
+                    text_cursor,
+                    desc
+                ),
+                "status" => sort_and_seek!(
+                    statement,
+                    collection_entries::status,
+                    collection_entries::uuid,
+                    text_cursor,
+                    desc
+                ),
+                "time_updated" => sort_and_seek!(
+                    statement,
+                    collection_entries::time_updated,
+                    collection_entries::uuid,
+                    time_cursor,
+                    desc
+                ),
+                _ => sort_and_seek!(
+                    statement,
+                    collection_entries::time_created,
+                    collection_entries::uuid,
+                    time_cursor,
+                    desc
+                ),
+            };
+
+            if query.cursor.is_none() {
+                statement = statement.offset(query.offset as i64);
+            }
+
+            // one extra row tells whether there is a next page.
+            let rows = match query.limit {
+                Some(limit) => diesel::RunQueryDsl::load::<Self>(
+                    statement.limit(limit.saturating_add(1).min(i64::MAX as usize) as i64),
+                    db,
+                )?,
+                None => diesel::RunQueryDsl::load::<Self>(statement, db)?,
+            };
+
+            (total, rows)
+        });
+
+        Ok(Self::page_of(rows, total, query.limit, field))
+    }
+
+    fn query_content(
+        collection: &Collection,
+        query: &EntryQuery,
+        field: &str,
+        desc: bool,
+        db: &DbConnection,
+    ) -> Result<Paginated<Self>, diesel::result::Error> {
+        let entries = on_connection!(db, |db| diesel::RunQueryDsl::load::<Self>(Self::filtered(collection, query), db))?;
+        let mut entries: Vec<(Self, Value)> = entries
+            .into_iter()
+            .map(|e| {
+                let content = serde_json::from_str(&e.content).unwrap_or(Value::Null);
+                (e, content)
+            })
+            .filter(|(_, content)| {
+                query
+                    .filters
+                    .iter()
+                    .all(|(key, value)| content.get(key) == Some(value))
+            })
+            .collect();
+
+        // the uuid breaks ties, so every entry has one place to continue a cursor from.
+        let ordered = |ordering: Ordering| if desc { ordering.reverse() } else { ordering };
+        entries.sort_by(|(a, a_content), (b, b_content)| {
+            let ordering = compare_keys(
+                a.sort_key(a_content, field).as_ref(),
+                b.sort_key(b_content, field).as_ref(),
+            );
+            ordered(ordering.then_with(|| a.uuid.cmp(&b.uuid)))
+        });
+
+        let total = entries.len() as i64;
+        let start = match &query.cursor {
+            Some((value, id)) => {
+                let value = Self::cursor_key(value, field);
+                entries
+                    .iter()
+                    .position(|(e, content)| {
+                        let ordering = compare_keys(e.sort_key(content, field).as_ref(), value.as_ref())
+                            .then_with(|| e.uuid.as_str().cmp(id));
+                        ordered(ordering) == Ordering::Greater
+                    })
+                    .unwrap_or(entries.len())
+            }
+            None => query.offset.min(entries.len()),
+        };
+
+        let rows = entries
+            .into_iter()
+            .skip(start)
+            .take(query.limit.map_or(usize::MAX, |limit| limit.saturating_add(1)))
+            .map(|(e, _)| e)
+            .collect();
+
+        Ok(Self::page_of(rows, total, query.limit, field))
+    }
+
+    /// The entries of a collection, with the status filter of a listing.
+    fn filtered<DB>(collection: &Collection, query: &EntryQuery) -> collection_entries::BoxedQuery<'static, DB>
+    where
+        DB: Backend + HasSqlType<Text> + 'static,
+        String: ToSql<Text, DB>,
+    {
+        let mut statement = collection_entries::table
+            .filter(collection_entries::collection_uuid.eq(collection.uuid.clone()))
+            .into_boxed();
+
+        if let Some(entry_status) = &query.status {
+            statement = statement.filter(collection_entries::status.eq(entry_status.clone()));
+        }
+
+        statement
+    }
+
+    /// Cuts the rows read for a page, with the one extra row that tells whether there is a next page.
+    fn page_of(mut rows: Vec<Self>, total: i64, limit: Option<usize>, field: &str) -> Paginated<Self> {
+        let next_cursor = match limit {
+            Some(limit) if rows.len() > limit => {
+                rows.truncate(limit);
+                rows.last().map(|entry| encode_cursor(&entry.sort_value(field), &entry.uuid))
+            }
+            _ => None,
+        };
+
+        Paginated {
+            items: rows,
+            total,
+            next_cursor,
+        }
+    }
+
+    /// A column as text, or a content field as JSON, for a cursor.
+    fn sort_value(&self, field: &str) -> String {
+        match field {
+            "slug" => self.slug.clone(),
+            "status" => self.status.clone(),
+            "time_updated" => format_time(&self.time_updated),
+            "time_created" => format_time(&self.time_created),
+            _ => serde_json::from_str::<Value>(&self.content)
+                .ok()
+                .and_then(|content| content.get(field).map(Value::to_string))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The value an entry is sorted by, `None` for a content field it doesn't have.
+    fn sort_key(&self, content: &Value, field: &str) -> Option<Value> {
+        match field {
+            "slug" | "status" | "time_updated" | "time_created" => Some(Value::String(self.sort_value(field))),
+            _ => content.get(field).cloned(),
+        }
+    }
+
+    /// Reads the sort value of a cursor back into a sort key.
+    fn cursor_key(value: &str, field: &str) -> Option<Value> {
+        match field {
+            "slug" | "status" | "time_updated" | "time_created" => Some(Value::String(value.to_string())),
+            _ => serde_json::from_str(value).ok(),
+        }
+    }
+}
+
+/// Compares two sort keys. Entries without the field come last, and values of different types are equal.
+fn compare_keys(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
@@ -0,0 +1,50 @@
+use serde::Deserialize;
+
+/// A named set of rows to load into a database, such as the demo site or data for tests.
+/// Read from `<name>.yaml`, `<name>.yml` or `<name>.json` in the fixtures folder.
+///
+/// Pages hold their own modules and categories, so a fixture never needs to know a uuid.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    #[serde(default)]
+    pub pages: Vec<FixturePage>,
+    #[serde(default)]
+    pub users: Vec<FixtureUser>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixturePage {
+    pub page_name: String,
+    pub page_url: String,
+    pub page_title: String,
+    pub cache_control: Option<String>,
+    #[serde(default)]
+    pub modules: Vec<FixtureModule>,
+    #[serde(default)]
+    pub categories: Vec<FixtureCategory>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixtureCategory {
+    pub title: String,
+    #[serde(default)]
+    pub modules: Vec<FixtureModule>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixtureModule {
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixtureUser {
+    pub username: String,
+    /// Hashed before it's stored. An empty password is kept empty, which lets `root` log in once without one.
+    pub password: String,
+}
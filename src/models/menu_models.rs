@@ -0,0 +1,274 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::page_models::Page;
+use super::{DbConnection, Model, RunQueryDsl};
+use crate::schema::menu_items;
+use crate::schema::menus;
+use crate::schema::pages;
+
+#[derive(Identifiable, Debug, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone)]
+#[primary_key(uuid)]
+#[table_name = "menus"]
+pub struct Menu {
+    pub uuid: String,
+    /// Used to reference the menu from templates, such as `main` or `footer`.
+    pub name: String,
+    pub title: String,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone)]
+#[table_name = "menus"]
+pub struct MutMenu {
+    pub uuid: Option<String>,
+    pub name: String,
+    pub title: String,
+}
+
+/// A link in a menu.
+/// Internal links point at a page by uuid so they keep working when the page's url changes.
+#[derive(
+    Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone,
+)]
+#[belongs_to(Menu, foreign_key = "menu_uuid")]
+#[belongs_to(Page, foreign_key = "page_uuid")]
+#[primary_key(uuid)]
+#[table_name = "menu_items"]
+pub struct MenuItem {
+    pub uuid: String,
+    pub menu_uuid: String,
+    pub parent_uuid: Option<String>,
+    /// Items are ordered by position within their parent.
+    pub position: i32,
+    pub title: String,
+    pub page_uuid: Option<String>,
+    /// External url, used when there is no `page_uuid`.
+    pub url: Option<String>,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone)]
+#[table_name = "menu_items"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct MutMenuItem {
+    /// None is written as NULL for menu items, so the uuid is always set.
+    #[serde(default)]
+    pub uuid: String,
+    /// Taken from the url rather than the body.
+    #[serde(default)]
+    pub menu_uuid: String,
+    pub parent_uuid: Option<String>,
+    #[serde(default)]
+    pub position: i32,
+    pub title: String,
+    pub page_uuid: Option<String>,
+    pub url: Option<String>,
+}
+
+/// A menu item with its link resolved and its children nested below it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MenuItemDTO {
+    pub uuid: String,
+    pub title: String,
+    pub url: String,
+    pub page_uuid: Option<String>,
+    pub external: bool,
+    /// Whether this item links to the page being displayed.
+    pub active: bool,
+    /// Whether this item or one of its children is active.
+    pub active_trail: bool,
+    pub children: Vec<MenuItemDTO>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MenuDTO {
+    pub uuid: String,
+    pub name: String,
+    pub title: String,
+    pub items: Vec<MenuItemDTO>,
+}
+
+impl MutMenuItem {
+    /// An item must link to exactly one of a page or an external url.
+    pub fn has_single_target(&self) -> bool {
+        self.page_uuid.is_some() != self.url.is_some()
+    }
+}
+
+impl Model<Self, MutMenu, String> for Menu {
+    fn create(new: &MutMenu, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(menus::table).values(new).execute(db)
+    }
+
+    fn read_one(_name: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use menus::dsl::name;
+
+        menus::table.filter(name.eq(_name)).first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        menus::table.load::<Self>(db)
+    }
+
+    fn update(
+        _name: String,
+        new: &MutMenu,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use menus::dsl::name;
+
+        diesel::update(menus::table.filter(name.eq(_name)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_name: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use menus::dsl::name;
+
+        diesel::delete(menus::table.filter(name.eq(_name))).execute(db)
+    }
+}
+
+impl Menu {
+    /// Reads the menu as a tree, marking the items that link to `current_url` as active.
+    pub fn read_tree(
+        &self,
+        current_url: Option<&str>,
+        db: &DbConnection,
+    ) -> Result<MenuDTO, diesel::result::Error> {
+        use menu_items::dsl::position;
+
+        let items: Vec<(MenuItem, Option<String>)> = MenuItem::belonging_to(self)
+            .left_join(pages::table)
+            .select((menu_items::all_columns, pages::page_url.nullable()))
+            .order(position.asc())
+            .load::<(MenuItem, Option<String>)>(db)?;
+
+        Ok(MenuDTO {
+            uuid: self.uuid.clone(),
+            name: self.name.clone(),
+            title: self.title.clone(),
+            items: MenuItem::tree(&items, None, current_url),
+        })
+    }
+}
+
+impl Model<Self, MutMenuItem, String> for MenuItem {
+    fn create(new: &MutMenuItem, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(menu_items::table)
+            .values(new)
+            .execute(db)
+    }
+
+    fn read_one(_id: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use menu_items::dsl::uuid;
+
+        menu_items::table.filter(uuid.eq(_id)).first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        menu_items::table.load::<Self>(db)
+    }
+
+    fn update(
+        _id: String,
+        new: &MutMenuItem,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use menu_items::dsl::uuid;
+
+        diesel::update(menu_items::table.filter(uuid.eq(_id)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use menu_items::dsl::uuid;
+
+        diesel::delete(menu_items::table.filter(uuid.eq(_id))).execute(db)
+    }
+}
+
+impl MenuItem {
+    fn tree(
+        items: &[(MenuItem, Option<String>)],
+        parent: Option<&String>,
+        current_url: Option<&str>,
+    ) -> Vec<MenuItemDTO> {
+        items
+            .iter()
+            .filter(|(item, _)| item.parent_uuid.as_ref() == parent)
+            .map(|(item, page_url)| {
+                let url = page_url
+                    .clone()
+                    .or_else(|| item.url.clone())
+                    .unwrap_or_default();
+                let children = Self::tree(items, Some(&item.uuid), current_url);
+                let active = current_url == Some(url.as_str()) && item.page_uuid.is_some();
+
+                MenuItemDTO {
+                    uuid: item.uuid.clone(),
+                    title: item.title.clone(),
+                    external: item.page_uuid.is_none(),
+                    page_uuid: item.page_uuid.clone(),
+                    active_trail: active || children.iter().any(|c| c.active_trail),
+                    active,
+                    url,
+                    children,
+                }
+            })
+            .collect()
+    }
+
+    /// Reads a single item, making sure it belongs to the given menu.
+    pub fn read_one_in(
+        menu: &Menu,
+        _id: String,
+        db: &DbConnection,
+    ) -> Result<Self, diesel::result::Error> {
+        use menu_items::dsl::uuid;
+
+        Self::belonging_to(menu)
+            .filter(uuid.eq(_id))
+            .first::<Self>(db)
+    }
+
+    /// Checks that an item's parent is in the same menu and is not the item itself or one of its children.
+    pub fn valid_parent(
+        new: &MutMenuItem,
+        current: Option<&MenuItem>,
+        db: &DbConnection,
+    ) -> Result<bool, diesel::result::Error> {
+        use menu_items::dsl::menu_uuid;
+
+        let parent_uuid = match &new.parent_uuid {
+            Some(parent_uuid) => parent_uuid,
+            None => return Ok(true),
+        };
+
+        let siblings = menu_items::table
+            .filter(menu_uuid.eq(new.menu_uuid.clone()))
+            .load::<MenuItem>(db)?;
+
+        let mut ancestor = siblings.iter().find(|i| &i.uuid == parent_uuid);
+
+        if ancestor.is_none() {
+            return Ok(false);
+        }
+
+        // walk up from the new parent. reaching the current item means it would become its own ancestor.
+        let mut depth = 0;
+        while let Some(item) = ancestor {
+            if Some(&item.uuid) == current.map(|c| &c.uuid) || depth > siblings.len() {
+                return Ok(false);
+            }
+
+            ancestor = item
+                .parent_uuid
+                .as_ref()
+                .and_then(|p| siblings.iter().find(|i| &i.uuid == p));
+            depth += 1;
+        }
+
+        Ok(true)
+    }
+}
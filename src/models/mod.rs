@@ -1,17 +1,55 @@
+#[macro_use]
+pub mod pagination;
+
+pub mod backend;
+pub mod batch_models;
+pub mod collection_models;
 pub mod config_models;
+pub mod fixture_models;
+pub mod menu_models;
 pub mod module_models;
 pub mod page_models;
+pub mod redirect_models;
+pub mod taxonomy_models;
 pub mod user_models;
 
-use actix_web::web;
-use diesel::{MysqlConnection, query_builder::AsChangeset, r2d2::{ConnectionManager, Pool, PoolError, PooledConnection}};
+use actix_web::{error::BlockingError, web};
+use diesel::{connection::SimpleConnection, query_builder::AsChangeset, r2d2::{CustomizeConnection, Pool, PoolError, PooledConnection}};
+use std::ops::Deref;
+use std::time::Duration;
 
+use crate::services::config_service::ConfigError;
 use crate::services::errors_service::CustomHttpError;
 
 use self::config_models::LocalConfig;
+use self::pagination::{ListQuery, Paginated};
+
+use self::backend::{Backend, DbConnectionManager};
+
+pub use self::backend::{DbConnection, RunQueryDsl};
+
+/// The pool of connections to the configured database, which knows which kind of database it is.
+#[derive(Clone)]
+pub struct DbPool {
+    pool: Pool<DbConnectionManager>,
+    backend: Backend,
+}
+
+impl DbPool {
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+}
 
-pub type MySQLPool = Pool<ConnectionManager<MysqlConnection>>;
-pub type MySQLPooledConnection = PooledConnection<ConnectionManager<MysqlConnection>>;
+impl Deref for DbPool {
+    type Target = Pool<DbConnectionManager>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
+
+pub type DbPooledConnection = PooledConnection<DbConnectionManager>;
 
 /// CRUD implementation.
 /// TQueryable: The queryable struct.
@@ -19,15 +57,78 @@ pub type MySQLPooledConnection = PooledConnection<ConnectionManager<MysqlConnect
 /// TPrimary: The primary key type.
 /// TDto: The DTO object that will be sent back to the user.
 pub trait Model<TQueryable, TMutable: AsChangeset, TPrimary, TDto = TQueryable> {
-    fn create(new: &TMutable, db: &MysqlConnection) -> Result<usize, diesel::result::Error>;
-    fn read_one(id: TPrimary, db: &MysqlConnection) -> Result<TDto, diesel::result::Error>;
-    fn read_all(db: &MysqlConnection) -> Result<Vec<TDto>, diesel::result::Error>;
+    fn create(new: &TMutable, db: &DbConnection) -> Result<usize, diesel::result::Error>;
+    fn read_one(id: TPrimary, db: &DbConnection) -> Result<TDto, diesel::result::Error>;
+    fn read_all(db: &DbConnection) -> Result<Vec<TDto>, diesel::result::Error>;
     fn update(
         id: TPrimary,
         new: &TMutable,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error>;
-    fn delete(id: TPrimary, db: &MysqlConnection) -> Result<usize, diesel::result::Error>;
+    fn delete(id: TPrimary, db: &DbConnection) -> Result<usize, diesel::result::Error>;
+
+    /// Reads one page of a listing.
+    /// Models behind list endpoints override this to filter, sort and page in SQL.
+    /// By default it pages through `read_all` without filtering or sorting.
+    fn read_page(
+        query: &ListQuery,
+        db: &DbConnection,
+    ) -> Result<Paginated<TDto>, diesel::result::Error> {
+        let all = Self::read_all(db)?;
+        let total = all.len() as i64;
+        let items = all
+            .into_iter()
+            .skip(query.offset() as usize)
+            .take(query.limit() as usize)
+            .collect();
+
+        Ok(Paginated {
+            items,
+            total,
+            next_cursor: None,
+        })
+    }
+}
+
+/// Serde adapter for columns that hold JSON as text.
+/// Lets a `String` column be sent and received as a nested JSON value rather than an escaped string.
+pub mod json_text {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(text: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        let value: Value = serde_json::from_str(text).unwrap_or(Value::Null);
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        serde_json::to_string(&value).map_err(D::Error::custom)
+    }
+}
+
+/// Serde adapter for nullable columns that can be left as they are.
+/// A missing field is `None`, which leaves the column alone, and `null` is `Some(None)`, which clears it.
+/// Use with `#[serde(default)]`.
+pub mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.as_ref().and_then(Option::as_ref).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<T>>, D::Error> {
+        Option::deserialize(deserializer).map(Some)
+    }
+}
+
+/// Rules on a mutable struct that need the database, such as unique columns and rows it refers to.
+/// Checked by `validation_service::validate` after the struct's own `#[validate]` rules pass.
+pub trait ValidateRows {
+    /// `id` is the row being updated, so it isn't counted as taking its own unique values.
+    fn validate_rows(&self, id: Option<&str>, db: &DbConnection) -> Result<(), CustomHttpError>;
 }
 
 pub trait DTO<TColumns> {
@@ -40,52 +141,150 @@ pub trait DTO<TColumns> {
 pub trait Joinable<TLeft, TRight, TPrimary> {
     fn read_one_join_on(
         id: TPrimary,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<(TLeft, Vec<TRight>), diesel::result::Error>;
 }
 
-pub fn format_connection_string(conf: LocalConfig) -> String {
+/// `database_url` is used as is when it's set, which for SQLite is a file path or `:memory:`.
+/// Otherwise a MySQL url is built from the `mysql_` settings.
+pub fn format_connection_string(conf: LocalConfig) -> Result<String, ConfigError> {
+    if let Some(database_url) = conf.database_url {
+        return Ok(database_url);
+    }
+
     match conf.mysql_url {
         Some(mysql_url) => {
-            format!(
+            let mysql_port = conf.mysql_port.ok_or(ConfigError::Missing("mysql_port", "APP_MYSQL_PORT"))?;
+
+            Ok(format!(
                 "mysql://{}:{}@{}:{}/{}",
                 conf.mysql_username,
                 conf.mysql_password,
                 mysql_url,
-                conf.mysql_port.unwrap(),
+                mysql_port,
                 conf.mysql_database
-            )
+            ))
         }
         None if std::env::var("MYSQL_UNIX_PORT").is_ok() => {
-            format!(
+            Ok(format!(
                 "mysql://{}:{}@/{}",
                 conf.mysql_username,
                 conf.mysql_password,
                 conf.mysql_database
-            )
-        }
-        None => {
-            panic!("Must supply one of the following: [mysql_url], [sql_name | socket_dir]")
+            ))
         }
+        None => Err(ConfigError::Missing("database_url", "APP_DATABASE_URL")),
     }
 }
 
-pub fn establish_database_connection(conf: LocalConfig) -> Option<MySQLPool> {
-    let db_url = format_connection_string(conf);
+/// Fails early when the configured database isn't one this server was built with, and otherwise says which one it is.
+pub fn check_connection_string(db_url: &str) -> Result<Backend, ConfigError> {
+    Backend::for_url(db_url).ok_or_else(|| {
+        let built_with: Vec<&str> = Backend::ALL.iter().map(|backend| backend.name()).collect();
 
-    Some(init_pool(&db_url).expect("Failed to create pool."))
+        ConfigError::Invalid(
+            "`database_url`".to_string(),
+            format!(
+                "this server was built for {} databases. Set it to one of them, or rebuild with the matching feature.",
+                built_with.join(", ")
+            ),
+        )
+    })
 }
 
-pub fn init_connection(db_url: &str) -> ConnectionManager<diesel::MysqlConnection> {
-    ConnectionManager::<MysqlConnection>::new(db_url)
+pub fn establish_database_connection(conf: LocalConfig) -> Result<DbPool, ConfigError> {
+    let db_url = format_connection_string(conf.clone())?;
+    let backend = check_connection_string(&db_url)?;
+
+    init_pool(&db_url, backend, &conf).map_err(|e| ConfigError::Unreachable(e.to_string()))
+}
+
+pub fn init_connection(db_url: &str) -> DbConnectionManager {
+    DbConnectionManager::new(db_url)
+}
+
+/// Prepares every connection the pool hands out, with the backend's own setup and the statement timeout (in milliseconds)
+/// so a runaway query can't hold a blocking thread forever.
+/// That's `MAX_EXECUTION_TIME` on MySQL, which only applies to `SELECT`s, `statement_timeout` on Postgres,
+/// and how long to wait on a locked database on SQLite.
+#[derive(Debug)]
+struct ConnectionSetup {
+    backend: Backend,
+    query_timeout: Option<u64>,
+}
+
+impl CustomizeConnection<DbConnection, diesel::r2d2::Error> for ConnectionSetup {
+    fn on_acquire(&self, conn: &mut DbConnection) -> Result<(), diesel::r2d2::Error> {
+        if let Some(setup) = self.backend.connection_setup() {
+            conn.batch_execute(setup)
+                .map_err(diesel::r2d2::Error::QueryError)?;
+        }
+
+        if let Some(query_timeout) = self.query_timeout {
+            conn.batch_execute(&self.backend.query_timeout_statement(query_timeout))
+                .map_err(diesel::r2d2::Error::QueryError)?;
+        }
+
+        Ok(())
+    }
 }
 
 // https://dev.to/werner/practical-rust-web-development-connection-pool-46f4
-pub fn init_pool(db_url: &str) -> Result<MySQLPool, PoolError> {
+pub fn init_pool(db_url: &str, backend: Backend, conf: &LocalConfig) -> Result<DbPool, PoolError> {
     let manager = init_connection(db_url);
-    Pool::builder().max_size(2).build(manager)
+    let mut builder = Pool::builder()
+        .max_size(conf.pool_size.unwrap_or(10))
+        .connection_timeout(Duration::from_secs(conf.pool_timeout.unwrap_or(30)));
+
+    if backend.connection_setup().is_some() || conf.query_timeout.is_some() {
+        builder = builder.connection_customizer(Box::new(ConnectionSetup {
+            backend,
+            query_timeout: conf.query_timeout,
+        }));
+    }
+
+    // an in-memory SQLite database only lives as long as a connection to it,
+    // so the pool never closes its connections, and a plain `:memory:` one can only be shared by using a single connection.
+    if backend.is_embedded() {
+        builder = builder.max_lifetime(None).idle_timeout(None);
+
+        if db_url == ":memory:" {
+            builder = builder.max_size(1);
+        }
+    }
+
+    Ok(DbPool {
+        pool: builder.build(manager)?,
+        backend,
+    })
+}
+
+pub fn pool_handler(pool: web::Data<DbPool>) -> Result<DbPooledConnection, CustomHttpError> {
+    checkout(&pool)
+}
+
+/// Checks out a connection. Every connection being busy for `pool_timeout` is the server's problem, not the request's.
+pub fn checkout(pool: &DbPool) -> Result<DbPooledConnection, CustomHttpError> {
+    pool.get().map_err(|e| {
+        log::error!("No database connection: {}", e);
+        CustomHttpError::Unavailable
+    })
 }
 
-pub fn pool_handler(pool: web::Data<MySQLPool>) -> Result<MySQLPooledConnection, CustomHttpError> {
-    pool.get().or(Err(CustomHttpError::BadRequest))
+/// Runs database work on actix's blocking thread pool so a slow query doesn't stall the worker serving other requests.
+/// Checking out the connection happens there too, since waiting on the pool blocks as well.
+pub async fn run_query<F, T>(pool: web::Data<DbPool>, query: F) -> Result<T, CustomHttpError>
+where
+    F: FnOnce(&DbConnection) -> Result<T, CustomHttpError> + Send + 'static,
+    T: Send + 'static,
+{
+    web::block(move || {
+        let db = pool_handler(pool)?;
+        query(&db)
+    })
+    .await
+    .map_err(|e| match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => CustomHttpError::Unknown,
+    })
 }
@@ -1,13 +1,23 @@
+use chrono::NaiveDateTime;
+use diesel::backend::Backend;
 use diesel::prelude::*;
-use diesel::{Insertable, Queryable, RunQueryDsl};
+use diesel::serialize::ToSql;
+use diesel::sql_types::{HasSqlType, Text, Timestamp};
+use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 use super::page_models::Page;
-use super::{Model};
+use super::pagination::{encode_cursor, format_time, parse_time, ListQuery, Paginated};
+use super::backend::on_connection;
+use super::{DbConnection, Model, RunQueryDsl, ValidateRows};
 use crate::schema::module_category;
 use crate::schema::modules;
+use crate::schema::pages;
+use crate::services::errors_service::{CustomHttpError, FieldError};
+use crate::services::validation_service::validate_text_size;
 
-#[derive(Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone, Eq, Hash)]
 #[belongs_to(Page, foreign_key = "page_uuid")]
 #[belongs_to(ModuleCategory, foreign_key = "category_uuid")]
 #[primary_key(uuid)]
@@ -18,15 +28,20 @@ pub struct Module {
     pub category_uuid: Option<String>,
     pub title: String,
     pub content: String,
+    pub time_updated: NaiveDateTime,
+    /// Incremented on every update, used as the `ETag` for optimistic concurrency.
+    pub version: i32,
 }
 
-#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone)]
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone, Validate)]
 #[table_name = "modules"]
 pub struct MutModule {
     pub uuid: Option<String>,
+    #[validate(length(min = 1, max = 255, message = "Must be 1 to 255 characters."))]
     pub title: String,
     pub page_uuid: String,
     pub category_uuid: Option<String>,
+    #[validate(custom = "validate_text_size")]
     pub content: String,
 }
 
@@ -44,7 +59,7 @@ pub struct FieldsDTO {
 }
 
 #[derive(
-    Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, PartialEq, Clone, Eq, Hash,
+    Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone, Eq, Hash,
 )]
 #[primary_key(uuid)]
 #[belongs_to(Page, foreign_key = "page_uuid")]
@@ -52,21 +67,103 @@ pub struct FieldsDTO {
 pub struct ModuleCategory {
     pub uuid: String,
     pub page_uuid: String,
-    pub title: String
+    pub title: String,
+    pub version: i32,
 }
 
 #[derive(
-    Debug, Serialize, Deserialize, AsChangeset, Insertable, PartialEq, Clone, Eq, Hash,
+    Debug, Serialize, Deserialize, AsChangeset, Insertable, PartialEq, Clone, Eq, Hash, Validate,
 )]
 #[table_name = "module_category"]
 pub struct MutCategory {
+    #[validate(length(min = 1, max = 255, message = "Must be 1 to 255 characters."))]
     pub title: String,
     pub page_uuid: String,
     pub uuid: Option<String>
 }
 
+/// Whether a page exists, for rows that belong to one.
+fn page_exists(page_uuid: &str, db: &DbConnection) -> Result<bool, diesel::result::Error> {
+    let count = pages::table
+        .filter(pages::uuid.eq(page_uuid))
+        .count()
+        .get_result::<i64>(db)?;
+
+    Ok(count > 0)
+}
+
+fn missing(field: &str, message: &str) -> CustomHttpError {
+    CustomHttpError::Invalid(vec![FieldError::new(field, "not_found", message)])
+}
+
+impl ValidateRows for MutModule {
+    /// The page has to exist, and so does the category if there is one. The category has to be on the same page.
+    fn validate_rows(&self, _: Option<&str>, db: &DbConnection) -> Result<(), CustomHttpError> {
+        if !page_exists(&self.page_uuid, db)? {
+            return Err(missing("page_uuid", "No page has this uuid."));
+        }
+
+        if let Some(category_uuid) = &self.category_uuid {
+            let count = module_category::table
+                .filter(module_category::uuid.eq(category_uuid))
+                .filter(module_category::page_uuid.eq(&self.page_uuid))
+                .count()
+                .get_result::<i64>(db)?;
+
+            if count == 0 {
+                return Err(missing("category_uuid", "No category on this page has this uuid."));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ValidateRows for MutCategory {
+    fn validate_rows(&self, _: Option<&str>, db: &DbConnection) -> Result<(), CustomHttpError> {
+        match page_exists(&self.page_uuid, db)? {
+            true => Ok(()),
+            false => Err(missing("page_uuid", "No page has this uuid.")),
+        }
+    }
+}
+
 impl ModuleCategory {
-    pub fn join(_id: String, db: &MysqlConnection) -> Result<Vec<Module>, diesel::result::Error> {
+    /// Updates the category only if it's still at `expected_version`. `Ok(0)` means someone else saved it first.
+    pub fn update_versioned(
+        _id: String,
+        expected_version: i32,
+        new: &MutCategory,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use module_category::dsl::{uuid, version};
+
+        diesel::update(
+            module_category::table
+                .filter(uuid.eq(_id))
+                .filter(version.eq(expected_version)),
+        )
+        .set((new, version.eq(version + 1)))
+        .execute(db)
+    }
+
+    /// Deletes the category only if it's still at `expected_version`. `Ok(0)` means it changed since it was read.
+    pub fn delete_versioned(
+        _id: String,
+        expected_version: i32,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use module_category::dsl::{uuid, version};
+
+        diesel::delete(
+            module_category::table
+                .filter(uuid.eq(_id))
+                .filter(version.eq(expected_version)),
+        )
+        .execute(db)
+    }
+
+    pub fn join(_id: String, db: &DbConnection) -> Result<Vec<Module>, diesel::result::Error> {
         use module_category::dsl::uuid;
         let categories = module_category::table.filter(uuid.eq(_id)).first::<Self>(db)?;
 
@@ -75,13 +172,13 @@ impl ModuleCategory {
 }
 
 impl Model<Self, MutCategory, String, ModuleCategory> for ModuleCategory {
-    fn create(new: &MutCategory, db: &MysqlConnection) -> Result<usize, diesel::result::Error> {
-        Ok(diesel::insert_or_ignore_into(module_category::table)
+    fn create(new: &MutCategory, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        Ok(diesel::insert_into(module_category::table)
             .values(new)
             .execute(db)?)
     }
 
-    fn read_one(_id: String, db: &MysqlConnection) -> Result<ModuleCategory, diesel::result::Error> {
+    fn read_one(_id: String, db: &DbConnection) -> Result<ModuleCategory, diesel::result::Error> {
         use module_category::dsl::uuid;
 
         let module = module_category::table.filter(uuid.eq(_id)).first::<ModuleCategory>(db)?;
@@ -89,23 +186,23 @@ impl Model<Self, MutCategory, String, ModuleCategory> for ModuleCategory {
         Ok(module)
     }
 
-    fn read_all(_db: &MysqlConnection) -> Result<Vec<ModuleCategory>, diesel::result::Error> {
+    fn read_all(_db: &DbConnection) -> Result<Vec<ModuleCategory>, diesel::result::Error> {
         unimplemented!()
     }
 
     fn update(
         _id: String,
         new: &MutCategory,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error> {
-        use module_category::dsl::uuid;
+        use module_category::dsl::{uuid, version};
 
         Ok(diesel::update(module_category::table.filter(uuid.eq(_id)))
-            .set(new)
+            .set((new, version.eq(version + 1)))
             .execute(db)?)
     }
 
-    fn delete(_id: String, db: &MysqlConnection) -> Result<usize, diesel::result::Error> {
+    fn delete(_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
         use module_category::dsl::uuid;
 
         Ok(diesel::delete(module_category::table.filter(uuid.eq(_id))).execute(db)?)
@@ -115,14 +212,14 @@ impl Model<Self, MutCategory, String, ModuleCategory> for ModuleCategory {
 impl Model<Self, MutModule, String, Module> for Module {
     fn create(
         new_module: &MutModule,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error> {
         Ok(diesel::insert_into(modules::table)
             .values(new_module)
             .execute(db)?)
     }
 
-    fn read_one(mod_id: String, db: &MysqlConnection) -> Result<Module, diesel::result::Error> {
+    fn read_one(mod_id: String, db: &DbConnection) -> Result<Module, diesel::result::Error> {
         use modules::dsl::uuid;
 
         let module = modules::table.filter(uuid.eq(mod_id)).first::<Self>(db)?;
@@ -130,14 +227,14 @@ impl Model<Self, MutModule, String, Module> for Module {
         Ok(module.into())
     }
 
-    fn read_all(db: &MysqlConnection) -> Result<Vec<Module>, diesel::result::Error> {
+    fn read_all(db: &DbConnection) -> Result<Vec<Module>, diesel::result::Error> {
         use modules::dsl::category_uuid;
         Ok(modules::table
             .filter(category_uuid.is_null())
             .load::<Module>(db)?.into_iter().map(|m| { m.into() }).collect())
     }
 
-    fn delete(mod_id: String, db: &MysqlConnection) -> Result<usize, diesel::result::Error> {
+    fn delete(mod_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
         use modules::dsl::uuid;
 
         Ok(diesel::delete(modules::table.filter(uuid.eq(mod_id))).execute(db)?)
@@ -146,12 +243,133 @@ impl Model<Self, MutModule, String, Module> for Module {
     fn update(
         mod_id: String,
         new_module: &MutModule,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error> {
-        use modules::dsl::uuid;
+        use modules::dsl::{uuid, version};
 
         Ok(diesel::update(modules::table.filter(uuid.eq(mod_id)))
-            .set(new_module)
+            .set((new_module, version.eq(version + 1)))
             .execute(db)?)
     }
+
+    fn read_page(
+        query: &ListQuery,
+        db: &DbConnection,
+    ) -> Result<Paginated<Module>, diesel::result::Error> {
+        let (field, desc) = query.sort(Self::SORT_FIELDS[0]);
+        let cursor = query.cursor();
+        let text_cursor = cursor.clone();
+        let time_cursor = cursor
+            .clone()
+            .and_then(|(value, id)| Some((parse_time(&value)?, id)));
+        let limit = query.limit();
+
+        // a boxed query is for one kind of database, so it's built and run on the connection itself.
+        let (total, mut rows) = on_connection!(db, |db| {
+            let total = diesel::RunQueryDsl::get_result::<i64>(Self::filtered(query).count(), db)?;
+
+            let statement = Self::filtered(query);
+            let mut statement = match field {
+                "time_updated" => {
+                    sort_and_seek!(statement, modules::time_updated, modules::uuid, time_cursor, desc)
+                }
+                _ => sort_and_seek!(statement, modules::title, modules::uuid, text_cursor, desc),
+            };
+
+            if cursor.is_none() {
+                statement = statement.offset(query.offset());
+            }
+
+            // one extra row tells whether there is a next page.
+            (total, diesel::RunQueryDsl::load::<Self>(statement.limit(limit + 1), db)?)
+        });
+
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last()
+                .map(|module| encode_cursor(&module.sort_value(field), &module.uuid))
+        } else {
+            None
+        };
+
+        Ok(Paginated {
+            items: rows,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+impl Module {
+    /// Fields modules can be sorted on. The first is the default.
+    pub const SORT_FIELDS: &'static [&'static str] = &["title", "time_updated"];
+
+    /// Modules matching the filters of a listing, which are the page, the title prefix and the updated range.
+    /// Like `read_all`, modules inside a category aren't listed.
+    fn filtered<DB>(query: &ListQuery) -> modules::BoxedQuery<'static, DB>
+    where
+        DB: Backend + HasSqlType<Text> + HasSqlType<Timestamp> + 'static,
+        String: ToSql<Text, DB>,
+        NaiveDateTime: ToSql<Timestamp, DB>,
+    {
+        let mut statement = modules::table
+            .filter(modules::category_uuid.is_null())
+            .into_boxed();
+
+        if let Some(page_uuid) = &query.page_uuid {
+            statement = statement.filter(modules::page_uuid.eq(page_uuid.clone()));
+        }
+        if let Some(title) = query.title_prefix() {
+            statement = statement.filter(modules::title.like(title).escape('\\'));
+        }
+        if let Some(after) = query.updated_after {
+            statement = statement.filter(modules::time_updated.ge(after));
+        }
+        if let Some(before) = query.updated_before {
+            statement = statement.filter(modules::time_updated.lt(before));
+        }
+
+        statement
+    }
+
+    pub fn sort_value(&self, field: &str) -> String {
+        match field {
+            "time_updated" => format_time(&self.time_updated),
+            _ => self.title.clone(),
+        }
+    }
+
+    /// Updates the module only if it's still at `expected_version`. `Ok(0)` means someone else saved it first.
+    pub fn update_versioned(
+        mod_id: String,
+        expected_version: i32,
+        new_module: &MutModule,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use modules::dsl::{uuid, version};
+
+        diesel::update(
+            modules::table
+                .filter(uuid.eq(mod_id))
+                .filter(version.eq(expected_version)),
+        )
+        .set((new_module, version.eq(version + 1)))
+        .execute(db)
+    }
+
+    /// Deletes the module only if it's still at `expected_version`. `Ok(0)` means it changed since it was read.
+    pub fn delete_versioned(
+        mod_id: String,
+        expected_version: i32,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use modules::dsl::{uuid, version};
+
+        diesel::delete(
+            modules::table
+                .filter(uuid.eq(mod_id))
+                .filter(version.eq(expected_version)),
+        )
+        .execute(db)
+    }
 }
\ No newline at end of file
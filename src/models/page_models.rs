@@ -1,19 +1,28 @@
 use chrono::NaiveDateTime;
+use diesel::backend::Backend;
 use diesel::prelude::*;
+use diesel::serialize::ToSql;
+use diesel::sql_types::{HasSqlType, Text, Timestamp};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use validator::Validate;
 
 use super::module_models::Module;
-use super::Model;
+use super::pagination::{encode_cursor, format_time, parse_time, ListQuery, Paginated};
+use super::backend::on_connection;
+use super::{DbConnection, Model, RunQueryDsl, ValidateRows};
 use crate::models::module_models::CategoryDTO;
 use crate::models::module_models::FieldsDTO;
 use crate::models::module_models::ModuleCategory;
 use crate::schema::module_category;
 use crate::schema::modules;
 use crate::schema::pages;
+use crate::services::errors_service::{CustomHttpError, FieldError};
+use crate::services::validation_service::{validate_path, validate_template_name};
 
-#[derive(Identifiable, Debug, Serialize, Deserialize, Queryable, PartialEq, Clone)]
+#[derive(Identifiable, Debug, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone)]
 #[primary_key(uuid)]
+#[table_name = "pages"]
 pub struct Page {
     pub uuid: String,
     /// This should match the name of the HTML file.
@@ -22,15 +31,33 @@ pub struct Page {
     pub page_url: String,
     pub page_title: String,
     pub time_created: NaiveDateTime,
+    pub time_updated: NaiveDateTime,
+    /// Overrides the `Cache-Control` header of the rendered page.
+    pub cache_control: Option<String>,
+    /// Incremented on every update, used as the `ETag` for optimistic concurrency.
+    pub version: i32,
 }
 
-#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone)]
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone, Validate)]
 #[table_name = "pages"]
 pub struct MutPage {
     pub uuid: Option<String>,
+    #[validate(
+        length(min = 1, max = 500, message = "Must be 1 to 500 characters."),
+        custom = "validate_template_name"
+    )]
     pub page_name: String,
+    #[validate(
+        length(min = 1, max = 255, message = "Must be 1 to 255 characters."),
+        custom = "validate_path"
+    )]
     pub page_url: String,
+    #[validate(length(min = 1, max = 500, message = "Must be 1 to 500 characters."))]
     pub page_title: String,
+    /// `null` clears it, and leaving it out keeps the page's current one.
+    #[serde(default, with = "super::nullable")]
+    #[validate(length(max = 255, message = "Must be at most 255 characters."))]
+    pub cache_control: Option<Option<String>>,
 }
 
 /// Used in the displaying of pages.
@@ -41,6 +68,9 @@ pub struct PageModuleDisplayDTO {
     pub page_url: String,
     pub page_title: String,
     pub time_created: NaiveDateTime,
+    pub time_updated: NaiveDateTime,
+    pub cache_control: Option<String>,
+    pub version: i32,
     /// the key of the hashmap is the `title` of the module, and the rest is the module.
     /// For the usefulness of this, see the `get` function on the default helpers.
     pub fields: HashMap<String, Module>,
@@ -55,6 +85,9 @@ impl From<Page> for PageModuleDisplayDTO {
             page_url: origin_page.page_url.to_string(),
             page_title: origin_page.page_title.to_string(),
             time_created: origin_page.time_created,
+            time_updated: origin_page.time_updated,
+            cache_control: origin_page.cache_control,
+            version: origin_page.version,
             fields: HashMap::new(),
             array_fields: HashMap::new(),
         }
@@ -69,6 +102,9 @@ pub struct PageModuleDTO {
     pub page_url: String,
     pub page_title: String,
     pub time_created: NaiveDateTime,
+    pub time_updated: NaiveDateTime,
+    pub cache_control: Option<String>,
+    pub version: i32,
     pub fields: FieldsDTO
 }
 
@@ -80,6 +116,9 @@ impl From<Page> for PageModuleDTO {
             page_url: origin_page.page_url.to_string(),
             page_title: origin_page.page_title.to_string(),
             time_created: origin_page.time_created,
+            time_updated: origin_page.time_updated,
+            cache_control: origin_page.cache_control,
+            version: origin_page.version,
             fields: FieldsDTO::default(),
         }
     }
@@ -92,6 +131,9 @@ pub struct PageDTO {
     pub page_url: String,
     pub page_title: String,
     pub time_created: NaiveDateTime,
+    pub time_updated: NaiveDateTime,
+    pub cache_control: Option<String>,
+    pub version: i32,
 }
 
 impl From<Page> for PageDTO {
@@ -102,18 +144,43 @@ impl From<Page> for PageDTO {
             page_url: origin_page.page_url.to_string(),
             page_title: origin_page.page_title.to_string(), 
             time_created: origin_page.time_created,
+            time_updated: origin_page.time_updated,
+            cache_control: origin_page.cache_control,
+            version: origin_page.version,
         }
     }
 }
 
+impl ValidateRows for MutPage {
+    /// Urls are how pages are found, so no two pages can share one.
+    fn validate_rows(&self, id: Option<&str>, db: &DbConnection) -> Result<(), CustomHttpError> {
+        use pages::dsl::{page_url, uuid};
+
+        let taken_by = pages::table
+            .filter(page_url.eq(self.page_url.clone()))
+            .select(uuid)
+            .load::<String>(db)?;
+
+        if taken_by.iter().any(|taken_by| Some(taken_by.as_str()) != id) {
+            return Err(CustomHttpError::Conflict(vec![FieldError::new(
+                "page_url",
+                "taken",
+                "Another page already has this url.",
+            )]));
+        }
+
+        Ok(())
+    }
+}
+
 impl Model<Page, MutPage, String, PageDTO> for Page {
-    fn create(new_page: &MutPage, db: &MysqlConnection) -> Result<usize, diesel::result::Error> {
-        Ok(diesel::insert_or_ignore_into(pages::table)
+    fn create(new_page: &MutPage, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        Ok(diesel::insert_into(pages::table)
             .values(new_page)
             .execute(db)?)
     }
 
-    fn read_one(_id: String, db: &MysqlConnection) -> Result<PageDTO, diesel::result::Error> {
+    fn read_one(_id: String, db: &DbConnection) -> Result<PageDTO, diesel::result::Error> {
         use pages::dsl::uuid;
 
         let res = pages::table.filter(uuid.eq(_id)).first::<Self>(db)?.into();
@@ -121,7 +188,7 @@ impl Model<Page, MutPage, String, PageDTO> for Page {
         Ok(res)
     }
 
-    fn read_all(db: &MysqlConnection) -> Result<Vec<PageDTO>, diesel::result::Error> {
+    fn read_all(db: &DbConnection) -> Result<Vec<PageDTO>, diesel::result::Error> {
         let res = pages::table.load::<Self>(db)?.into_iter().map(|x| x.into()).collect();
 
         Ok(res)
@@ -130,26 +197,173 @@ impl Model<Page, MutPage, String, PageDTO> for Page {
     fn update(
         _id: String,
         new_page: &MutPage,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error> {
-        use pages::dsl::uuid;
+        use pages::dsl::{uuid, version};
 
         Ok(diesel::update(pages::table.filter(uuid.eq(_id)))
-            .set(new_page)
+            .set((new_page, version.eq(version + 1)))
             .execute(db)?)
     }
 
-    fn delete(_id: String, db: &MysqlConnection) -> Result<usize, diesel::result::Error> {
+    fn delete(_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
         use pages::dsl::uuid;
 
         Ok(diesel::delete(pages::table.filter(uuid.eq(_id))).execute(db)?)
     }
+
+    fn read_page(
+        query: &ListQuery,
+        db: &DbConnection,
+    ) -> Result<Paginated<PageDTO>, diesel::result::Error> {
+        let (field, desc) = query.sort(Self::SORT_FIELDS[0]);
+        let cursor = query.cursor();
+        let text_cursor = cursor.clone();
+        let time_cursor = cursor
+            .clone()
+            .and_then(|(value, id)| Some((parse_time(&value)?, id)));
+        let limit = query.limit();
+
+        // a boxed query is for one kind of database, so it's built and run on the connection itself.
+        let (total, mut rows) = on_connection!(db, |db| {
+            let total = diesel::RunQueryDsl::get_result::<i64>(Self::filtered(query).count(), db)?;
+
+            let statement = Self::filtered(query);
+            let mut statement = match field {
+                "page_name" => {
+                    sort_and_seek!(statement, pages::page_name, pages::uuid, text_cursor, desc)
+                }
+                "page_url" => {
+                    sort_and_seek!(statement, pages::page_url, pages::uuid, text_cursor, desc)
+                }
+                "page_title" => {
+                    sort_and_seek!(statement, pages::page_title, pages::uuid, text_cursor, desc)
+                }
+                "time_updated" => {
+                    sort_and_seek!(statement, pages::time_updated, pages::uuid, time_cursor, desc)
+                }
+                _ => sort_and_seek!(statement, pages::time_created, pages::uuid, time_cursor, desc),
+            };
+
+            if cursor.is_none() {
+                statement = statement.offset(query.offset());
+            }
+
+            // one extra row tells whether there is a next page.
+            (total, diesel::RunQueryDsl::load::<Self>(statement.limit(limit + 1), db)?)
+        });
+
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last()
+                .map(|page| encode_cursor(&page.sort_value(field), &page.uuid))
+        } else {
+            None
+        };
+
+        Ok(Paginated {
+            items: rows.into_iter().map(|page| page.into()).collect(),
+            total,
+            next_cursor,
+        })
+    }
 }
 
 impl Page {
+    /// Fields pages can be sorted on. The first is the default.
+    pub const SORT_FIELDS: &'static [&'static str] = &[
+        "time_created",
+        "time_updated",
+        "page_name",
+        "page_url",
+        "page_title",
+    ];
+
+    /// Pages matching the filters of a listing, which are the title prefix and the created and updated ranges.
+    fn filtered<DB>(query: &ListQuery) -> pages::BoxedQuery<'static, DB>
+    where
+        DB: Backend + HasSqlType<Text> + HasSqlType<Timestamp> + 'static,
+        String: ToSql<Text, DB>,
+        NaiveDateTime: ToSql<Timestamp, DB>,
+    {
+        let mut statement = pages::table.into_boxed();
+
+        if let Some(title) = query.title_prefix() {
+            statement = statement.filter(pages::page_title.like(title).escape('\\'));
+        }
+        if let Some(after) = query.created_after {
+            statement = statement.filter(pages::time_created.ge(after));
+        }
+        if let Some(before) = query.created_before {
+            statement = statement.filter(pages::time_created.lt(before));
+        }
+        if let Some(after) = query.updated_after {
+            statement = statement.filter(pages::time_updated.ge(after));
+        }
+        if let Some(before) = query.updated_before {
+            statement = statement.filter(pages::time_updated.lt(before));
+        }
+
+        statement
+    }
+
+    pub fn sort_value(&self, field: &str) -> String {
+        match field {
+            "page_name" => self.page_name.clone(),
+            "page_url" => self.page_url.clone(),
+            "page_title" => self.page_title.clone(),
+            "time_updated" => format_time(&self.time_updated),
+            _ => format_time(&self.time_created),
+        }
+    }
+
+    /// Updates the page only if it's still at `expected_version`. `Ok(0)` means someone else saved it first.
+    pub fn update_versioned(
+        _id: String,
+        expected_version: i32,
+        new_page: &MutPage,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use pages::dsl::{uuid, version};
+
+        diesel::update(
+            pages::table
+                .filter(uuid.eq(_id))
+                .filter(version.eq(expected_version)),
+        )
+        .set((new_page, version.eq(version + 1)))
+        .execute(db)
+    }
+
+    /// Deletes the page only if it's still at `expected_version`. `Ok(0)` means it changed since it was read.
+    pub fn delete_versioned(
+        _id: String,
+        expected_version: i32,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use pages::dsl::{uuid, version};
+
+        diesel::delete(
+            pages::table
+                .filter(uuid.eq(_id))
+                .filter(version.eq(expected_version)),
+        )
+        .execute(db)
+    }
+
+    /// Bumps `time_updated` for changes that don't write to the page's own row, such as removing one of its modules.
+    /// This keeps the page's `Last-Modified` moving forward.
+    pub fn touch(_id: &str, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use pages::dsl::{time_updated, uuid};
+
+        diesel::update(pages::table.filter(uuid.eq(_id)))
+            .set(time_updated.eq(diesel::dsl::now))
+            .execute(db)
+    }
+
     pub fn read_one_join_on(
         _id: String,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<PageModuleDTO, diesel::result::Error> {
         use pages::dsl::uuid;
         use modules::dsl::category_uuid;
@@ -192,7 +406,7 @@ impl Page {
     /// This is used for displaying a page, rather than getting a page's modules/array modules.
     pub fn read_one_join_on_url(
         id: String,
-        db: &MysqlConnection,
+        db: &DbConnection,
     ) -> Result<(Self, FieldsDTO), diesel::result::Error> {
         use crate::schema::pages::dsl::page_url;
 
@@ -0,0 +1,146 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+use crate::services::errors_service::{CustomHttpError, FieldError};
+
+pub const DEFAULT_LIMIT: i64 = 100;
+pub const MAX_LIMIT: i64 = 500;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Paging, sorting and filtering options for list endpoints, read from the query string.
+/// Each model only applies the filters that make sense for it.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Returned by the previous page's `next` link. Takes precedence over `offset`.
+    pub cursor: Option<String>,
+    /// Name of the field to sort on. A leading `-` sorts descending.
+    pub sort: Option<String>,
+    pub page_uuid: Option<String>,
+    /// Matches titles starting with this.
+    pub title: Option<String>,
+    pub created_after: Option<NaiveDateTime>,
+    pub created_before: Option<NaiveDateTime>,
+    pub updated_after: Option<NaiveDateTime>,
+    pub updated_before: Option<NaiveDateTime>,
+}
+
+/// One page of a listing.
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Number of rows matching the filters, across every page.
+    pub total: i64,
+    /// Cursor for the page after this one, if there is one.
+    pub next_cursor: Option<String>,
+}
+
+impl ListQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+
+    /// The field to sort on and whether it's descending.
+    pub fn sort<'a>(&'a self, default: &'a str) -> (&'a str, bool) {
+        match self.sort.as_deref() {
+            Some(sort) => match sort.strip_prefix('-') {
+                Some(field) => (field, true),
+                None => (sort, false),
+            },
+            None => (default, false),
+        }
+    }
+
+    /// The sort value and uuid of the last row of the previous page.
+    pub fn cursor(&self) -> Option<(String, String)> {
+        let cursor = self.cursor.as_ref()?;
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()?;
+
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Checks the sort field is one of `sortable` and that the cursor can be read.
+    pub fn validate(&self, sortable: &[&str]) -> Result<(), CustomHttpError> {
+        let (field, _) = self.sort(sortable[0]);
+        let mut errors = Vec::new();
+
+        if !sortable.contains(&field) {
+            errors.push(FieldError::new(
+                "sort",
+                "invalid",
+                format!("Must be one of {:?}.", sortable),
+            ));
+        }
+        if self.cursor.is_some() && self.cursor().is_none() {
+            errors.push(FieldError::new("cursor", "invalid", "Isn't a cursor from a `Link` header."));
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(CustomHttpError::Invalid(errors)),
+        }
+    }
+
+    /// Escapes `title` for use in a `LIKE` prefix match.
+    pub fn title_prefix(&self) -> Option<String> {
+        self.title.as_ref().map(|title| {
+            let escaped = title
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("{}%", escaped)
+        })
+    }
+}
+
+/// Builds the opaque cursor that continues a listing after a row.
+pub fn encode_cursor(sort_value: &str, uuid: &str) -> String {
+    serde_json::to_vec(&(sort_value, uuid))
+        .unwrap_or_default()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn format_time(time: &NaiveDateTime) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+pub fn parse_time(time: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok()
+}
+
+/// Sorts a boxed query on `$column` with `$uuid` as the tie breaker,
+/// then skips past the cursor's row if there is one.
+/// `$cursor` is an `Option<(value, uuid)>` where the value has the column's type.
+macro_rules! sort_and_seek {
+    ($query:expr, $column:expr, $uuid:expr, $cursor:expr, $desc:expr) => {{
+        let mut query = $query;
+
+        if let Some((value, id)) = $cursor {
+            query = if $desc {
+                query.filter($column.lt(value.clone()).or($column.eq(value).and($uuid.lt(id))))
+            } else {
+                query.filter($column.gt(value.clone()).or($column.eq(value).and($uuid.gt(id))))
+            };
+        }
+
+        if $desc {
+            query.order(($column.desc(), $uuid.desc()))
+        } else {
+            query.order(($column.asc(), $uuid.asc()))
+        }
+    }};
+}
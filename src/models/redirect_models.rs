@@ -0,0 +1,242 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{DbConnection, Model, RunQueryDsl, ValidateRows};
+use crate::services::errors_service::{CustomHttpError, FieldError};
+use crate::schema::redirects;
+use crate::services::validation_service::validate_path;
+
+/// Status codes a redirect can respond with. 410 marks content as permanently removed.
+pub const REDIRECT_STATUSES: [i32; 3] = [301, 302, 410];
+
+#[derive(Identifiable, Debug, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone)]
+#[primary_key(uuid)]
+#[table_name = "redirects"]
+pub struct Redirect {
+    pub uuid: String,
+    /// The path that is matched against the request, such as `/old-page`.
+    pub source_path: String,
+    /// Path or full url to redirect to. Empty for 410s.
+    pub target: Option<String>,
+    pub status_code: i32,
+    /// How many times this redirect has been followed.
+    pub hits: i64,
+    pub time_created: NaiveDateTime,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone, Debug)]
+#[table_name = "redirects"]
+pub struct MutRedirect {
+    pub uuid: Option<String>,
+    pub source_path: String,
+    pub target: Option<String>,
+    #[serde(default = "MutRedirect::default_status")]
+    pub status_code: i32,
+}
+
+impl MutRedirect {
+    pub fn default_status() -> i32 {
+        301
+    }
+
+    pub fn validate(&self) -> Result<(), CustomHttpError> {
+        let target_ok = match (self.status_code, &self.target) {
+            (410, _) => true,
+            (_, Some(target)) => !target.is_empty() && target != &self.source_path,
+            (_, None) => false,
+        };
+        let mut errors = Vec::new();
+
+        // matched against request paths, which are checked the same way page urls are.
+        if validate_path(&self.source_path).is_err() {
+            errors.push(FieldError::new(
+                "source_path",
+                "invalid",
+                "Must start with `/` and can't contain spaces, `?` or `#`.",
+            ));
+        }
+        if !REDIRECT_STATUSES.contains(&self.status_code) {
+            errors.push(FieldError::new(
+                "status_code",
+                "invalid",
+                format!("Must be one of {:?}.", REDIRECT_STATUSES),
+            ));
+        }
+        if !target_ok {
+            errors.push(FieldError::new(
+                "target",
+                "invalid",
+                "Required unless `status_code` is 410, and can't be `source_path`.",
+            ));
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(CustomHttpError::Invalid(errors)),
+        }
+    }
+}
+
+impl ValidateRows for MutRedirect {
+    /// A redirect can't lead back to its own source through the redirects already saved,
+    /// or visitors would be sent around in circles.
+    fn validate_rows(&self, id: Option<&str>, db: &DbConnection) -> Result<(), CustomHttpError> {
+        use redirects::dsl::{source_path, status_code, target, uuid};
+
+        let mut next = match self.status_code {
+            410 => None,
+            _ => self.target.clone(),
+        };
+        let mut seen = Vec::new();
+
+        // full urls leave the site, so only paths can lead to another redirect.
+        while let Some(path) = next.filter(|path| path.starts_with('/') && !seen.contains(path)) {
+            if path == self.source_path {
+                return Err(CustomHttpError::Invalid(vec![FieldError::new(
+                    "target",
+                    "loop",
+                    "Leads back to `source_path` through other redirects.",
+                )]));
+            }
+
+            // the redirect this one replaces or updates doesn't count.
+            let found = redirects::table
+                .filter(source_path.eq(path.clone()))
+                .filter(source_path.ne(self.source_path.clone()))
+                .filter(status_code.ne(410))
+                .select((uuid, target))
+                .first::<(String, Option<String>)>(db)
+                .optional()?;
+
+            next = found
+                .filter(|(found_uuid, _)| Some(found_uuid.as_str()) != id)
+                .and_then(|(_, found_target)| found_target);
+            seen.push(path);
+        }
+
+        Ok(())
+    }
+}
+
+impl Model<Self, MutRedirect, String> for Redirect {
+    fn create(new: &MutRedirect, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(redirects::table)
+            .values(new)
+            .execute(db)
+    }
+
+    fn read_one(_id: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use redirects::dsl::uuid;
+
+        redirects::table.filter(uuid.eq(_id)).first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        use redirects::dsl::source_path;
+
+        redirects::table.order(source_path.asc()).load::<Self>(db)
+    }
+
+    fn update(
+        _id: String,
+        new: &MutRedirect,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use redirects::dsl::uuid;
+
+        diesel::update(redirects::table.filter(uuid.eq(_id)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use redirects::dsl::uuid;
+
+        diesel::delete(redirects::table.filter(uuid.eq(_id))).execute(db)
+    }
+}
+
+impl Redirect {
+    /// Finds the redirect for a request path and counts the hit.
+    pub fn follow(path: &str, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use redirects::dsl::{hits, source_path, uuid};
+
+        let redirect = redirects::table
+            .filter(source_path.eq(path))
+            .first::<Self>(db)?;
+
+        diesel::update(redirects::table.filter(uuid.eq(redirect.uuid.clone())))
+            .set(hits.eq(hits + 1))
+            .execute(db)?;
+
+        Ok(redirect)
+    }
+
+    /// Called when a page moves from `old_url` to `new_url`.
+    /// Adds a 301 from the old url, points existing redirects at the new url so they don't chain,
+    /// and removes any redirect away from the new url since it is now a live page.
+    pub fn page_moved(
+        old_url: &str,
+        new_url: &str,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use redirects::dsl::{source_path, target};
+
+        db.transaction(|| {
+            diesel::delete(redirects::table.filter(source_path.eq(new_url))).execute(db)?;
+
+            diesel::update(redirects::table.filter(target.eq(old_url)))
+                .set(target.eq(new_url))
+                .execute(db)?;
+
+            let redirect = MutRedirect {
+                uuid: Some(uuid::Uuid::new_v4().to_string()),
+                source_path: old_url.to_string(),
+                target: Some(new_url.to_string()),
+                status_code: 301,
+            };
+
+            Self::replace(&redirect, db)
+        })
+    }
+
+    /// Inserts a redirect, or replaces the one with the same source path.
+    fn replace(redirect: &MutRedirect, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        match db {
+            #[cfg(feature = "mysql")]
+            DbConnection::Mysql(db) => {
+                diesel::RunQueryDsl::execute(diesel::replace_into(redirects::table).values(redirect), db)
+            }
+            #[cfg(feature = "postgres")]
+            DbConnection::Postgres(db) => diesel::RunQueryDsl::execute(
+                diesel::insert_into(redirects::table)
+                    .values(redirect)
+                    .on_conflict(redirects::source_path)
+                    .do_update()
+                    .set(redirect),
+                db,
+            ),
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(db) => {
+                diesel::RunQueryDsl::execute(diesel::replace_into(redirects::table).values(redirect), db)
+            }
+        }
+    }
+
+    /// Inserts every redirect, replacing existing ones with the same source path.
+    /// Each one is checked for loops against the ones before it. Nothing is written if any of them fail.
+    pub fn import(
+        new_redirects: &[MutRedirect],
+        db: &DbConnection,
+    ) -> Result<usize, CustomHttpError> {
+        db.transaction(|| {
+            for redirect in new_redirects {
+                redirect.validate_rows(None, db)?;
+                Self::replace(redirect, db)?;
+            }
+
+            Ok(new_redirects.len())
+        })
+    }
+}
@@ -0,0 +1,300 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::page_models::{Page, PageDTO};
+use super::{DbConnection, Model, RunQueryDsl};
+use crate::schema::page_terms;
+use crate::schema::pages;
+use crate::schema::terms;
+use crate::schema::vocabularies;
+use crate::services::validation_service::validate_slug;
+
+/// A named group of terms, such as `topics` or `regions`.
+#[derive(Identifiable, Debug, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone)]
+#[primary_key(uuid)]
+#[table_name = "vocabularies"]
+pub struct Vocabulary {
+    pub uuid: String,
+    pub name: String,
+    pub title: String,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone, Validate)]
+#[table_name = "vocabularies"]
+pub struct MutVocabulary {
+    pub uuid: Option<String>,
+    /// Used in the url, such as `topics` in `/v1/taxonomy/vocabularies/topics`.
+    #[validate(custom = "validate_slug")]
+    pub name: String,
+    pub title: String,
+}
+
+#[derive(
+    Debug, Identifiable, Associations, Serialize, Deserialize, Queryable, Insertable, PartialEq, Clone,
+)]
+#[belongs_to(Vocabulary, foreign_key = "vocabulary_uuid")]
+#[primary_key(uuid)]
+#[table_name = "terms"]
+pub struct Term {
+    pub uuid: String,
+    pub vocabulary_uuid: String,
+    /// Terms without a parent are at the top of their vocabulary.
+    pub parent_uuid: Option<String>,
+    pub slug: String,
+    pub title: String,
+}
+
+#[derive(Insertable, AsChangeset, Deserialize, Serialize, Clone)]
+#[table_name = "terms"]
+pub struct MutTerm {
+    pub uuid: Option<String>,
+    pub vocabulary_uuid: String,
+    pub parent_uuid: Option<String>,
+    pub slug: String,
+    pub title: String,
+}
+
+/// The assignment of a term to a page.
+#[derive(
+    Debug, Identifiable, Associations, Insertable, Queryable, Serialize, Deserialize, Clone,
+)]
+#[belongs_to(Page, foreign_key = "page_uuid")]
+#[belongs_to(Term, foreign_key = "term_uuid")]
+#[primary_key(page_uuid, term_uuid)]
+#[table_name = "page_terms"]
+pub struct PageTerm {
+    pub page_uuid: String,
+    pub term_uuid: String,
+}
+
+/// A term along with all of the terms below it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TermTreeDTO {
+    pub uuid: String,
+    pub slug: String,
+    pub title: String,
+    pub children: Vec<TermTreeDTO>,
+}
+
+impl Model<Self, MutVocabulary, String> for Vocabulary {
+    fn create(new: &MutVocabulary, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(vocabularies::table)
+            .values(new)
+            .execute(db)
+    }
+
+    fn read_one(_name: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use vocabularies::dsl::name;
+
+        vocabularies::table.filter(name.eq(_name)).first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        vocabularies::table.load::<Self>(db)
+    }
+
+    fn update(
+        _name: String,
+        new: &MutVocabulary,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use vocabularies::dsl::name;
+
+        diesel::update(vocabularies::table.filter(name.eq(_name)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_name: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use vocabularies::dsl::name;
+
+        diesel::delete(vocabularies::table.filter(name.eq(_name))).execute(db)
+    }
+}
+
+impl Vocabulary {
+    /// Builds the nested tree of every term in this vocabulary.
+    pub fn read_tree(
+        &self,
+        db: &DbConnection,
+    ) -> Result<Vec<TermTreeDTO>, diesel::result::Error> {
+        let all_terms = Term::belonging_to(self).load::<Term>(db)?;
+
+        Ok(Term::tree(&all_terms, None))
+    }
+}
+
+impl Model<Self, MutTerm, String> for Term {
+    fn create(new: &MutTerm, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        diesel::insert_into(terms::table).values(new).execute(db)
+    }
+
+    fn read_one(_id: String, db: &DbConnection) -> Result<Self, diesel::result::Error> {
+        use terms::dsl::uuid;
+
+        terms::table.filter(uuid.eq(_id)).first::<Self>(db)
+    }
+
+    fn read_all(db: &DbConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        terms::table.load::<Self>(db)
+    }
+
+    fn update(
+        _id: String,
+        new: &MutTerm,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use terms::dsl::uuid;
+
+        diesel::update(terms::table.filter(uuid.eq(_id)))
+            .set(new)
+            .execute(db)
+    }
+
+    fn delete(_id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use terms::dsl::uuid;
+
+        diesel::delete(terms::table.filter(uuid.eq(_id))).execute(db)
+    }
+}
+
+impl Term {
+    fn tree(all_terms: &[Term], parent: Option<&String>) -> Vec<TermTreeDTO> {
+        all_terms
+            .iter()
+            .filter(|t| t.parent_uuid.as_ref() == parent)
+            .map(|t| TermTreeDTO {
+                uuid: t.uuid.clone(),
+                slug: t.slug.clone(),
+                title: t.title.clone(),
+                children: Self::tree(all_terms, Some(&t.uuid)),
+            })
+            .collect()
+    }
+
+    /// Reads a term by its slug within a vocabulary.
+    pub fn read_one_by_slug(
+        vocabulary: &Vocabulary,
+        term_slug: String,
+        db: &DbConnection,
+    ) -> Result<Self, diesel::result::Error> {
+        use terms::dsl::slug;
+
+        Self::belonging_to(vocabulary)
+            .filter(slug.eq(term_slug))
+            .first::<Self>(db)
+    }
+
+    /// Returns the uuids of this term and every term below it.
+    pub fn descendants(&self, db: &DbConnection) -> Result<Vec<String>, diesel::result::Error> {
+        use terms::dsl::vocabulary_uuid;
+
+        let all_terms = terms::table
+            .filter(vocabulary_uuid.eq(self.vocabulary_uuid.clone()))
+            .load::<Term>(db)?;
+
+        let mut found = vec![self.uuid.clone()];
+        let mut index = 0;
+
+        while index < found.len() {
+            let current = found[index].clone();
+            for term in all_terms.iter() {
+                if term.parent_uuid.as_ref() == Some(&current) && !found.contains(&term.uuid) {
+                    found.push(term.uuid.clone());
+                }
+            }
+            index += 1;
+        }
+
+        Ok(found)
+    }
+
+    /// Checks that a term's parent exists in the same vocabulary and is not the term itself or one of its children.
+    pub fn valid_parent(
+        new: &MutTerm,
+        current: Option<&Term>,
+        db: &DbConnection,
+    ) -> Result<bool, diesel::result::Error> {
+        let parent_uuid = match &new.parent_uuid {
+            Some(parent_uuid) => parent_uuid,
+            None => return Ok(true),
+        };
+
+        let parent = Self::read_one(parent_uuid.clone(), db)?;
+
+        if parent.vocabulary_uuid != new.vocabulary_uuid {
+            return Ok(false);
+        }
+
+        match current {
+            Some(term) => Ok(!term.descendants(db)?.contains(parent_uuid)),
+            None => Ok(true),
+        }
+    }
+
+    /// Lists every page that has this term or one of its children.
+    pub fn read_pages(&self, db: &DbConnection) -> Result<Vec<PageDTO>, diesel::result::Error> {
+        use page_terms::dsl::term_uuid;
+
+        let term_uuids = self.descendants(db)?;
+
+        let res = pages::table
+            .inner_join(page_terms::table)
+            .filter(term_uuid.eq_any(term_uuids))
+            .select(pages::all_columns)
+            .distinct()
+            .load::<Page>(db)?
+            .into_iter()
+            .map(|p| p.into())
+            .collect();
+
+        Ok(res)
+    }
+
+    /// Lists the terms assigned to a page.
+    pub fn read_for_page(
+        _page_uuid: String,
+        db: &DbConnection,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        use page_terms::dsl::page_uuid;
+
+        terms::table
+            .inner_join(page_terms::table)
+            .filter(page_uuid.eq(_page_uuid))
+            .select(terms::all_columns)
+            .load::<Self>(db)
+    }
+
+    /// Assigning a term the page already has does nothing.
+    pub fn assign(new: &PageTerm, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        match db {
+            #[cfg(feature = "mysql")]
+            DbConnection::Mysql(db) => {
+                diesel::RunQueryDsl::execute(diesel::insert_or_ignore_into(page_terms::table).values(new), db)
+            }
+            #[cfg(feature = "postgres")]
+            DbConnection::Postgres(db) => diesel::RunQueryDsl::execute(
+                diesel::insert_into(page_terms::table)
+                    .values(new)
+                    .on_conflict_do_nothing(),
+                db,
+            ),
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(db) => {
+                diesel::RunQueryDsl::execute(diesel::insert_or_ignore_into(page_terms::table).values(new), db)
+            }
+        }
+    }
+
+    pub fn unassign(old: &PageTerm, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use page_terms::dsl::{page_uuid, term_uuid};
+
+        diesel::delete(
+            page_terms::table
+                .filter(page_uuid.eq(old.page_uuid.clone()))
+                .filter(term_uuid.eq(old.term_uuid.clone())),
+        )
+        .execute(db)
+    }
+}
@@ -1,8 +1,15 @@
-use super::Model;
+use super::{DbConnection, Model, RunQueryDsl, ValidateRows};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 use crate::schema::users;
+use crate::services::errors_service::{CustomHttpError, FieldError};
+use crate::services::validation_service::{validate_role, validate_username};
+
+/// Admins manage users as well as content. Editors only manage content.
+pub const ROLES: [&str; 2] = ["admin", "editor"];
+pub const ADMIN: &str = "admin";
 
 #[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
 #[primary_key("uuid")]
@@ -12,36 +19,76 @@ pub struct User {
     pub username: String,
     pub password: String,
     pub token: Option<String>,
+    pub role: String,
 }
 
-#[derive(Debug, AsChangeset, Insertable, Clone, Serialize, Deserialize)]
+#[derive(Debug, AsChangeset, Insertable, Clone, Serialize, Deserialize, Validate)]
 #[table_name = "users"]
 pub struct MutUser {
     pub uuid: Option<String>,
+    #[validate(
+        length(min = 1, max = 255, message = "Must be 1 to 255 characters."),
+        custom = "validate_username"
+    )]
     pub username: String,
+    /// Only checked when creating or updating a user, logging in takes any password.
+    #[validate(
+        required,
+        length(min = 8, max = 128, message = "Must be 8 to 128 characters.")
+    )]
     pub password: Option<String>,
     pub token: Option<String>,
+    /// Only set from the command line. Left out, new users get the database's default of `admin`.
+    #[serde(skip)]
+    #[validate(custom = "validate_role")]
+    pub role: Option<String>,
+}
+
+impl ValidateRows for MutUser {
+    /// Users are found by username, so no two users can share one. `id` is the current username.
+    fn validate_rows(&self, id: Option<&str>, db: &DbConnection) -> Result<(), CustomHttpError> {
+        use users::dsl::username;
+
+        if id == Some(self.username.as_str()) {
+            return Ok(());
+        }
+
+        let count = users::table
+            .filter(username.eq(&self.username))
+            .count()
+            .get_result::<i64>(db)?;
+
+        if count > 0 {
+            return Err(CustomHttpError::Conflict(vec![FieldError::new(
+                "username",
+                "taken",
+                "Another user already has this username.",
+            )]));
+        }
+
+        Ok(())
+    }
 }
 
 impl Model<User, MutUser, String> for User {
-    fn create(new: &MutUser, db: &diesel::MysqlConnection) -> Result<usize, diesel::result::Error> {
+    fn create(new: &MutUser, db: &DbConnection) -> Result<usize, diesel::result::Error> {
         diesel::insert_into(users::table).values(new).execute(db)
     }
 
-    fn read_one(id: String, db: &diesel::MysqlConnection) -> Result<User, diesel::result::Error> {
+    fn read_one(id: String, db: &DbConnection) -> Result<User, diesel::result::Error> {
         use users::dsl::username;
 
         Ok(users::table.filter(username.eq(id)).first::<User>(db)?)
     }
 
-    fn read_all(_: &diesel::MysqlConnection) -> Result<Vec<User>, diesel::result::Error> {
+    fn read_all(_: &DbConnection) -> Result<Vec<User>, diesel::result::Error> {
         unimplemented!()
     }
 
     fn update(
         id: String,
         new: &MutUser,
-        db: &diesel::MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error> {
         use users::dsl::username;
         let update = diesel::update(users::table.filter(username.eq(id)))
@@ -51,15 +98,17 @@ impl Model<User, MutUser, String> for User {
         Ok(update)
     }
 
-    fn delete(_: String, _: &diesel::MysqlConnection) -> Result<usize, diesel::result::Error> {
-        todo!()
+    fn delete(id: String, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use users::dsl::username;
+
+        diesel::delete(users::table.filter(username.eq(id))).execute(db)
     }
 }
 
 impl User {
     pub fn update_with_token(
         new: &MutUser,
-        db: &diesel::MysqlConnection,
+        db: &DbConnection,
     ) -> Result<usize, diesel::result::Error> {
         use users::dsl::username;
 
@@ -69,4 +118,25 @@ impl User {
 
         Ok(res)
     }
+
+    /// Sets a new password hash and logs the user out.
+    pub fn reset_password(
+        name: &str,
+        new_password: &str,
+        db: &DbConnection,
+    ) -> Result<usize, diesel::result::Error> {
+        use users::dsl::{password, token, username};
+
+        diesel::update(users::table.filter(username.eq(name)))
+            .set((password.eq(new_password), token.eq(None::<String>)))
+            .execute(db)
+    }
+
+    pub fn set_role(name: &str, new_role: &str, db: &DbConnection) -> Result<usize, diesel::result::Error> {
+        use users::dsl::{role, username};
+
+        diesel::update(users::table.filter(username.eq(name)))
+            .set(role.eq(new_role))
+            .execute(db)
+    }
 }
@@ -0,0 +1,833 @@
+use std::ops::Deref;
+use std::sync::{Mutex, MutexGuard};
+use validator::Validate;
+
+use super::{CollectionRepository, MenuRepository, RedirectRepository, Repository, TaxonomyRepository, UserRepository};
+use crate::models::collection_models::{Collection, CollectionEntry, EntryQuery, MutCollection, MutCollectionEntry};
+use crate::models::menu_models::{Menu, MenuDTO, MenuItem, MutMenu, MutMenuItem};
+use crate::models::module_models::{FieldsDTO, Module, ModuleCategory, MutCategory, MutModule};
+use crate::models::page_models::{MutPage, Page, PageDTO, PageModuleDTO};
+use crate::models::pagination::{ListQuery, Paginated};
+use crate::models::redirect_models::{MutRedirect, Redirect};
+use crate::models::taxonomy_models::{MutTerm, MutVocabulary, PageTerm, Term, TermTreeDTO, Vocabulary};
+use crate::models::user_models::{MutUser, User};
+use crate::models::{checkout, DbConnection, DbPool, DbPooledConnection, Model, ValidateRows};
+use crate::services::auth_service::encrypt_password;
+use crate::services::errors_service::{CustomHttpError, FieldError};
+use crate::services::validation_service::validate;
+
+/// Keeps everything in the database, through the models.
+pub struct DieselRepository {
+    source: Source,
+}
+
+enum Source {
+    Pool(DbPool),
+    /// The connection a transaction is open on, which every call uses until it ends.
+    Transaction(Mutex<DbPooledConnection>),
+}
+
+/// A connection from the pool, or the one of the transaction.
+enum Checkout<'a> {
+    Pooled(DbPooledConnection),
+    Transaction(MutexGuard<'a, DbPooledConnection>),
+}
+
+impl Deref for Checkout<'_> {
+    type Target = DbConnection;
+
+    fn deref(&self) -> &DbConnection {
+        match self {
+            Checkout::Pooled(db) => db,
+            Checkout::Transaction(db) => db,
+        }
+    }
+}
+
+impl DieselRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            source: Source::Pool(pool),
+        }
+    }
+
+    fn connection(&self) -> Result<Checkout<'_>, CustomHttpError> {
+        match &self.source {
+            Source::Pool(pool) => Ok(Checkout::Pooled(checkout(pool)?)),
+            // a panic while holding the lock already ends the transaction, so a poisoned lock is still usable.
+            Source::Transaction(db) => Ok(Checkout::Transaction(db.lock().unwrap_or_else(|e| e.into_inner()))),
+        }
+    }
+}
+
+impl Repository for DieselRepository {
+    fn create_page(&self, new: &MutPage) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        create_page(new, &db)
+    }
+
+    fn read_page(&self, id: &str) -> Result<PageDTO, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Page::read_one(id.to_string(), &db)?)
+    }
+
+    fn read_page_with_modules(&self, id: &str) -> Result<PageModuleDTO, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Page::read_one_join_on(id.to_string(), &db)?)
+    }
+
+    fn read_page_tags(&self, id: &str) -> Result<Vec<Term>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Term::read_for_page(id.to_string(), &db)?)
+    }
+
+    fn list_pages(&self, query: &ListQuery) -> Result<Paginated<PageDTO>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Page::read_page(query, &db)?)
+    }
+
+    fn update_page(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutPage,
+    ) -> Result<(PageDTO, i32), CustomHttpError> {
+        let db = self.connection()?;
+
+        update_page(id, expected_version, new, &db)
+    }
+
+    fn delete_page(&self, id: &str, expected_version: Option<i32>) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        delete_page(id, expected_version, &db)
+    }
+
+    fn find_page_by_url(&self, url: &str) -> Result<(Page, FieldsDTO), CustomHttpError> {
+        let db = self.connection()?;
+
+        Page::read_one_join_on_url(url.to_string(), &db).map_err(|e| {
+            // the details are lost once this is a `CustomHttpError`, so anything but a missing page is logged here.
+            if e != diesel::result::Error::NotFound {
+                log::error!("Failed to read page `{}`: {}", url, e);
+            }
+
+            e.into()
+        })
+    }
+
+    fn follow_redirect(&self, path: &str) -> Result<Redirect, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Redirect::follow(path, &db)?)
+    }
+
+    fn create_module(&self, new: &MutModule) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        create_module(new, &db)
+    }
+
+    fn read_module(&self, id: &str) -> Result<Module, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Module::read_one(id.to_string(), &db)?)
+    }
+
+    fn list_modules(&self, query: &ListQuery) -> Result<Paginated<Module>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Module::read_page(query, &db)?)
+    }
+
+    fn update_module(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutModule,
+    ) -> Result<(Module, i32), CustomHttpError> {
+        let db = self.connection()?;
+
+        update_module(id, expected_version, new, &db)
+    }
+
+    fn delete_module(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+    ) -> Result<(Module, usize), CustomHttpError> {
+        let db = self.connection()?;
+
+        delete_module(id, expected_version, &db)
+    }
+
+    fn read_category(&self, id: &str) -> Result<ModuleCategory, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(ModuleCategory::read_one(id.to_string(), &db)?)
+    }
+
+    fn read_category_modules(&self, id: &str) -> Result<Vec<Module>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(ModuleCategory::join(id.to_string(), &db)?)
+    }
+
+    fn create_category(&self, new: &MutCategory) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        create_category(new, &db)
+    }
+
+    fn update_category(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutCategory,
+    ) -> Result<(ModuleCategory, i32), CustomHttpError> {
+        let db = self.connection()?;
+
+        update_category(id, expected_version, new, &db)
+    }
+
+    fn delete_category(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+    ) -> Result<(ModuleCategory, usize), CustomHttpError> {
+        let db = self.connection()?;
+
+        delete_category(id, expected_version, &db)
+    }
+
+    fn transaction(
+        &self,
+        work: &mut dyn FnMut(&dyn Repository) -> Result<(), CustomHttpError>,
+    ) -> Result<(), CustomHttpError> {
+        let pool = match &self.source {
+            Source::Pool(pool) => pool,
+            // every call already goes through the open transaction.
+            Source::Transaction(_) => return work(self),
+        };
+
+        let db = checkout(pool)?;
+        db.begin_transaction()?;
+
+        let repository = Self {
+            source: Source::Transaction(Mutex::new(db)),
+        };
+        let res = work(&repository);
+
+        let db = match repository.source {
+            Source::Transaction(db) => db.into_inner().unwrap_or_else(|e| e.into_inner()),
+            Source::Pool(_) => unreachable!(),
+        };
+
+        match res {
+            Ok(()) => Ok(db.commit_transaction()?),
+            Err(e) => {
+                db.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl CollectionRepository for DieselRepository {
+    fn create_collection(&self, new: &MutCollection) -> Result<(), CustomHttpError> {
+        new.validate()?;
+        let db = self.connection()?;
+
+        Collection::create(new, &db)?;
+        Ok(())
+    }
+
+    fn list_collections(&self) -> Result<Vec<Collection>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Collection::read_all(&db)?)
+    }
+
+    fn read_collection(&self, name: &str) -> Result<Collection, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Collection::read_one(name.to_string(), &db)?)
+    }
+
+    fn update_collection(&self, name: &str, new: &MutCollection) -> Result<usize, CustomHttpError> {
+        new.validate()?;
+        let db = self.connection()?;
+
+        Ok(Collection::update(name.to_string(), new, &db)?)
+    }
+
+    fn delete_collection(&self, name: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Collection::delete(name.to_string(), &db)?)
+    }
+
+    fn create_entry(&self, name: &str, new: &MutCollectionEntry) -> Result<MutCollectionEntry, CustomHttpError> {
+        let db = self.connection()?;
+        let collection = Collection::read_one(name.to_string(), &db)?;
+
+        let mut entry = new.clone();
+        entry.collection_uuid = collection.uuid.clone();
+
+        collection
+            .validate_entry(&entry)
+            .map_err(|e| CustomHttpError::Invalid(vec![e]))?;
+
+        CollectionEntry::create(&entry, &db)?;
+        Ok(entry)
+    }
+
+    fn list_entries(
+        &self,
+        name: &str,
+        query: &ListQuery,
+        status: Option<String>,
+    ) -> Result<Paginated<CollectionEntry>, CustomHttpError> {
+        let db = self.connection()?;
+        let collection = Collection::read_one(name.to_string(), &db)?;
+
+        // content fields can be sorted on too, so the fields are known once the collection is read.
+        let sort_fields = collection.sort_fields();
+        query.validate(&sort_fields.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+        let entry_query = EntryQuery::from_list(query, status);
+        Ok(CollectionEntry::query(&collection, &entry_query, &db)?)
+    }
+
+    fn read_entry(&self, name: &str, id: &str) -> Result<CollectionEntry, CustomHttpError> {
+        let db = self.connection()?;
+        let collection = Collection::read_one(name.to_string(), &db)?;
+
+        Ok(CollectionEntry::read_one_in(&collection, id.to_string(), &db)?)
+    }
+
+    fn update_entry(
+        &self,
+        name: &str,
+        id: &str,
+        new: &MutCollectionEntry,
+    ) -> Result<MutCollectionEntry, CustomHttpError> {
+        let db = self.connection()?;
+        let collection = Collection::read_one(name.to_string(), &db)?;
+        // makes sure the entry is part of this collection before changing it.
+        CollectionEntry::read_one_in(&collection, id.to_string(), &db)?;
+
+        let mut entry = new.clone();
+        entry.collection_uuid = collection.uuid.clone();
+
+        collection
+            .validate_entry(&entry)
+            .map_err(|e| CustomHttpError::Invalid(vec![e]))?;
+
+        CollectionEntry::update(id.to_string(), &entry, &db)?;
+        Ok(entry)
+    }
+
+    fn delete_entry(&self, name: &str, id: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+        let collection = Collection::read_one(name.to_string(), &db)?;
+        CollectionEntry::read_one_in(&collection, id.to_string(), &db)?;
+
+        Ok(CollectionEntry::delete(id.to_string(), &db)?)
+    }
+}
+
+impl TaxonomyRepository for DieselRepository {
+    fn create_vocabulary(&self, new: &MutVocabulary) -> Result<(), CustomHttpError> {
+        new.validate()?;
+        let db = self.connection()?;
+
+        Vocabulary::create(new, &db)?;
+        Ok(())
+    }
+
+    fn list_vocabularies(&self) -> Result<Vec<Vocabulary>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Vocabulary::read_all(&db)?)
+    }
+
+    fn read_vocabulary(&self, name: &str) -> Result<Vocabulary, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Vocabulary::read_one(name.to_string(), &db)?)
+    }
+
+    fn read_vocabulary_terms(&self, name: &str) -> Result<Vec<TermTreeDTO>, CustomHttpError> {
+        let db = self.connection()?;
+        let vocabulary = Vocabulary::read_one(name.to_string(), &db)?;
+
+        Ok(vocabulary.read_tree(&db)?)
+    }
+
+    fn update_vocabulary(&self, name: &str, new: &MutVocabulary) -> Result<usize, CustomHttpError> {
+        new.validate()?;
+        let db = self.connection()?;
+
+        Ok(Vocabulary::update(name.to_string(), new, &db)?)
+    }
+
+    fn delete_vocabulary(&self, name: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Vocabulary::delete(name.to_string(), &db)?)
+    }
+
+    fn create_term(&self, new: &MutTerm) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        if !Term::valid_parent(new, None, &db)? {
+            return Err(invalid_term_parent());
+        }
+
+        Term::create(new, &db)?;
+        Ok(())
+    }
+
+    fn read_term(&self, id: &str) -> Result<Term, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Term::read_one(id.to_string(), &db)?)
+    }
+
+    fn update_term(&self, id: &str, new: &MutTerm) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+        let current = Term::read_one(id.to_string(), &db)?;
+
+        if !Term::valid_parent(new, Some(&current), &db)? {
+            return Err(invalid_term_parent());
+        }
+        // the terms below it would be left under a parent in another vocabulary.
+        if new.vocabulary_uuid != current.vocabulary_uuid && current.descendants(&db)?.len() > 1 {
+            return Err(CustomHttpError::Invalid(vec![FieldError::new(
+                "vocabulary_uuid",
+                "invalid",
+                "Can't move a term with terms below it to another vocabulary.",
+            )]));
+        }
+
+        Ok(Term::update(id.to_string(), new, &db)?)
+    }
+
+    fn delete_term(&self, id: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Term::delete(id.to_string(), &db)?)
+    }
+
+    fn read_term_pages(&self, id: &str) -> Result<Vec<PageDTO>, CustomHttpError> {
+        let db = self.connection()?;
+        let term = Term::read_one(id.to_string(), &db)?;
+
+        Ok(term.read_pages(&db)?)
+    }
+
+    fn assign_page_term(&self, page_term: &PageTerm) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        // both must exist, otherwise this is a 404 rather than a foreign key error.
+        let _: PageDTO = Page::read_one(page_term.page_uuid.clone(), &db)?;
+        Term::read_one(page_term.term_uuid.clone(), &db)?;
+
+        Term::assign(page_term, &db)?;
+        Ok(())
+    }
+
+    fn unassign_page_term(&self, page_term: &PageTerm) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Term::unassign(page_term, &db)?)
+    }
+}
+
+impl MenuRepository for DieselRepository {
+    fn create_menu(&self, new: &MutMenu) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        Menu::create(new, &db)?;
+        Ok(())
+    }
+
+    fn list_menus(&self) -> Result<Vec<Menu>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Menu::read_all(&db)?)
+    }
+
+    fn read_menu_tree(&self, name: &str, current_url: Option<&str>) -> Result<MenuDTO, CustomHttpError> {
+        let db = self.connection()?;
+        let menu = Menu::read_one(name.to_string(), &db)?;
+
+        Ok(menu.read_tree(current_url, &db)?)
+    }
+
+    fn update_menu(&self, name: &str, new: &MutMenu) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Menu::update(name.to_string(), new, &db)?)
+    }
+
+    fn delete_menu(&self, name: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Menu::delete(name.to_string(), &db)?)
+    }
+
+    fn create_menu_item(&self, name: &str, new: &MutMenuItem) -> Result<MutMenuItem, CustomHttpError> {
+        let db = self.connection()?;
+        let menu = Menu::read_one(name.to_string(), &db)?;
+
+        let mut item = new.clone();
+        item.menu_uuid = menu.uuid;
+
+        if !item.has_single_target() {
+            return Err(invalid_item_target());
+        }
+        if !MenuItem::valid_parent(&item, None, &db)? {
+            return Err(invalid_item_parent());
+        }
+
+        MenuItem::create(&item, &db)?;
+        Ok(item)
+    }
+
+    fn update_menu_item(&self, name: &str, id: &str, new: &MutMenuItem) -> Result<MutMenuItem, CustomHttpError> {
+        let db = self.connection()?;
+        let menu = Menu::read_one(name.to_string(), &db)?;
+        let current = MenuItem::read_one_in(&menu, id.to_string(), &db)?;
+
+        let mut item = new.clone();
+        item.uuid = id.to_string();
+        item.menu_uuid = menu.uuid;
+
+        if !item.has_single_target() {
+            return Err(invalid_item_target());
+        }
+        if !MenuItem::valid_parent(&item, Some(&current), &db)? {
+            return Err(invalid_item_parent());
+        }
+
+        MenuItem::update(id.to_string(), &item, &db)?;
+        Ok(item)
+    }
+
+    fn delete_menu_item(&self, name: &str, id: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+        let menu = Menu::read_one(name.to_string(), &db)?;
+        MenuItem::read_one_in(&menu, id.to_string(), &db)?;
+
+        Ok(MenuItem::delete(id.to_string(), &db)?)
+    }
+}
+
+impl RedirectRepository for DieselRepository {
+    fn create_redirect(&self, new: &MutRedirect) -> Result<(), CustomHttpError> {
+        new.validate()?;
+        let db = self.connection()?;
+
+        new.validate_rows(None, &db)?;
+        Redirect::create(new, &db)?;
+        Ok(())
+    }
+
+    fn list_redirects(&self) -> Result<Vec<Redirect>, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Redirect::read_all(&db)?)
+    }
+
+    fn read_redirect(&self, id: &str) -> Result<Redirect, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Redirect::read_one(id.to_string(), &db)?)
+    }
+
+    fn update_redirect(&self, id: &str, new: &MutRedirect) -> Result<usize, CustomHttpError> {
+        new.validate()?;
+        let db = self.connection()?;
+
+        new.validate_rows(Some(id), &db)?;
+        Ok(Redirect::update(id.to_string(), new, &db)?)
+    }
+
+    fn delete_redirect(&self, id: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(Redirect::delete(id.to_string(), &db)?)
+    }
+
+    fn import_redirects(&self, new: &[MutRedirect]) -> Result<usize, CustomHttpError> {
+        for redirect in new {
+            redirect.validate()?;
+        }
+        let db = self.connection()?;
+
+        Redirect::import(new, &db)
+    }
+}
+
+impl UserRepository for DieselRepository {
+    fn create_user(&self, new: &MutUser) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        // the password's length is checked before it's hashed.
+        validate(new, None, &db)?;
+        User::create(&hashed(new)?, &db)?;
+
+        Ok(())
+    }
+
+    fn read_user(&self, username: &str) -> Result<User, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(User::read_one(username.to_string(), &db)?)
+    }
+
+    fn update_user(&self, username: &str, new: &MutUser) -> Result<(), CustomHttpError> {
+        let db = self.connection()?;
+
+        validate(new, Some(username), &db)?;
+        User::update(username.to_string(), &hashed(new)?, &db)?;
+
+        Ok(())
+    }
+
+    fn delete_user(&self, username: &str) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(User::delete(username.to_string(), &db)?)
+    }
+
+    fn set_token(&self, user: &MutUser) -> Result<usize, CustomHttpError> {
+        let db = self.connection()?;
+
+        Ok(User::update_with_token(user, &db)?)
+    }
+}
+
+fn create_page(new: &MutPage, db: &DbConnection) -> Result<(), CustomHttpError> {
+    validate(new, None, db)?;
+    Page::create(new, db)?;
+
+    Ok(())
+}
+
+/// Moving the page to another url also moves its redirects.
+/// Returns the page as it was before the update, and its new version.
+fn update_page(
+    id: &str,
+    expected_version: Option<i32>,
+    new: &MutPage,
+    db: &DbConnection,
+) -> Result<(PageDTO, i32), CustomHttpError> {
+    validate(new, Some(id), db)?;
+
+    db.transaction::<_, CustomHttpError, _>(|| {
+        let old_page: PageDTO = Page::read_one(id.to_string(), db)?;
+
+        let updated = match expected_version {
+            Some(expected_version) => Page::update_versioned(id.to_string(), expected_version, new, db)?,
+            None => Page::update(id.to_string(), new, db)?,
+        };
+
+        // the page exists, so nothing being updated means it was saved by someone else first.
+        if updated == 0 {
+            return Err(CustomHttpError::PreconditionFailed);
+        }
+
+        // keep inbound links to the old url working.
+        if old_page.page_url != new.page_url {
+            Redirect::page_moved(&old_page.page_url, &new.page_url, db)?;
+        }
+
+        let new_page: PageDTO = Page::read_one(id.to_string(), db)?;
+        Ok((old_page, new_page.version))
+    })
+}
+
+fn delete_page(id: &str, expected_version: Option<i32>, db: &DbConnection) -> Result<usize, CustomHttpError> {
+    let _: PageDTO = Page::read_one(id.to_string(), db)?;
+
+    let res = match expected_version {
+        Some(expected_version) => Page::delete_versioned(id.to_string(), expected_version, db)?,
+        None => Page::delete(id.to_string(), db)?,
+    };
+
+    if res == 0 {
+        return Err(CustomHttpError::PreconditionFailed);
+    }
+
+    Ok(res)
+}
+
+fn create_module(new: &MutModule, db: &DbConnection) -> Result<(), CustomHttpError> {
+    validate(new, None, db)?;
+
+    db.transaction::<_, CustomHttpError, _>(|| {
+        Module::create(new, db)?;
+        Page::touch(&new.page_uuid, db)?;
+
+        Ok(())
+    })
+}
+
+/// Returns the module as it was before the update, and its new version.
+fn update_module(
+    id: &str,
+    expected_version: Option<i32>,
+    new: &MutModule,
+    db: &DbConnection,
+) -> Result<(Module, i32), CustomHttpError> {
+    validate(new, Some(id), db)?;
+
+    db.transaction::<_, CustomHttpError, _>(|| {
+        let old_module = Module::read_one(id.to_string(), db)?;
+
+        let updated = match expected_version {
+            Some(expected_version) => Module::update_versioned(id.to_string(), expected_version, new, db)?,
+            None => Module::update(id.to_string(), new, db)?,
+        };
+
+        // the module exists, so nothing being updated means it was saved by someone else first.
+        if updated == 0 {
+            return Err(CustomHttpError::PreconditionFailed);
+        }
+
+        // the module may be moving to another page, so both pages are touched.
+        Page::touch(&old_module.page_uuid, db)?;
+        Page::touch(&new.page_uuid, db)?;
+
+        let new_module = Module::read_one(id.to_string(), db)?;
+        Ok((old_module, new_module.version))
+    })
+}
+
+/// Returns the deleted module and the number of rows deleted.
+fn delete_module(
+    id: &str,
+    expected_version: Option<i32>,
+    db: &DbConnection,
+) -> Result<(Module, usize), CustomHttpError> {
+    db.transaction::<_, CustomHttpError, _>(|| {
+        let old_module = Module::read_one(id.to_string(), db)?;
+
+        let res = match expected_version {
+            Some(expected_version) => Module::delete_versioned(id.to_string(), expected_version, db)?,
+            None => Module::delete(id.to_string(), db)?,
+        };
+
+        if res == 0 {
+            return Err(CustomHttpError::PreconditionFailed);
+        }
+
+        Page::touch(&old_module.page_uuid, db)?;
+
+        Ok((old_module, res))
+    })
+}
+
+fn create_category(new: &MutCategory, db: &DbConnection) -> Result<(), CustomHttpError> {
+    validate(new, None, db)?;
+
+    db.transaction::<_, CustomHttpError, _>(|| {
+        ModuleCategory::create(new, db)?;
+        Page::touch(&new.page_uuid, db)?;
+
+        Ok(())
+    })
+}
+
+/// Returns the category as it was before the update, and its new version.
+fn update_category(
+    id: &str,
+    expected_version: Option<i32>,
+    new: &MutCategory,
+    db: &DbConnection,
+) -> Result<(ModuleCategory, i32), CustomHttpError> {
+    validate(new, Some(id), db)?;
+
+    db.transaction::<_, CustomHttpError, _>(|| {
+        let old_category = ModuleCategory::read_one(id.to_string(), db)?;
+
+        let updated = match expected_version {
+            Some(expected_version) => ModuleCategory::update_versioned(id.to_string(), expected_version, new, db)?,
+            None => ModuleCategory::update(id.to_string(), new, db)?,
+        };
+
+        // the category exists, so nothing being updated means it was saved by someone else first.
+        if updated == 0 {
+            return Err(CustomHttpError::PreconditionFailed);
+        }
+
+        Page::touch(&old_category.page_uuid, db)?;
+        Page::touch(&new.page_uuid, db)?;
+
+        let new_category = ModuleCategory::read_one(id.to_string(), db)?;
+        Ok((old_category, new_category.version))
+    })
+}
+
+/// Returns the deleted category and the number of rows deleted.
+fn delete_category(
+    id: &str,
+    expected_version: Option<i32>,
+    db: &DbConnection,
+) -> Result<(ModuleCategory, usize), CustomHttpError> {
+    db.transaction::<_, CustomHttpError, _>(|| {
+        let old_category = ModuleCategory::read_one(id.to_string(), db)?;
+
+        let res = match expected_version {
+            Some(expected_version) => ModuleCategory::delete_versioned(id.to_string(), expected_version, db)?,
+            None => ModuleCategory::delete(id.to_string(), db)?,
+        };
+
+        if res == 0 {
+            return Err(CustomHttpError::PreconditionFailed);
+        }
+
+        Page::touch(&old_category.page_uuid, db)?;
+
+        Ok((old_category, res))
+    })
+}
+
+/// `user` with its password hashed, ready to be saved.
+fn hashed(user: &MutUser) -> Result<MutUser, CustomHttpError> {
+    let mut user = user.clone();
+    user.password = Some(encrypt_password(&user.password.clone().unwrap_or_default())?);
+
+    Ok(user)
+}
+
+fn invalid_term_parent() -> CustomHttpError {
+    CustomHttpError::Invalid(vec![FieldError::new(
+        "parent_uuid",
+        "invalid",
+        "Must be another term of the same vocabulary that isn't below this one.",
+    )])
+}
+
+fn invalid_item_target() -> CustomHttpError {
+    CustomHttpError::Invalid(vec![
+        FieldError::new("page_uuid", "invalid", "Set exactly one of `page_uuid` or `url`."),
+        FieldError::new("url", "invalid", "Set exactly one of `page_uuid` or `url`."),
+    ])
+}
+
+fn invalid_item_parent() -> CustomHttpError {
+    CustomHttpError::Invalid(vec![FieldError::new(
+        "parent_uuid",
+        "invalid",
+        "Must be another item of the same menu that isn't below this one.",
+    )])
+}
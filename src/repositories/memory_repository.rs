@@ -0,0 +1,736 @@
+use actix_web::web;
+use chrono::{NaiveDateTime, Utc};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::{RedirectRepository, Repositories, Repository};
+use crate::models::module_models::{CategoryDTO, FieldsDTO, Module, ModuleCategory, MutCategory, MutModule};
+use crate::models::page_models::{MutPage, Page, PageDTO, PageModuleDTO};
+use crate::models::pagination::{encode_cursor, ListQuery, Paginated};
+use crate::models::redirect_models::{MutRedirect, Redirect};
+use crate::models::taxonomy_models::Term;
+use crate::models::DbPool;
+use crate::services::errors_service::{CustomHttpError, FieldError};
+
+#[derive(Default, Clone)]
+struct Rows {
+    pages: Vec<Page>,
+    categories: Vec<ModuleCategory>,
+    modules: Vec<Module>,
+    redirects: Vec<Redirect>,
+}
+
+/// Keeps pages, modules, categories and redirects in memory, for testing controllers and rendering without a database.
+/// It follows the same rules as the database: unique page urls, modules on pages that exist,
+/// deleting a page deletes its modules and versions move on every update.
+/// Taxonomy isn't kept, so pages have no tags.
+#[derive(Default)]
+pub struct MemoryRepository {
+    rows: Mutex<Rows>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shares this repository for content and redirects, and the database behind `pool` for everything else.
+    pub fn repositories(self: &Arc<Self>, pool: DbPool) -> Repositories {
+        Repositories {
+            content: web::Data::from(self.clone() as Arc<dyn Repository>),
+            redirects: web::Data::from(self.clone() as Arc<dyn RedirectRepository>),
+            ..Repositories::diesel(pool)
+        }
+    }
+
+    fn rows(&self) -> MutexGuard<'_, Rows> {
+        // a panic while holding the lock can't leave the rows half written, so a poisoned lock is still usable.
+        self.rows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn taken(field: &str, message: &str) -> CustomHttpError {
+    CustomHttpError::Conflict(vec![FieldError::new(field, "taken", message)])
+}
+
+fn missing(field: &str, message: &str) -> CustomHttpError {
+    CustomHttpError::Invalid(vec![FieldError::new(field, "not_found", message)])
+}
+
+/// `None` matches any version.
+fn check_version(version: i32, expected_version: Option<i32>) -> Result<(), CustomHttpError> {
+    match expected_version {
+        Some(expected_version) if expected_version != version => Err(CustomHttpError::PreconditionFailed),
+        _ => Ok(()),
+    }
+}
+
+/// Sorts, seeks and pages `rows` the way `sort_and_seek!` does in SQL, so cursors work the same.
+fn paginate<T>(
+    mut rows: Vec<T>,
+    query: &ListQuery,
+    desc: bool,
+    sort_value: impl Fn(&T) -> (String, String),
+) -> Paginated<T> {
+    let total = rows.len() as i64;
+    let cursor = query.cursor();
+
+    rows.sort_by_key(|row| sort_value(row));
+    if desc {
+        rows.reverse();
+    }
+
+    let rows = rows.into_iter().filter(|row| match &cursor {
+        Some((value, id)) => {
+            let (row_value, row_id) = sort_value(row);
+            match desc {
+                true => (&row_value, &row_id) < (value, id),
+                false => (&row_value, &row_id) > (value, id),
+            }
+        }
+        None => true,
+    });
+    let offset = match cursor {
+        Some(_) => 0,
+        None => query.offset() as usize,
+    };
+
+    // one extra row tells whether there is a next page.
+    let limit = query.limit() as usize;
+    let mut rows: Vec<T> = rows.skip(offset).take(limit + 1).collect();
+
+    let next_cursor = if rows.len() > limit {
+        rows.truncate(limit);
+        rows.last().map(|row| {
+            let (value, id) = sort_value(row);
+            encode_cursor(&value, &id)
+        })
+    } else {
+        None
+    };
+
+    Paginated {
+        items: rows,
+        total,
+        next_cursor,
+    }
+}
+
+fn in_range(time: NaiveDateTime, after: Option<NaiveDateTime>, before: Option<NaiveDateTime>) -> bool {
+    let after_ok = match after {
+        Some(after) => time >= after,
+        None => true,
+    };
+    let before_ok = match before {
+        Some(before) => time < before,
+        None => true,
+    };
+
+    after_ok && before_ok
+}
+
+/// Whether `title` starts with the `title` filter of a listing.
+fn title_matches(title: &str, query: &ListQuery) -> bool {
+    match &query.title {
+        Some(prefix) => title.starts_with(prefix.as_str()),
+        None => true,
+    }
+}
+
+impl Rows {
+    fn page(&self, id: &str) -> Result<&Page, CustomHttpError> {
+        self.pages.iter().find(|p| p.uuid == id).ok_or(CustomHttpError::NotFound)
+    }
+
+    fn page_mut(&mut self, id: &str) -> Result<&mut Page, CustomHttpError> {
+        self.pages.iter_mut().find(|p| p.uuid == id).ok_or(CustomHttpError::NotFound)
+    }
+
+    fn module(&self, id: &str) -> Result<&Module, CustomHttpError> {
+        self.modules.iter().find(|m| m.uuid == id).ok_or(CustomHttpError::NotFound)
+    }
+
+    /// The same checks as `ValidateRows for MutPage`.
+    fn validate_page(&self, new: &MutPage, id: Option<&str>) -> Result<(), CustomHttpError> {
+        new.validate()?;
+
+        let taken_url = self
+            .pages
+            .iter()
+            .any(|p| p.page_url == new.page_url && Some(p.uuid.as_str()) != id);
+
+        match taken_url {
+            true => Err(taken("page_url", "Another page already has this url.")),
+            false => Ok(()),
+        }
+    }
+
+    /// The same checks as `ValidateRows for MutModule`.
+    fn validate_module(&self, new: &MutModule) -> Result<(), CustomHttpError> {
+        new.validate()?;
+
+        if self.page(&new.page_uuid).is_err() {
+            return Err(missing("page_uuid", "No page has this uuid."));
+        }
+
+        if let Some(category_uuid) = &new.category_uuid {
+            let on_page = self
+                .categories
+                .iter()
+                .any(|c| &c.uuid == category_uuid && c.page_uuid == new.page_uuid);
+
+            if !on_page {
+                return Err(missing("category_uuid", "No category on this page has this uuid."));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn category(&self, id: &str) -> Result<&ModuleCategory, CustomHttpError> {
+        self.categories.iter().find(|c| c.uuid == id).ok_or(CustomHttpError::NotFound)
+    }
+
+    fn redirect(&self, id: &str) -> Result<&Redirect, CustomHttpError> {
+        self.redirects.iter().find(|r| r.uuid == id).ok_or(CustomHttpError::NotFound)
+    }
+
+    /// The same checks as `ValidateRows for MutCategory`.
+    fn validate_category(&self, new: &MutCategory) -> Result<(), CustomHttpError> {
+        new.validate()?;
+
+        match self.page(&new.page_uuid) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(missing("page_uuid", "No page has this uuid.")),
+        }
+    }
+
+    /// The same checks as `MutRedirect::validate` and `ValidateRows for MutRedirect`.
+    fn validate_redirect(&self, new: &MutRedirect, id: Option<&str>) -> Result<(), CustomHttpError> {
+        new.validate()?;
+
+        let mut next = match new.status_code {
+            410 => None,
+            _ => new.target.clone(),
+        };
+        let mut seen = Vec::new();
+
+        while let Some(path) = next.filter(|path| path.starts_with('/') && !seen.contains(path)) {
+            if path == new.source_path {
+                return Err(CustomHttpError::Invalid(vec![FieldError::new(
+                    "target",
+                    "loop",
+                    "Leads back to `source_path` through other redirects.",
+                )]));
+            }
+
+            next = self
+                .redirects
+                .iter()
+                .filter(|r| r.source_path == path && r.source_path != new.source_path)
+                .filter(|r| r.status_code != 410 && Some(r.uuid.as_str()) != id)
+                .find_map(|r| r.target.clone());
+            seen.push(path);
+        }
+
+        Ok(())
+    }
+
+    /// Like the unique source path of the database, only one redirect other than `id` can have `source_path`.
+    fn check_source_path(&self, source_path: &str, id: Option<&str>) -> Result<(), CustomHttpError> {
+        let taken_path = self
+            .redirects
+            .iter()
+            .any(|r| r.source_path == source_path && Some(r.uuid.as_str()) != id);
+
+        match taken_path {
+            true => Err(taken("source_path", "Another redirect already has this source path.")),
+            false => Ok(()),
+        }
+    }
+
+    fn category_modules(&self, category_uuid: &str) -> Vec<Module> {
+        self.modules
+            .iter()
+            .filter(|m| m.category_uuid.as_deref() == Some(category_uuid))
+            .cloned()
+            .collect()
+    }
+
+    /// The same as `Redirect::page_moved`.
+    fn page_moved(&mut self, old_url: &str, new_url: &str) {
+        self.redirects.retain(|r| r.source_path != new_url);
+
+        for redirect in self.redirects.iter_mut() {
+            if redirect.target.as_deref() == Some(old_url) {
+                redirect.target = Some(new_url.to_string());
+            }
+        }
+
+        self.redirects.retain(|r| r.source_path != old_url);
+        self.redirects.push(Redirect {
+            uuid: Uuid::new_v4().to_string(),
+            source_path: old_url.to_string(),
+            target: Some(new_url.to_string()),
+            status_code: 301,
+            hits: 0,
+            time_created: now(),
+        });
+    }
+}
+
+impl Repository for MemoryRepository {
+    fn create_page(&self, new: &MutPage) -> Result<(), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_page(new, None)?;
+
+        let uuid = new.uuid.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        if rows.page(&uuid).is_ok() {
+            return Err(taken("uuid", "Another page already has this uuid."));
+        }
+
+        rows.pages.push(Page {
+            uuid,
+            page_name: new.page_name.clone(),
+            page_url: new.page_url.clone(),
+            page_title: new.page_title.clone(),
+            time_created: now(),
+            time_updated: now(),
+            cache_control: new.cache_control.clone().flatten(),
+            version: 1,
+        });
+
+        Ok(())
+    }
+
+    fn read_page(&self, id: &str) -> Result<PageDTO, CustomHttpError> {
+        Ok(self.rows().page(id)?.clone().into())
+    }
+
+    fn read_page_with_modules(&self, id: &str) -> Result<PageModuleDTO, CustomHttpError> {
+        let rows = self.rows();
+        let mut page: PageModuleDTO = rows.page(id)?.clone().into();
+
+        page.fields = FieldsDTO {
+            modules: rows
+                .modules
+                .iter()
+                .filter(|m| m.page_uuid == id && m.category_uuid.is_none())
+                .cloned()
+                .collect(),
+            categories: Some(
+                rows.categories
+                    .iter()
+                    .filter(|c| c.page_uuid == id)
+                    .map(|c| CategoryDTO {
+                        uuid: c.uuid.clone(),
+                        title: c.title.clone(),
+                        modules: rows.category_modules(&c.uuid),
+                    })
+                    .collect(),
+            ),
+        };
+
+        Ok(page)
+    }
+
+    fn read_page_tags(&self, id: &str) -> Result<Vec<Term>, CustomHttpError> {
+        self.rows().page(id)?;
+
+        Ok(Vec::new())
+    }
+
+    fn list_pages(&self, query: &ListQuery) -> Result<Paginated<PageDTO>, CustomHttpError> {
+        let rows: Vec<Page> = self
+            .rows()
+            .pages
+            .iter()
+            .filter(|p| title_matches(&p.page_title, query))
+            .filter(|p| in_range(p.time_created, query.created_after, query.created_before))
+            .filter(|p| in_range(p.time_updated, query.updated_after, query.updated_before))
+            .cloned()
+            .collect();
+
+        let (field, desc) = query.sort(Page::SORT_FIELDS[0]);
+        let pages = paginate(rows, query, desc, |p| (p.sort_value(field), p.uuid.clone()));
+
+        Ok(Paginated {
+            items: pages.items.into_iter().map(|p| p.into()).collect(),
+            total: pages.total,
+            next_cursor: pages.next_cursor,
+        })
+    }
+
+    fn update_page(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutPage,
+    ) -> Result<(PageDTO, i32), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_page(new, Some(id))?;
+
+        let page = rows.page_mut(id)?;
+        check_version(page.version, expected_version)?;
+        let old_page: PageDTO = page.clone().into();
+
+        let old_url = std::mem::replace(&mut page.page_url, new.page_url.clone());
+        page.page_name = new.page_name.clone();
+        page.page_title = new.page_title.clone();
+        if let Some(cache_control) = &new.cache_control {
+            page.cache_control = cache_control.clone();
+        }
+        page.time_updated = now();
+        page.version += 1;
+        let version = page.version;
+
+        // keep inbound links to the old url working.
+        if old_url != new.page_url {
+            rows.page_moved(&old_url, &new.page_url);
+        }
+
+        Ok((old_page, version))
+    }
+
+    fn delete_page(&self, id: &str, expected_version: Option<i32>) -> Result<usize, CustomHttpError> {
+        let mut rows = self.rows();
+        check_version(rows.page(id)?.version, expected_version)?;
+
+        rows.pages.retain(|p| p.uuid != id);
+        rows.categories.retain(|c| c.page_uuid != id);
+        rows.modules.retain(|m| m.page_uuid != id);
+
+        Ok(1)
+    }
+
+    fn find_page_by_url(&self, url: &str) -> Result<(Page, FieldsDTO), CustomHttpError> {
+        let rows = self.rows();
+        let page = rows
+            .pages
+            .iter()
+            .find(|p| p.page_url == url)
+            .ok_or(CustomHttpError::NotFound)?
+            .clone();
+
+        let modules: Vec<Module> = rows
+            .modules
+            .iter()
+            .filter(|m| m.page_uuid == page.uuid)
+            .cloned()
+            .collect();
+
+        // like the inner join in `read_one_join_on_url`, only categories with modules are listed.
+        let categories = rows
+            .categories
+            .iter()
+            .filter(|c| c.page_uuid == page.uuid)
+            .map(|c| CategoryDTO {
+                uuid: c.uuid.clone(),
+                title: c.title.clone(),
+                modules: rows.category_modules(&c.uuid),
+            })
+            .filter(|c| !c.modules.is_empty())
+            .collect();
+
+        Ok((
+            page,
+            FieldsDTO {
+                modules,
+                categories: Some(categories),
+            },
+        ))
+    }
+
+    fn follow_redirect(&self, path: &str) -> Result<Redirect, CustomHttpError> {
+        let mut rows = self.rows();
+        let redirect = rows
+            .redirects
+            .iter_mut()
+            .find(|r| r.source_path == path)
+            .ok_or(CustomHttpError::NotFound)?;
+
+        let followed = redirect.clone();
+        redirect.hits += 1;
+
+        Ok(followed)
+    }
+
+    fn create_module(&self, new: &MutModule) -> Result<(), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_module(new)?;
+
+        let uuid = new.uuid.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        if rows.module(&uuid).is_ok() {
+            return Err(taken("uuid", "Another module already has this uuid."));
+        }
+
+        rows.modules.push(Module {
+            uuid,
+            page_uuid: new.page_uuid.clone(),
+            category_uuid: new.category_uuid.clone(),
+            title: new.title.clone(),
+            content: new.content.clone(),
+            time_updated: now(),
+            version: 1,
+        });
+        rows.page_mut(&new.page_uuid)?.time_updated = now();
+
+        Ok(())
+    }
+
+    fn read_module(&self, id: &str) -> Result<Module, CustomHttpError> {
+        Ok(self.rows().module(id)?.clone())
+    }
+
+    fn list_modules(&self, query: &ListQuery) -> Result<Paginated<Module>, CustomHttpError> {
+        // like `Module::read_all`, modules inside a category aren't listed.
+        let rows: Vec<Module> = self
+            .rows()
+            .modules
+            .iter()
+            .filter(|m| m.category_uuid.is_none())
+            .filter(|m| match &query.page_uuid {
+                Some(page_uuid) => &m.page_uuid == page_uuid,
+                None => true,
+            })
+            .filter(|m| title_matches(&m.title, query))
+            .filter(|m| in_range(m.time_updated, query.updated_after, query.updated_before))
+            .cloned()
+            .collect();
+
+        let (field, desc) = query.sort(Module::SORT_FIELDS[0]);
+
+        Ok(paginate(rows, query, desc, |m| (m.sort_value(field), m.uuid.clone())))
+    }
+
+    fn update_module(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutModule,
+    ) -> Result<(Module, i32), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_module(new)?;
+
+        let old_module = rows.module(id)?.clone();
+        check_version(old_module.version, expected_version)?;
+
+        let module = rows
+            .modules
+            .iter_mut()
+            .find(|m| m.uuid == id)
+            .ok_or(CustomHttpError::NotFound)?;
+        module.page_uuid = new.page_uuid.clone();
+        module.category_uuid = new.category_uuid.clone();
+        module.title = new.title.clone();
+        module.content = new.content.clone();
+        module.time_updated = now();
+        module.version += 1;
+        let version = module.version;
+
+        // the module may be moving to another page, so both pages are touched.
+        rows.page_mut(&old_module.page_uuid)?.time_updated = now();
+        rows.page_mut(&new.page_uuid)?.time_updated = now();
+
+        Ok((old_module, version))
+    }
+
+    fn delete_module(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+    ) -> Result<(Module, usize), CustomHttpError> {
+        let mut rows = self.rows();
+
+        let old_module = rows.module(id)?.clone();
+        check_version(old_module.version, expected_version)?;
+
+        rows.modules.retain(|m| m.uuid != id);
+        rows.page_mut(&old_module.page_uuid)?.time_updated = now();
+
+        Ok((old_module, 1))
+    }
+
+    fn read_category(&self, id: &str) -> Result<ModuleCategory, CustomHttpError> {
+        self.rows()
+            .categories
+            .iter()
+            .find(|c| c.uuid == id)
+            .cloned()
+            .ok_or(CustomHttpError::NotFound)
+    }
+
+    fn read_category_modules(&self, id: &str) -> Result<Vec<Module>, CustomHttpError> {
+        let rows = self.rows();
+        rows.category(id)?;
+
+        Ok(rows.category_modules(id))
+    }
+
+    fn create_category(&self, new: &MutCategory) -> Result<(), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_category(new)?;
+
+        let uuid = new.uuid.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        if rows.category(&uuid).is_ok() {
+            return Err(taken("uuid", "Another category already has this uuid."));
+        }
+
+        rows.categories.push(ModuleCategory {
+            uuid,
+            page_uuid: new.page_uuid.clone(),
+            title: new.title.clone(),
+            version: 1,
+        });
+        rows.page_mut(&new.page_uuid)?.time_updated = now();
+
+        Ok(())
+    }
+
+    fn update_category(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutCategory,
+    ) -> Result<(ModuleCategory, i32), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_category(new)?;
+
+        let old_category = rows.category(id)?.clone();
+        check_version(old_category.version, expected_version)?;
+
+        let category = rows
+            .categories
+            .iter_mut()
+            .find(|c| c.uuid == id)
+            .ok_or(CustomHttpError::NotFound)?;
+        category.page_uuid = new.page_uuid.clone();
+        category.title = new.title.clone();
+        category.version += 1;
+        let version = category.version;
+
+        rows.page_mut(&old_category.page_uuid)?.time_updated = now();
+        rows.page_mut(&new.page_uuid)?.time_updated = now();
+
+        Ok((old_category, version))
+    }
+
+    fn delete_category(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+    ) -> Result<(ModuleCategory, usize), CustomHttpError> {
+        let mut rows = self.rows();
+
+        let old_category = rows.category(id)?.clone();
+        check_version(old_category.version, expected_version)?;
+
+        // like the foreign key, the modules in the category go with it.
+        rows.categories.retain(|c| c.uuid != id);
+        rows.modules.retain(|m| m.category_uuid.as_deref() != Some(id));
+        rows.page_mut(&old_category.page_uuid)?.time_updated = now();
+
+        Ok((old_category, 1))
+    }
+
+    fn transaction(
+        &self,
+        work: &mut dyn FnMut(&dyn Repository) -> Result<(), CustomHttpError>,
+    ) -> Result<(), CustomHttpError> {
+        // the work runs on a copy, and holding the lock keeps anyone else from writing until it's kept or dropped.
+        let mut rows = self.rows();
+        let draft = Self {
+            rows: Mutex::new(rows.clone()),
+        };
+
+        work(&draft)?;
+        *rows = draft.rows.into_inner().unwrap_or_else(|e| e.into_inner());
+
+        Ok(())
+    }
+}
+
+impl RedirectRepository for MemoryRepository {
+    fn create_redirect(&self, new: &MutRedirect) -> Result<(), CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_redirect(new, None)?;
+        rows.check_source_path(&new.source_path, None)?;
+
+        rows.redirects.push(Redirect {
+            uuid: new.uuid.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
+            source_path: new.source_path.clone(),
+            target: new.target.clone(),
+            status_code: new.status_code,
+            hits: 0,
+            time_created: now(),
+        });
+
+        Ok(())
+    }
+
+    fn list_redirects(&self) -> Result<Vec<Redirect>, CustomHttpError> {
+        let mut redirects = self.rows().redirects.clone();
+        redirects.sort_by(|a, b| a.source_path.cmp(&b.source_path));
+
+        Ok(redirects)
+    }
+
+    fn read_redirect(&self, id: &str) -> Result<Redirect, CustomHttpError> {
+        Ok(self.rows().redirect(id)?.clone())
+    }
+
+    fn update_redirect(&self, id: &str, new: &MutRedirect) -> Result<usize, CustomHttpError> {
+        let mut rows = self.rows();
+        rows.validate_redirect(new, Some(id))?;
+        rows.check_source_path(&new.source_path, Some(id))?;
+
+        // like an update in SQL, a missing redirect updates nothing.
+        let redirect = match rows.redirects.iter_mut().find(|r| r.uuid == id) {
+            Some(redirect) => redirect,
+            None => return Ok(0),
+        };
+        redirect.source_path = new.source_path.clone();
+        redirect.target = new.target.clone();
+        redirect.status_code = new.status_code;
+
+        Ok(1)
+    }
+
+    fn delete_redirect(&self, id: &str) -> Result<usize, CustomHttpError> {
+        let mut rows = self.rows();
+        let count = rows.redirects.len();
+        rows.redirects.retain(|r| r.uuid != id);
+
+        Ok(count - rows.redirects.len())
+    }
+
+    fn import_redirects(&self, new: &[MutRedirect]) -> Result<usize, CustomHttpError> {
+        // nothing is kept if any of them fail.
+        let mut rows = self.rows();
+        let mut draft = rows.clone();
+
+        for redirect in new {
+            draft.validate_redirect(redirect, None)?;
+
+            // like `Redirect::replace`, the one with the same source path is replaced.
+            draft.redirects.retain(|r| r.source_path != redirect.source_path);
+            draft.redirects.push(Redirect {
+                uuid: redirect.uuid.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
+                source_path: redirect.source_path.clone(),
+                target: redirect.target.clone(),
+                status_code: redirect.status_code,
+                hits: 0,
+                time_created: now(),
+            });
+        }
+
+        *rows = draft;
+
+        Ok(new.len())
+    }
+}
@@ -0,0 +1,222 @@
+pub mod diesel_repository;
+#[cfg(test)]
+pub mod memory_repository;
+
+use actix_web::{error::BlockingError, web};
+use std::sync::Arc;
+
+use self::diesel_repository::DieselRepository;
+use crate::models::collection_models::{Collection, CollectionEntry, MutCollection, MutCollectionEntry};
+use crate::models::menu_models::{Menu, MenuDTO, MutMenu, MutMenuItem};
+use crate::models::module_models::{FieldsDTO, Module, ModuleCategory, MutCategory, MutModule};
+use crate::models::page_models::{MutPage, Page, PageDTO, PageModuleDTO};
+use crate::models::pagination::{ListQuery, Paginated};
+use crate::models::redirect_models::{MutRedirect, Redirect};
+use crate::models::taxonomy_models::{MutTerm, MutVocabulary, PageTerm, Term, TermTreeDTO, Vocabulary};
+use crate::models::user_models::{MutUser, User};
+use crate::models::DbPool;
+use crate::services::errors_service::CustomHttpError;
+
+/// Storage for pages, modules and categories, which is everything the page renderer reads
+/// and everything a batch writes.
+/// The controllers get it from `web::Data<dyn Repository>`, so they can run against the database or against memory.
+///
+/// Writes check their input the same way `validation_service::validate` does.
+/// `expected_version` is the version from `If-Match`, and `None` writes whatever the current version is.
+/// A write that finds the row at another version fails with `PreconditionFailed`.
+pub trait Repository: Send + Sync {
+    fn create_page(&self, new: &MutPage) -> Result<(), CustomHttpError>;
+    fn read_page(&self, id: &str) -> Result<PageDTO, CustomHttpError>;
+    /// The page with its modules and categories under `fields`.
+    fn read_page_with_modules(&self, id: &str) -> Result<PageModuleDTO, CustomHttpError>;
+    fn read_page_tags(&self, id: &str) -> Result<Vec<Term>, CustomHttpError>;
+    fn list_pages(&self, query: &ListQuery) -> Result<Paginated<PageDTO>, CustomHttpError>;
+    /// Moving the page to another url also moves its redirects, as `Redirect::page_moved` does.
+    /// Returns the page as it was before the update, and its new version.
+    fn update_page(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutPage,
+    ) -> Result<(PageDTO, i32), CustomHttpError>;
+    fn delete_page(&self, id: &str, expected_version: Option<i32>) -> Result<usize, CustomHttpError>;
+
+    /// The page at `url` with every one of its modules, for rendering.
+    fn find_page_by_url(&self, url: &str) -> Result<(Page, FieldsDTO), CustomHttpError>;
+    /// The redirect away from `path`, counting the hit.
+    fn follow_redirect(&self, path: &str) -> Result<Redirect, CustomHttpError>;
+
+    fn create_module(&self, new: &MutModule) -> Result<(), CustomHttpError>;
+    fn read_module(&self, id: &str) -> Result<Module, CustomHttpError>;
+    fn list_modules(&self, query: &ListQuery) -> Result<Paginated<Module>, CustomHttpError>;
+    /// Returns the module as it was before the update, and its new version.
+    fn update_module(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutModule,
+    ) -> Result<(Module, i32), CustomHttpError>;
+    /// Returns the deleted module and the number of rows deleted.
+    fn delete_module(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+    ) -> Result<(Module, usize), CustomHttpError>;
+
+    fn create_category(&self, new: &MutCategory) -> Result<(), CustomHttpError>;
+    fn read_category(&self, id: &str) -> Result<ModuleCategory, CustomHttpError>;
+    fn read_category_modules(&self, id: &str) -> Result<Vec<Module>, CustomHttpError>;
+    /// Returns the category as it was before the update, and its new version.
+    fn update_category(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+        new: &MutCategory,
+    ) -> Result<(ModuleCategory, i32), CustomHttpError>;
+    /// Returns the deleted category and the number of rows deleted.
+    fn delete_category(
+        &self,
+        id: &str,
+        expected_version: Option<i32>,
+    ) -> Result<(ModuleCategory, usize), CustomHttpError>;
+
+    /// Runs `work` against a repository whose writes are all kept if it returns `Ok`, and all dropped otherwise.
+    /// Nobody else sees them before then. Transactions inside `work` are part of this one.
+    fn transaction(
+        &self,
+        work: &mut dyn FnMut(&dyn Repository) -> Result<(), CustomHttpError>,
+    ) -> Result<(), CustomHttpError>;
+}
+
+/// Collections and their entries. Entries are found by the name of their collection and checked against its fields.
+pub trait CollectionRepository: Send + Sync {
+    fn create_collection(&self, new: &MutCollection) -> Result<(), CustomHttpError>;
+    fn list_collections(&self) -> Result<Vec<Collection>, CustomHttpError>;
+    fn read_collection(&self, name: &str) -> Result<Collection, CustomHttpError>;
+    fn update_collection(&self, name: &str, new: &MutCollection) -> Result<usize, CustomHttpError>;
+    fn delete_collection(&self, name: &str) -> Result<usize, CustomHttpError>;
+
+    /// Returns the entry as it was saved, with its collection's uuid.
+    fn create_entry(&self, name: &str, new: &MutCollectionEntry) -> Result<MutCollectionEntry, CustomHttpError>;
+    /// Entries can be sorted on their columns or on any field of the collection.
+    fn list_entries(
+        &self,
+        name: &str,
+        query: &ListQuery,
+        status: Option<String>,
+    ) -> Result<Paginated<CollectionEntry>, CustomHttpError>;
+    fn read_entry(&self, name: &str, id: &str) -> Result<CollectionEntry, CustomHttpError>;
+    /// Returns the entry as it was saved, with its collection's uuid.
+    fn update_entry(
+        &self,
+        name: &str,
+        id: &str,
+        new: &MutCollectionEntry,
+    ) -> Result<MutCollectionEntry, CustomHttpError>;
+    fn delete_entry(&self, name: &str, id: &str) -> Result<usize, CustomHttpError>;
+}
+
+/// Vocabularies, their terms and which pages have them. Vocabularies are found by name.
+/// A term's parent has to be in the same vocabulary and can't be below the term.
+pub trait TaxonomyRepository: Send + Sync {
+    fn create_vocabulary(&self, new: &MutVocabulary) -> Result<(), CustomHttpError>;
+    fn list_vocabularies(&self) -> Result<Vec<Vocabulary>, CustomHttpError>;
+    fn read_vocabulary(&self, name: &str) -> Result<Vocabulary, CustomHttpError>;
+    /// Every term of the vocabulary, nested below its parent.
+    fn read_vocabulary_terms(&self, name: &str) -> Result<Vec<TermTreeDTO>, CustomHttpError>;
+    fn update_vocabulary(&self, name: &str, new: &MutVocabulary) -> Result<usize, CustomHttpError>;
+    fn delete_vocabulary(&self, name: &str) -> Result<usize, CustomHttpError>;
+
+    fn create_term(&self, new: &MutTerm) -> Result<(), CustomHttpError>;
+    fn read_term(&self, id: &str) -> Result<Term, CustomHttpError>;
+    /// Only a term with nothing below it can move to another vocabulary.
+    fn update_term(&self, id: &str, new: &MutTerm) -> Result<usize, CustomHttpError>;
+    fn delete_term(&self, id: &str) -> Result<usize, CustomHttpError>;
+    /// The pages that have the term or one below it.
+    fn read_term_pages(&self, id: &str) -> Result<Vec<PageDTO>, CustomHttpError>;
+
+    /// The page and the term must both exist. Assigning a term the page already has does nothing.
+    fn assign_page_term(&self, page_term: &PageTerm) -> Result<(), CustomHttpError>;
+    fn unassign_page_term(&self, page_term: &PageTerm) -> Result<usize, CustomHttpError>;
+}
+
+/// Menus and their items. Menus are found by name, and items by their menu's name and their uuid.
+pub trait MenuRepository: Send + Sync {
+    fn create_menu(&self, new: &MutMenu) -> Result<(), CustomHttpError>;
+    fn list_menus(&self) -> Result<Vec<Menu>, CustomHttpError>;
+    /// The menu with its items nested below their parents, marking those that link to `current_url` as active.
+    fn read_menu_tree(&self, name: &str, current_url: Option<&str>) -> Result<MenuDTO, CustomHttpError>;
+    fn update_menu(&self, name: &str, new: &MutMenu) -> Result<usize, CustomHttpError>;
+    fn delete_menu(&self, name: &str) -> Result<usize, CustomHttpError>;
+
+    /// Returns the item as it was saved, with its menu's uuid.
+    fn create_menu_item(&self, name: &str, new: &MutMenuItem) -> Result<MutMenuItem, CustomHttpError>;
+    /// Returns the item as it was saved, with its menu's uuid.
+    fn update_menu_item(&self, name: &str, id: &str, new: &MutMenuItem) -> Result<MutMenuItem, CustomHttpError>;
+    fn delete_menu_item(&self, name: &str, id: &str) -> Result<usize, CustomHttpError>;
+}
+
+/// Redirects, checked with `MutRedirect::validate` and for loops through the redirects already saved.
+/// Following them is up to `Repository::follow_redirect`, since the renderer does it.
+pub trait RedirectRepository: Send + Sync {
+    fn create_redirect(&self, new: &MutRedirect) -> Result<(), CustomHttpError>;
+    /// Every redirect, by source path.
+    fn list_redirects(&self) -> Result<Vec<Redirect>, CustomHttpError>;
+    fn read_redirect(&self, id: &str) -> Result<Redirect, CustomHttpError>;
+    fn update_redirect(&self, id: &str, new: &MutRedirect) -> Result<usize, CustomHttpError>;
+    fn delete_redirect(&self, id: &str) -> Result<usize, CustomHttpError>;
+    /// Saves every redirect, replacing those with the same source path, or none of them if any fail.
+    fn import_redirects(&self, new: &[MutRedirect]) -> Result<usize, CustomHttpError>;
+}
+
+/// Users, found by username. Passwords are given in plain text and hashed once they've been checked.
+pub trait UserRepository: Send + Sync {
+    fn create_user(&self, new: &MutUser) -> Result<(), CustomHttpError>;
+    fn read_user(&self, username: &str) -> Result<User, CustomHttpError>;
+    fn update_user(&self, username: &str, new: &MutUser) -> Result<(), CustomHttpError>;
+    fn delete_user(&self, username: &str) -> Result<usize, CustomHttpError>;
+    /// Saves the token of a login, which every authenticated request is compared against.
+    fn set_token(&self, user: &MutUser) -> Result<usize, CustomHttpError>;
+}
+
+/// One of each repository, as the app shares them with the controllers.
+#[derive(Clone)]
+pub struct Repositories {
+    pub content: web::Data<dyn Repository>,
+    pub collections: web::Data<dyn CollectionRepository>,
+    pub taxonomy: web::Data<dyn TaxonomyRepository>,
+    pub menus: web::Data<dyn MenuRepository>,
+    pub redirects: web::Data<dyn RedirectRepository>,
+    pub users: web::Data<dyn UserRepository>,
+}
+
+impl Repositories {
+    /// Every repository kept in the database behind `pool`.
+    pub fn diesel(pool: DbPool) -> Self {
+        let repository = Arc::new(DieselRepository::new(pool));
+
+        Self {
+            content: web::Data::from(repository.clone() as Arc<dyn Repository>),
+            collections: web::Data::from(repository.clone() as Arc<dyn CollectionRepository>),
+            taxonomy: web::Data::from(repository.clone() as Arc<dyn TaxonomyRepository>),
+            menus: web::Data::from(repository.clone() as Arc<dyn MenuRepository>),
+            redirects: web::Data::from(repository.clone() as Arc<dyn RedirectRepository>),
+            users: web::Data::from(repository as Arc<dyn UserRepository>),
+        }
+    }
+}
+
+/// Runs repository work on actix's blocking thread pool, like `run_query` does for the database.
+pub async fn run_repository<R, F, T>(repository: web::Data<R>, work: F) -> Result<T, CustomHttpError>
+where
+    R: ?Sized + Send + Sync + 'static,
+    F: FnOnce(&R) -> Result<T, CustomHttpError> + Send + 'static,
+    T: Send + 'static,
+{
+    web::block(move || work(&**repository))
+        .await
+        .map_err(|e| match e {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => CustomHttpError::Unknown,
+        })
+}
@@ -0,0 +1,13 @@
+use actix_web::{web, Scope};
+use super::Router;
+
+use crate::controllers::batch_controllers::*;
+
+pub struct BatchRouter;
+
+impl Router for BatchRouter {
+    fn new() -> Scope {
+        web::scope("/batch")
+            .route("", web::post().to(run_batch))
+    }
+}
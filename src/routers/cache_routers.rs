@@ -0,0 +1,14 @@
+use actix_web::{web, Scope};
+use super::Router;
+
+use crate::controllers::cache_controllers::*;
+
+pub struct CacheRouter;
+
+impl Router for CacheRouter {
+    fn new() -> Scope {
+        web::scope("/cache")
+            .route("", web::get().to(get_cache_stats))
+            .route("", web::delete().to(clear_cache))
+    }
+}
@@ -0,0 +1,22 @@
+use super::Router;
+use actix_web::{web, Scope};
+
+use crate::controllers::collection_controllers::*;
+
+pub struct CollectionRouter;
+
+impl Router for CollectionRouter {
+    fn new() -> Scope {
+        web::scope("/collections")
+            .route("", web::post().to(create_collection))
+            .route("", web::get().to(get_collections))
+            .route("/{type}", web::get().to(get_collection))
+            .route("/{type}", web::put().to(update_collection))
+            .route("/{type}", web::delete().to(delete_collection))
+            .route("/{type}/entries", web::post().to(create_entry))
+            .route("/{type}/entries", web::get().to(get_entries))
+            .route("/{type}/entries/{id}", web::get().to(get_entry))
+            .route("/{type}/entries/{id}", web::put().to(update_entry))
+            .route("/{type}/entries/{id}", web::delete().to(delete_entry))
+    }
+}
@@ -0,0 +1,20 @@
+use actix_web::{web, Scope};
+use super::Router;
+
+use crate::controllers::menu_controllers::*;
+
+pub struct MenuRouter;
+
+impl Router for MenuRouter {
+    fn new() -> Scope {
+        web::scope("/menus")
+            .route("", web::post().to(create_menu))
+            .route("", web::get().to(get_menus))
+            .route("/{name}", web::get().to(get_menu))
+            .route("/{name}", web::put().to(update_menu))
+            .route("/{name}", web::delete().to(delete_menu))
+            .route("/{name}/items", web::post().to(create_menu_item))
+            .route("/{name}/items/{id}", web::put().to(update_menu_item))
+            .route("/{name}/items/{id}", web::delete().to(delete_menu_item))
+    }
+}
@@ -1,8 +1,15 @@
 use actix_web::Scope;
 
+pub mod batch_routers;
 pub mod module_routers;
 pub mod page_routers;
+pub mod redirect_routers;
+pub mod cache_routers;
 pub mod category_routers;
+pub mod collection_routers;
+pub mod menu_routers;
+pub mod system_routers;
+pub mod taxonomy_routers;
 pub mod user_routers;
 
 pub trait Router {
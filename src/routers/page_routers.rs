@@ -1,6 +1,7 @@
 use actix_web::{web, Scope};
 
 use crate::controllers::page_controllers::*;
+use crate::controllers::taxonomy_controllers::{assign_page_term, get_page_terms, unassign_page_term};
 
 pub struct PageRouter;
 
@@ -13,5 +14,8 @@ impl PageRouter {
             .route("/{id}/modules", web::get().to(get_page_join_modules))
             .route("/{id}", web::put().to(update_page))
             .route("/{id}", web::delete().to(delete_page))
+            .route("/{id}/terms", web::get().to(get_page_terms))
+            .route("/{id}/terms/{term_id}", web::put().to(assign_page_term))
+            .route("/{id}/terms/{term_id}", web::delete().to(unassign_page_term))
     }
 }
@@ -0,0 +1,13 @@
+use actix_web::{web, Scope};
+use super::Router;
+
+use crate::controllers::system_controllers::*;
+
+pub struct SystemRouter;
+
+impl Router for SystemRouter {
+    fn new() -> Scope {
+        web::scope("/system")
+            .route("/status", web::get().to(get_status))
+    }
+}
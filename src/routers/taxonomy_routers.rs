@@ -0,0 +1,23 @@
+use actix_web::{web, Scope};
+use super::Router;
+
+use crate::controllers::taxonomy_controllers::*;
+
+pub struct TaxonomyRouter;
+
+impl Router for TaxonomyRouter {
+    fn new() -> Scope {
+        web::scope("/taxonomy")
+            .route("/vocabularies", web::post().to(create_vocabulary))
+            .route("/vocabularies", web::get().to(get_vocabularies))
+            .route("/vocabularies/{name}", web::get().to(get_vocabulary))
+            .route("/vocabularies/{name}", web::put().to(update_vocabulary))
+            .route("/vocabularies/{name}", web::delete().to(delete_vocabulary))
+            .route("/vocabularies/{name}/terms", web::get().to(get_vocabulary_terms))
+            .route("/terms", web::post().to(create_term))
+            .route("/terms/{id}", web::get().to(get_term))
+            .route("/terms/{id}", web::put().to(update_term))
+            .route("/terms/{id}", web::delete().to(delete_term))
+            .route("/terms/{id}/pages", web::get().to(get_term_pages))
+    }
+}
@@ -1,3 +1,24 @@
+table! {
+    collection_entries (uuid) {
+        uuid -> Varchar,
+        collection_uuid -> Varchar,
+        slug -> Varchar,
+        status -> Varchar,
+        content -> Text,
+        time_created -> Timestamp,
+        time_updated -> Timestamp,
+    }
+}
+
+table! {
+    collections (uuid) {
+        uuid -> Varchar,
+        name -> Varchar,
+        title -> Varchar,
+        fields -> Text,
+    }
+}
+
 table! {
     modules (uuid) {
         uuid -> Varchar,
@@ -5,6 +26,28 @@ table! {
         category_uuid -> Nullable<Varchar>,
         title -> Varchar,
         content -> Text,
+        time_updated -> Timestamp,
+        version -> Integer,
+    }
+}
+
+table! {
+    menu_items (uuid) {
+        uuid -> Varchar,
+        menu_uuid -> Varchar,
+        parent_uuid -> Nullable<Varchar>,
+        position -> Integer,
+        title -> Varchar,
+        page_uuid -> Nullable<Varchar>,
+        url -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    menus (uuid) {
+        uuid -> Varchar,
+        name -> Varchar,
+        title -> Varchar,
     }
 }
 
@@ -13,6 +56,14 @@ table! {
         uuid -> Varchar,
         page_uuid -> Varchar,
         title -> Varchar,
+        version -> Integer,
+    }
+}
+
+table! {
+    page_terms (page_uuid, term_uuid) {
+        page_uuid -> Varchar,
+        term_uuid -> Varchar,
     }
 }
 
@@ -23,6 +74,38 @@ table! {
         page_url -> Varchar,
         page_title -> Varchar,
         time_created -> Timestamp,
+        time_updated -> Timestamp,
+        cache_control -> Nullable<Varchar>,
+        version -> Integer,
+    }
+}
+
+table! {
+    rate_limit_windows (client_key, window_end) {
+        client_key -> Varchar,
+        window_end -> BigInt,
+        requests -> Integer,
+    }
+}
+
+table! {
+    redirects (uuid) {
+        uuid -> Varchar,
+        source_path -> Varchar,
+        target -> Nullable<Varchar>,
+        status_code -> Integer,
+        hits -> Bigint,
+        time_created -> Timestamp,
+    }
+}
+
+table! {
+    terms (uuid) {
+        uuid -> Varchar,
+        vocabulary_uuid -> Varchar,
+        parent_uuid -> Nullable<Varchar>,
+        slug -> Varchar,
+        title -> Varchar,
     }
 }
 
@@ -32,16 +115,40 @@ table! {
         username -> Varchar,
         password -> Varchar,
         token -> Nullable<Varchar>,
+        role -> Varchar,
+    }
+}
+
+table! {
+    vocabularies (uuid) {
+        uuid -> Varchar,
+        name -> Varchar,
+        title -> Varchar,
     }
 }
 
+joinable!(collection_entries -> collections (collection_uuid));
+joinable!(menu_items -> menus (menu_uuid));
+joinable!(menu_items -> pages (page_uuid));
 joinable!(module_category -> pages (page_uuid));
 joinable!(modules -> module_category (category_uuid));
 joinable!(modules -> pages (page_uuid));
+joinable!(page_terms -> pages (page_uuid));
+joinable!(page_terms -> terms (term_uuid));
+joinable!(terms -> vocabularies (vocabulary_uuid));
 
 allow_tables_to_appear_in_same_query!(
+    collection_entries,
+    collections,
+    menu_items,
+    menus,
     modules,
     module_category,
+    page_terms,
     pages,
+    rate_limit_windows,
+    redirects,
+    terms,
     users,
+    vocabularies,
 );
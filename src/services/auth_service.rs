@@ -1,14 +1,13 @@
 use actix_web::{dev::Payload, http::HeaderValue, web, FromRequest, HttpRequest};
 use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
-use diesel::MysqlConnection;
-use futures::{future::LocalBoxFuture, Future};
+use futures::future::LocalBoxFuture;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::errors_service::CustomHttpError;
-use crate::models::{pool_handler, user_models, Model, MySQLPool};
+use crate::repositories::{run_repository, UserRepository};
 
 #[derive(Error, Debug)]
 pub enum CryptoError {
@@ -65,9 +64,9 @@ pub fn decrypt(jwt: &String) -> Result<Claims, CryptoError> {
 pub fn compare(
     token: &Claims,
     enc_token: &String,
-    pool: &MysqlConnection,
+    users: &dyn UserRepository,
 ) -> Result<(), CryptoError> {
-    if let Ok(user) = user_models::User::read_one(token.sub.clone(), &pool) {
+    if let Ok(user) = users.read_user(&token.sub) {
         if user.token.is_none() {
             return Err(CryptoError::NotLoggedIn);
         }
@@ -82,6 +81,16 @@ pub fn compare(
     }
 }
 
+/// Checks that whoever `token` belongs to has `role`.
+pub fn require_role(token: &Claims, role: &str, users: &dyn UserRepository) -> Result<(), CustomHttpError> {
+    let user = users.read_user(&token.sub)?;
+
+    match user.role == role {
+        true => Ok(()),
+        false => Err(CustomHttpError::Forbidden),
+    }
+}
+
 pub fn encrypt_password(password: &String) -> Result<String, CryptoError> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
@@ -103,41 +112,30 @@ impl FromRequest for Claims {
     type Config = ();
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let pool = req.app_data::<web::Data<MySQLPool>>().unwrap().to_owned();
-        // TODO this needs to not be blocking. not terribly important as only one or two users will be performing authenticated actions.
-        let mysql_pool = pool_handler(pool).unwrap();
-        let auth_header = req.headers().get("Authorization");
+        let users = req.app_data::<web::Data<dyn UserRepository>>().unwrap().to_owned();
+        let auth_header = req.headers().get("Authorization").cloned();
 
         match auth_header {
-            Some(auth) => {
-                let fut = authenticate(auth, &mysql_pool);
-                Box::pin(fut)
-            }
+            Some(auth) => Box::pin(authenticate(auth, users)),
             _ => Box::pin(async { Err(CryptoError::NoAuthHeader.into()) }),
         }
     }
 }
 
-pub fn authenticate(
-    auth_header: &HeaderValue,
-    db: &MysqlConnection,
-) -> impl Future<Output = Result<Claims, CustomHttpError>> {
+pub async fn authenticate(
+    auth_header: HeaderValue,
+    users: web::Data<dyn UserRepository>,
+) -> Result<Claims, CustomHttpError> {
     let encrypted_token = std::str::from_utf8(auth_header.as_bytes())
         .unwrap()
         .to_string();
 
-    let decrypted_token = decrypt(&encrypted_token);
+    let decrypted_token = decrypt(&encrypted_token)?;
 
-    // done this way to pass up the error.
-    let mut logged_in = Err(CryptoError::NotLoggedIn);
-    if let Ok(decrypted_token) = &decrypted_token {
-        logged_in = compare(&decrypted_token, &encrypted_token, db);
-    }
-
-    async move {
-        match logged_in {
-            Ok(_) => Ok(decrypted_token?),
-            Err(e) => Err(e.into()),
-        }
-    }
+    // the token lookup runs on the blocking pool like any other query.
+    run_repository(users, move |users| {
+        compare(&decrypted_token, &encrypted_token, users)?;
+        Ok(decrypted_token)
+    })
+    .await
 }
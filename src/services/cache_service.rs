@@ -0,0 +1,230 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::models::config_models::LocalConfig;
+
+const DEFAULT_MAX_ENTRIES: usize = 500;
+const DEFAULT_TTL_SECONDS: u64 = 300;
+
+/// A rendered page along with the headers it's served with.
+#[derive(Clone)]
+pub struct RenderedPage {
+    pub body: String,
+    /// The latest update to the page or any of its modules.
+    pub last_modified: NaiveDateTime,
+    pub cache_control: Option<String>,
+}
+
+struct CachedPage {
+    /// The page the html was rendered from, used to invalidate it when the page or its modules change.
+    page_uuid: String,
+    /// Other pages shown on this one by menus and page listings.
+    listed_pages: Vec<String>,
+    page: RenderedPage,
+    created: Instant,
+    last_access: Instant,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub max_entries: usize,
+    pub ttl_seconds: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+}
+
+/// In-memory cache of rendered pages keyed by url.
+///
+/// Entries are removed when their page changes (`invalidate_page`), along with the ones listing it when
+/// a change shows in menus and page listings (`invalidate_listed_page`), or all at once when something
+/// that any page can display changes, such as templates, menus, collections or a page's url and title (`clear`).
+/// A `max_entries` of 0 disables the cache. Both limits can change while it runs, through `configure`.
+///
+/// A page is read and rendered before it's inserted, so an invalidation can land in between.
+/// Renders take the `generation` before reading the page, and `insert` drops the render if anything was invalidated since.
+pub struct PageCache {
+    entries: Mutex<HashMap<String, CachedPage>>,
+    max_entries: AtomicUsize,
+    ttl_millis: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    invalidations: AtomicU64,
+    /// Moves on with every invalidation. Only changed while `entries` is locked.
+    generation: AtomicU64,
+}
+
+impl PageCache {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries: AtomicUsize::new(max_entries),
+            ttl_millis: AtomicU64::new(ttl.as_millis() as u64),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_config(conf: &LocalConfig) -> Self {
+        let cache = Self::new(0, Duration::default());
+        cache.configure(conf);
+        cache
+    }
+
+    /// Applies the limits of a reloaded config. Pages already cached are kept,
+    /// and the ones over a lower `max_entries` are dropped as new pages come in.
+    pub fn configure(&self, conf: &LocalConfig) {
+        let ttl = Duration::from_secs(conf.cache_ttl.unwrap_or(DEFAULT_TTL_SECONDS));
+
+        self.max_entries
+            .store(conf.cache_max_entries.unwrap_or(DEFAULT_MAX_ENTRIES), Ordering::Relaxed);
+        self.ttl_millis.store(ttl.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn max_entries(&self) -> usize {
+        self.max_entries.load(Ordering::Relaxed)
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_millis(self.ttl_millis.load(Ordering::Relaxed))
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, CachedPage>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn get(&self, url: &str) -> Option<RenderedPage> {
+        if self.max_entries() == 0 {
+            return None;
+        }
+
+        let mut entries = self.entries();
+
+        let page = match entries.get_mut(url) {
+            Some(cached) if cached.created.elapsed() < self.ttl() => {
+                cached.last_access = Instant::now();
+                Some(cached.page.clone())
+            }
+            Some(_) => {
+                entries.remove(url);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => None,
+        };
+
+        match page {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        page
+    }
+
+    /// Taken before reading a page from the database, and given back to `insert` with its render.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Caches a render of the page read at `generation`, unless something was invalidated since, which may have been this page.
+    /// `listed_pages` are the other pages it shows, as `render_page` returns them.
+    pub fn insert(
+        &self,
+        url: &str,
+        page_uuid: &str,
+        listed_pages: &[String],
+        page: &RenderedPage,
+        generation: u64,
+    ) {
+        let max_entries = self.max_entries();
+        if max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries();
+        if self.generation() != generation {
+            return;
+        }
+
+        // make room by dropping the least recently used pages.
+        while entries.len() >= max_entries && !entries.contains_key(url) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, page)| page.last_access)
+                .map(|(key, _)| key.clone());
+
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let now = Instant::now();
+        entries.insert(
+            url.to_string(),
+            CachedPage {
+                page_uuid: page_uuid.to_string(),
+                listed_pages: listed_pages.to_vec(),
+                page: page.clone(),
+                created: now,
+                last_access: now,
+            },
+        );
+    }
+
+    /// Removes every url rendered from a page.
+    pub fn invalidate_page(&self, page_uuid: &str) {
+        self.invalidate(|page| page.page_uuid == page_uuid);
+    }
+
+    /// Removes every url rendered from a page, and the ones listing it in a menu or `pages_by_term`.
+    pub fn invalidate_listed_page(&self, page_uuid: &str) {
+        self.invalidate(|page| {
+            page.page_uuid == page_uuid || page.listed_pages.iter().any(|listed| listed == page_uuid)
+        });
+    }
+
+    fn invalidate(&self, stale: impl Fn(&CachedPage) -> bool) {
+        let mut entries = self.entries();
+        let before = entries.len();
+
+        entries.retain(|_, page| !stale(page));
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        self.invalidations
+            .fetch_add((before - entries.len()) as u64, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries();
+
+        self.invalidations
+            .fetch_add(entries.len() as u64, Ordering::Relaxed);
+        entries.clear();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries().len(),
+            max_entries: self.max_entries(),
+            ttl_seconds: self.ttl().as_secs(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+}
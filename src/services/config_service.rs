@@ -0,0 +1,228 @@
+use arc_swap::ArcSwap;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::models::config_models::LocalConfig;
+use crate::services::cors_service::CorsPolicies;
+use crate::services::rate_limit_service::RateLimitRules;
+
+/// Read when neither `--config` nor `APP_CONFIG` names a config file, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "radical.toml";
+
+const ENV_PREFIX: &str = "APP_";
+
+/// Settings that a reload applies. The rest are only read when the server starts.
+pub const RELOADABLE: &[&str] = &[
+    "max_req",
+    "rate_limits",
+    "rate_limit_routes",
+    "trusted_proxies",
+    "development_mode",
+    "cache_max_entries",
+    "cache_ttl",
+    "http_cache_control",
+    "cors_read_origins",
+    "cors_read_methods",
+    "cors_read_headers",
+    "cors_read_credentials",
+    "cors_read_max_age",
+    "cors_write_origins",
+    "cors_write_methods",
+    "cors_write_headers",
+    "cors_write_credentials",
+    "cors_write_max_age",
+];
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read the config file {0}: {1}")]
+    Unreadable(String, String),
+    #[error("The config file {0} isn't valid TOML: {1}")]
+    Malformed(String, String),
+    #[error("Unknown setting `{0}` in {1}.")]
+    UnknownKey(String, String),
+    #[error("Invalid {0}: {1}")]
+    Invalid(String, String),
+    #[error("`{0}` has to be set, in the config file or as `{1}`.")]
+    Missing(&'static str, &'static str),
+    #[error("`{0}` can't be used with `*` in `{1}`, since it would let any site act as a logged in user. List the origins instead.")]
+    Conflict(&'static str, &'static str),
+    #[error("Failed to connect to the database: {0}")]
+    Unreachable(String),
+}
+
+/// Reads the config from the file at `path`, or `radical.toml` if there is one, with `APP_` environment variables on top.
+/// Settings that neither of them set keep their defaults.
+pub fn load(path: Option<&Path>) -> Result<LocalConfig, ConfigError> {
+    load_from(path, std::env::vars())
+}
+
+/// Reads the config as `load` does, from `vars` instead of the environment.
+pub fn load_from(
+    path: Option<&Path>,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<LocalConfig, ConfigError> {
+    let mut settings = match path {
+        Some(path) => read_file(path)?,
+        None if Path::new(DEFAULT_CONFIG_FILE).is_file() => read_file(Path::new(DEFAULT_CONFIG_FILE))?,
+        None => BTreeMap::new(),
+    };
+
+    // unknown variables are left alone, since `APP_` is a common prefix for other software too.
+    for (name, value) in vars {
+        if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+            let key = key.to_lowercase();
+            check(&key, &value, || format!("`{}`", name))?;
+            settings.insert(key, value);
+        }
+    }
+
+    envy::from_iter(settings).map_err(|e| ConfigError::Invalid("config".to_string(), e.to_string()))
+}
+
+/// Checks what only the server needs, so commands can run without it.
+pub fn validate_for_serving(conf: &LocalConfig) -> Result<(), ConfigError> {
+    if conf.jwt_key.is_empty() {
+        return Err(ConfigError::Missing("jwt_key", "APP_JWT_KEY"));
+    }
+
+    RateLimitRules::from_config(conf)?;
+    if let Some(store) = conf.rate_limit_store.as_deref().filter(|store| !["memory", "database"].contains(store)) {
+        return Err(ConfigError::Invalid(
+            "`rate_limit_store`".to_string(),
+            format!("has to be `memory` or `database` (got `{}`).", store),
+        ));
+    }
+
+    let cors = CorsPolicies::from_config(conf);
+    if cors.read.any_origin_with_credentials() {
+        return Err(ConfigError::Conflict("cors_read_credentials", "cors_read_origins"));
+    }
+    if cors.write.any_origin_with_credentials() {
+        return Err(ConfigError::Conflict("cors_write_credentials", "cors_write_origins"));
+    }
+
+    Ok(())
+}
+
+/// Reads the top level keys of a TOML file. Lists are joined with `,`, the same as an environment variable lists them.
+fn read_file(path: &Path) -> Result<BTreeMap<String, String>, ConfigError> {
+    let origin = path.display().to_string();
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Unreadable(origin.clone(), e.to_string()))?;
+    let table: toml::value::Table =
+        toml::from_str(&text).map_err(|e| ConfigError::Malformed(origin.clone(), e.to_string()))?;
+    let known = known_keys();
+
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            if !known.contains(&key) {
+                return Err(ConfigError::UnknownKey(key, origin.clone()));
+            }
+
+            let value = match value {
+                toml::Value::Array(items) => items.iter().map(scalar).collect::<Option<Vec<_>>>().map(|items| items.join(",")),
+                value => scalar(&value),
+            }
+            .ok_or_else(|| {
+                ConfigError::Invalid(
+                    format!("`{}` in {}", key, origin),
+                    "has to be a string, number, boolean or a list of them.".to_string(),
+                )
+            })?;
+
+            check(&key, &value, || format!("`{}` in {}", key, origin))?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+fn scalar(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value.clone()),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Float(value) => Some(value.to_string()),
+        toml::Value::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads one setting on its own, so a bad value is blamed on its key and where it came from.
+fn check(key: &str, value: &str, origin: impl FnOnce() -> String) -> Result<(), ConfigError> {
+    match envy::from_iter::<_, LocalConfig>(std::iter::once((key.to_string(), value.to_string()))) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            // envy names the value and variable itself, as `... while parsing value 'x' provided by KEY`.
+            let e = e.to_string();
+            let reason = e.split(" while parsing value").next().unwrap_or_default();
+            Err(ConfigError::Invalid(origin(), format!("{} (got `{}`).", reason, value)))
+        }
+    }
+}
+
+/// The name of every setting.
+fn known_keys() -> Vec<String> {
+    match serde_json::to_value(LocalConfig::default()) {
+        Ok(Value::Object(settings)) => settings.into_iter().map(|(key, _)| key).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn to_map(conf: &LocalConfig) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(conf) {
+        Ok(Value::Object(settings)) => settings,
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// The config the server runs with, shared by every worker.
+/// `reload` reads the config file and environment again and applies the settings in `RELOADABLE`.
+pub struct Settings {
+    path: Option<PathBuf>,
+    current: ArcSwap<LocalConfig>,
+}
+
+impl Settings {
+    pub fn new(conf: LocalConfig, path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            current: ArcSwap::from_pointee(conf),
+        }
+    }
+
+    pub fn current(&self) -> Arc<LocalConfig> {
+        self.current.load_full()
+    }
+
+    /// Reads the config again and applies it. If it doesn't load, the current one is kept.
+    /// Returns the settings that changed but only take effect after a restart.
+    pub fn reload(&self) -> Result<Vec<String>, ConfigError> {
+        let conf = load(self.path.as_deref())?;
+        validate_for_serving(&conf)?;
+
+        Ok(self.apply(&conf))
+    }
+
+    /// Takes the settings in `RELOADABLE` from `conf`, and returns the other settings that differ.
+    pub fn apply(&self, conf: &LocalConfig) -> Vec<String> {
+        let mut applied = to_map(&self.current());
+        let mut needs_restart = Vec::new();
+
+        for (key, value) in to_map(conf) {
+            if RELOADABLE.contains(&key.as_str()) {
+                applied.insert(key, value);
+            } else if applied.get(&key) != Some(&value) {
+                needs_restart.push(key);
+            }
+        }
+
+        // it was serialized from a `LocalConfig` just above, so it reads back as one.
+        let applied = serde_json::from_value(Value::Object(applied)).expect("config reads back");
+        self.current.store(Arc::new(applied));
+
+        needs_restart
+    }
+}
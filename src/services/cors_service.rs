@@ -0,0 +1,352 @@
+use actix_web::body::{Body, MessageBody, ResponseBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, HeaderMap, HeaderValue, Method};
+use actix_web::web::Data;
+use actix_web::{Error, HttpResponse};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::models::config_models::LocalConfig;
+use crate::services::config_service::Settings;
+
+/// Response headers that scripts on other origins may read, beyond the ones every browser lets them.
+/// Versioned updates need the `ETag`.
+const EXPOSED_HEADERS: &str =
+    "etag, retry-after, ratelimit-limit, ratelimit-remaining, ratelimit-reset, ratelimit-policy";
+
+/// Routes whose reads need a login. They follow the write policy, as every change does.
+const AUTHENTICATED_ROUTES: &[&str] = &["/v1/user", "/v1/system", "/v1/cache"];
+
+/// What one kind of request is allowed from other origins.
+pub struct CorsPolicy {
+    origins: Vec<String>,
+    methods: Vec<Method>,
+    /// Lowercase, as headers are compared.
+    headers: Vec<String>,
+    credentials: bool,
+    max_age: u64,
+}
+
+impl CorsPolicy {
+    /// Public reads, which anyone may make unless configured otherwise. They don't need a login, so `authorization` isn't allowed by default.
+    pub fn read(conf: &LocalConfig) -> Self {
+        Self::new(
+            &conf.cors_read_origins,
+            &conf.cors_read_methods,
+            &conf.cors_read_headers,
+            conf.cors_read_credentials,
+            conf.cors_read_max_age,
+            &["*"],
+            &["GET", "HEAD"],
+            &["content-type", "if-none-match", "if-modified-since"],
+        )
+    }
+
+    /// Writes and the reads that need a login, which no other origin may make unless it's listed.
+    pub fn write(conf: &LocalConfig) -> Self {
+        Self::new(
+            &conf.cors_write_origins,
+            &conf.cors_write_methods,
+            &conf.cors_write_headers,
+            conf.cors_write_credentials,
+            conf.cors_write_max_age,
+            &[],
+            &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"],
+            &["authorization", "content-type", "if-match"],
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        origins: &Option<Vec<String>>,
+        methods: &Option<Vec<String>>,
+        headers: &Option<Vec<String>>,
+        credentials: Option<bool>,
+        max_age: Option<u64>,
+        default_origins: &[&str],
+        default_methods: &[&str],
+        default_headers: &[&str],
+    ) -> Self {
+        let list = |values: &Option<Vec<String>>, defaults: &[&str]| -> Vec<String> {
+            match values {
+                Some(values) => values
+                    .iter()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect(),
+                None => defaults.iter().map(|value| value.to_string()).collect(),
+            }
+        };
+
+        Self {
+            origins: list(origins, default_origins),
+            methods: list(methods, default_methods)
+                .iter()
+                .filter_map(|method| Method::from_bytes(method.to_uppercase().as_bytes()).ok())
+                .collect(),
+            headers: list(headers, default_headers)
+                .iter()
+                .map(|header| header.to_lowercase())
+                .collect(),
+            credentials: credentials.unwrap_or(false),
+            max_age: max_age.unwrap_or(3600),
+        }
+    }
+
+    /// Whether `origin` is listed. `https://*.example.com` allows every subdomain of `example.com`, but not `example.com` itself.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.origins.iter().any(|allowed| {
+            if allowed == "*" {
+                return true;
+            }
+
+            match allowed.split_once("*.") {
+                Some((scheme, domain)) => origin
+                    .strip_prefix(scheme)
+                    .and_then(|rest| rest.strip_suffix(domain))
+                    .and_then(|subdomain| subdomain.strip_suffix('.'))
+                    .is_some_and(|subdomain| {
+                        !subdomain.is_empty()
+                            && subdomain
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                    }),
+                None => allowed.eq_ignore_ascii_case(origin),
+            }
+        })
+    }
+
+    fn allows_header(&self, header: &str) -> bool {
+        self.headers.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(header))
+    }
+
+    /// Whether credentials are allowed from any origin at all, which the server refuses to start with.
+    pub fn any_origin_with_credentials(&self) -> bool {
+        self.credentials && self.origins.iter().any(|origin| origin == "*")
+    }
+}
+
+/// The policy for public reads, and the one for every request that needs a login.
+pub struct CorsPolicies {
+    pub read: CorsPolicy,
+    pub write: CorsPolicy,
+}
+
+impl CorsPolicies {
+    pub fn from_config(conf: &LocalConfig) -> Self {
+        Self {
+            read: CorsPolicy::read(conf),
+            write: CorsPolicy::write(conf),
+        }
+    }
+
+    fn for_route(&self, method: &Method, path: &str) -> &CorsPolicy {
+        let authenticated = AUTHENTICATED_ROUTES
+            .iter()
+            .any(|route| path == *route || path.starts_with(&format!("{}/", route)));
+
+        match *method {
+            Method::GET | Method::HEAD if !authenticated => &self.read,
+            _ => &self.write,
+        }
+    }
+}
+
+/// Answers CORS preflight requests and adds the CORS headers to responses, following the `cors_` settings.
+///
+/// `GET` and `HEAD` requests for public content follow the `cors_read_` policy.
+/// Every other method, and reads of `AUTHENTICATED_ROUTES`, follow the `cors_write_` one.
+/// The allowed origin is echoed rather than sent as `*`, so responses vary by `Origin`.
+/// A request from an origin that isn't allowed is still served, without the headers, so the browser keeps its response from the page.
+/// The policies follow the config as it's reloaded.
+pub struct Cors {
+    settings: Data<Settings>,
+}
+
+impl Cors {
+    pub fn new(settings: Data<Settings>) -> Self {
+        Self { settings }
+    }
+}
+
+impl<S, B> Transform<S> for Cors
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CorsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let conf = self.settings.current();
+
+        ok(CorsMiddleware {
+            service,
+            settings: self.settings.clone(),
+            policies: CorsPolicies::from_config(&conf),
+            conf,
+        })
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: S,
+    settings: Data<Settings>,
+    /// The config `policies` were read from, to tell when it's been reloaded.
+    conf: Arc<LocalConfig>,
+    policies: CorsPolicies,
+}
+
+impl<S, B> Service for CorsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let conf = self.settings.current();
+        if !Arc::ptr_eq(&conf, &self.conf) {
+            self.policies = CorsPolicies::from_config(&conf);
+            self.conf = conf;
+        }
+
+        let origin = req
+            .headers()
+            .get(header::ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .map(|origin| origin.to_string());
+
+        let origin = match origin {
+            Some(origin) => origin,
+            None => return self.pass(req, None),
+        };
+
+        let requested_method = req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|method| Method::from_bytes(method.as_bytes()).ok());
+
+        if req.method() == Method::OPTIONS {
+            if let Some(method) = requested_method {
+                let res = self.preflight(&req, &origin, &method);
+                return Box::pin(ok(req.into_response(res)));
+            }
+        }
+
+        let policy = self.policies.for_route(req.method(), req.path());
+        if !policy.allows_origin(&origin) || !policy.methods.contains(req.method()) {
+            return self.pass(req, None);
+        }
+
+        let mut headers = HeaderMap::new();
+        allow_origin(&mut headers, &origin, policy);
+        headers.insert(
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            HeaderValue::from_static(EXPOSED_HEADERS),
+        );
+
+        self.pass(req, Some(headers))
+    }
+}
+
+impl<S, B> CorsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    /// Serves the request, adding `headers` to the response.
+    fn pass(&mut self, req: ServiceRequest, headers: Option<HeaderMap>) -> LocalBoxFuture<'static, Result<ServiceResponse<Body>, Error>> {
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            // every response may differ by `Origin`, including the ones without CORS headers.
+            res.headers_mut().append(header::VARY, HeaderValue::from_static("Origin"));
+            if let Some(headers) = headers {
+                for (name, value) in headers.iter() {
+                    res.headers_mut().insert(name.clone(), value.clone());
+                }
+            }
+
+            Ok(res.map_body(|_, body| ResponseBody::Other(Body::from_message(body))))
+        })
+    }
+
+    /// Answers a preflight with `204 No Content` if the policy for `method` on its route allows the request, and `403 Forbidden` if not.
+    fn preflight(&self, req: &ServiceRequest, origin: &str, method: &Method) -> HttpResponse {
+        let policy = self.policies.for_route(method, req.path());
+        let requested_headers: Vec<String> = req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .and_then(|headers| headers.to_str().ok())
+            .unwrap_or_default()
+            .split(',')
+            .map(|header| header.trim().to_lowercase())
+            .filter(|header| !header.is_empty())
+            .collect();
+
+        let allowed = policy.allows_origin(origin)
+            && policy.methods.contains(method)
+            && requested_headers.iter().all(|header| policy.allows_header(header));
+
+        if !allowed {
+            return HttpResponse::Forbidden()
+                .header(header::VARY, "Origin")
+                .finish();
+        }
+
+        let mut res = HttpResponse::NoContent().finish();
+        let headers = res.headers_mut();
+        allow_origin(headers, origin, policy);
+
+        let methods: Vec<&str> = policy.methods.iter().map(Method::as_str).collect();
+        let allowed_headers = if policy.headers.iter().any(|header| header == "*") {
+            requested_headers.join(", ")
+        } else {
+            policy.headers.join(", ")
+        };
+
+        for (name, value) in [
+            (header::ACCESS_CONTROL_ALLOW_METHODS, methods.join(", ")),
+            (header::ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers),
+            (header::ACCESS_CONTROL_MAX_AGE, policy.max_age.to_string()),
+        ] {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+        headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+
+        res
+    }
+}
+
+fn allow_origin(headers: &mut HeaderMap, origin: &str, policy: &CorsPolicy) {
+    if let Ok(origin) = HeaderValue::from_str(origin) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+
+    if policy.credentials {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError, ResponseError};
+use actix_web::{http::header, http::StatusCode, HttpRequest, HttpResponse};
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind};
+use validator::ValidationErrors;
 use serde::Serialize;
 use thiserror::Error;
 
@@ -14,60 +17,247 @@ pub enum CustomHttpError {
     Unknown,
     #[error("User is not authorized.")]
     Unauthorized,
+    #[error("User's role doesn't allow this.")]
+    Forbidden,
+    #[error("Resource has changed since it was read.")]
+    PreconditionFailed,
+    #[error("Missing If-Match header.")]
+    PreconditionRequired,
+    #[error("{0}")]
+    Malformed(String),
+    #[error("Resource conflicts with an existing one.")]
+    Conflict(Vec<FieldError>),
+    #[error("Some fields are invalid.")]
+    Invalid(Vec<FieldError>),
+    #[error("Too many requests. Try again after `Retry-After` seconds.")]
+    TooManyRequests,
+    #[error("The server is too busy to answer. Try again after `Retry-After` seconds.")]
+    Unavailable,
 }
 
-/// Provides an interface for getting a description of the request.
+/// Seconds a client is asked to wait when no database connection was free.
+const UNAVAILABLE_RETRY_AFTER: u64 = 5;
+
+/// What is wrong with one field of a request.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    /// Machine readable reason, such as `taken` or `not_found`.
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Provides a machine readable code for each error, sent as `code` so clients don't have to match on messages.
 impl CustomHttpError {
-    pub fn descriptor(&self) -> String {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "bad_request",
+            Self::Unknown => "internal_error",
+            Self::NotFound => "not_found",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::PreconditionFailed => "precondition_failed",
+            Self::PreconditionRequired => "precondition_required",
+            Self::Malformed(_) => "malformed_request",
+            Self::Conflict(_) => "conflict",
+            Self::Invalid(_) => "validation_failed",
+            Self::TooManyRequests => "rate_limited",
+            Self::Unavailable => "unavailable",
+        }
+    }
+
+    pub fn field_errors(&self) -> &[FieldError] {
         match self {
-            Self::BadRequest => String::from("Server was unable to handle data"),
-            Self::Unknown => String::from("Internal server error"),
-            Self::NotFound => String::from("Resource was not found"),
-            Self::Unauthorized => String::from("Not authorized")
+            Self::Conflict(errors) | Self::Invalid(errors) => errors,
+            _ => &[],
+        }
+    }
+
+    /// The error with the fields it names, for output that has no response to carry them, such as the command line.
+    pub fn describe(&self) -> String {
+        let fields: Vec<String> = self
+            .field_errors()
+            .iter()
+            .map(|field| format!("`{}` {}", field.field, field.message))
+            .collect();
+
+        match fields.is_empty() {
+            true => self.to_string(),
+            false => format!("{} {}", self, fields.join(" ")),
+        }
+    }
+
+    /// The error as an RFC 7807 problem document.
+    pub fn problem(&self) -> Problem {
+        let status_code = self.status_code();
+
+        Problem {
+            title: status_code.canonical_reason().unwrap_or_default().to_string(),
+            status: status_code.as_u16(),
+            detail: self.to_string(),
+            code: self.code(),
+            errors: self.field_errors().to_vec(),
         }
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    code: u16,
-    error: String,
-    message: String,
+/// An RFC 7807 problem document, sent as `application/problem+json`.
+/// `type` is left out, which means `about:blank`, and `code` tells errors with the same status apart.
+#[derive(Serialize, Debug)]
+pub struct Problem {
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
 }
 
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
 /// Full implementation of ResponseError trait so that it can be sent back as an error through actix-web.
 impl ResponseError for CustomHttpError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            Self::BadRequest => StatusCode::BAD_REQUEST,
+            Self::BadRequest | Self::Malformed(_) => StatusCode::BAD_REQUEST,
             Self::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
             Self::NotFound => StatusCode::NOT_FOUND,
-            Self::Unauthorized => StatusCode::UNAUTHORIZED
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Self::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        let status_code = self.status_code();
-        let error_response = ErrorResponse {
-            code: status_code.as_u16(),
-            message: self.descriptor(),
-            error: self.to_string(),
-        };
+        let mut res = HttpResponse::build(self.status_code());
+        if let Self::Unavailable = self {
+            res.header(header::RETRY_AFTER, UNAVAILABLE_RETRY_AFTER);
+        }
 
-        HttpResponse::build(status_code).json(error_response)
+        res.content_type(PROBLEM_CONTENT_TYPE).json(self.problem())
     }
 }
 
+/// Sent for request bodies that aren't valid JSON or don't match the expected shape.
+pub fn json_error(err: JsonPayloadError, _: &HttpRequest) -> actix_web::Error {
+    CustomHttpError::Malformed(err.to_string()).into()
+}
+
+/// Sent for query strings that don't match the expected parameters.
+pub fn query_error(err: QueryPayloadError, _: &HttpRequest) -> actix_web::Error {
+    CustomHttpError::Malformed(err.to_string()).into()
+}
+
+/// Sent for path segments that can't be read as the expected type.
+pub fn path_error(err: PathError, _: &HttpRequest) -> actix_web::Error {
+    CustomHttpError::Malformed(err.to_string()).into()
+}
+
 /// Any time an SQL query fails, it gets mapped to here.
+/// Unique and foreign key violations are the client's fault, so they're reported against the column the database names.
 impl From<diesel::result::Error> for CustomHttpError {
     fn from(e: diesel::result::Error) -> Self {
         match e {
             diesel::result::Error::NotFound => CustomHttpError::NotFound,
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                CustomHttpError::Conflict(vec![FieldError::new(
+                    &violated_column(info.as_ref()),
+                    "taken",
+                    "Another resource already has this value.",
+                )])
+            }
+            // a row still refers to the one being deleted or changed.
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info)
+                if info.message().starts_with("Cannot delete or update a parent row")
+                    || info.details().unwrap_or_default().contains("is still referenced") =>
+            {
+                CustomHttpError::Conflict(Vec::new())
+            }
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                CustomHttpError::Invalid(vec![FieldError::new(
+                    &violated_column(info.as_ref()),
+                    "not_found",
+                    "Refers to a resource that doesn't exist.",
+                )])
+            }
             _ => CustomHttpError::Unknown,
         }
     }
 }
 
+/// Every broken `#[validate]` rule, sorted by field so the order doesn't change between requests.
+impl From<ValidationErrors> for CustomHttpError {
+    fn from(e: ValidationErrors) -> Self {
+        let mut errors: Vec<FieldError> = e
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| {
+                    // rules that can't carry their own message, such as `required`, get a generic one.
+                    let message = match (&error.message, error.code.as_ref()) {
+                        (Some(message), _) => message.to_string(),
+                        (None, "required") => String::from("Is required."),
+                        (None, code) => format!("Breaks the `{}` rule.", code),
+                    };
+
+                    FieldError::new(field, &error.code, message)
+                })
+            })
+            .collect();
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+
+        CustomHttpError::Invalid(errors)
+    }
+}
+
+/// The column a constraint violation is about.
+/// MySQL only names it in the message, as in "Duplicate entry 'x' for key 'page_url'" or "FOREIGN KEY (`page_uuid`)".
+/// Postgres names it in the details, as in "Key (page_uuid)=(x) is not present in table".
+/// SQLite names unique columns at the end of the message, as in "UNIQUE constraint failed: users.username",
+/// but never says which foreign key failed.
+/// Only the first column of a multi-column key is given.
+fn violated_column(info: &dyn DatabaseErrorInformation) -> String {
+    let column = match info.column_name() {
+        Some(column) => Some(column),
+        None => info
+            .details()
+            .and_then(|details| quoted_after(details, "Key (", ')'))
+            .or_else(|| quoted_after(info.message(), "for key '", '\''))
+            .or_else(|| quoted_after(info.message(), "FOREIGN KEY (`", '`'))
+            .or_else(|| info.message().strip_prefix("UNIQUE constraint failed: ")),
+    };
+
+    column
+        .and_then(|column| column.split(',').next())
+        .and_then(|column| column.rsplit('.').next())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Reads the name quoted after `prefix` in an error message.
+fn quoted_after<'a>(message: &'a str, prefix: &str, quote: char) -> Option<&'a str> {
+    let start = message.find(prefix)? + prefix.len();
+    let rest = &message[start..];
+
+    rest.find(quote).map(|end| &rest[..end])
+}
+
 impl From<jsonwebtoken::errors::Error> for CustomHttpError {
     fn from(e: jsonwebtoken::errors::Error) -> Self {
         match e {
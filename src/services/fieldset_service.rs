@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::errors_service::{CustomHttpError, FieldError};
+
+/// Lets clients choose which attributes and related resources come back.
+///
+/// `fields` lists the attributes of the requested resource. Related resources are narrowed with
+/// `fields[modules]`, `fields[categories]`, `fields[pages]` and `fields[tags]`.
+/// `include` lists the related resources to embed. All of them are comma separated.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct FieldsetQuery {
+    pub fields: Option<String>,
+    #[serde(rename = "fields[modules]")]
+    pub module_fields: Option<String>,
+    #[serde(rename = "fields[categories]")]
+    pub category_fields: Option<String>,
+    #[serde(rename = "fields[pages]")]
+    pub page_fields: Option<String>,
+    #[serde(rename = "fields[tags]")]
+    pub tag_fields: Option<String>,
+    pub include: Option<String>,
+}
+
+impl FieldsetQuery {
+    /// The requested relations, or `default` when `include` wasn't sent.
+    /// An empty `include=` asks for no relations at all.
+    pub fn includes(&self, default: &[&str]) -> Vec<String> {
+        match &self.include {
+            Some(include) => include
+                .split(',')
+                .map(|relation| relation.trim().to_string())
+                .filter(|relation| !relation.is_empty())
+                .collect(),
+            None => default.iter().map(|relation| relation.to_string()).collect(),
+        }
+    }
+
+    /// Checks every requested relation is one of `includable`.
+    pub fn validate(&self, includable: &[&str]) -> Result<(), CustomHttpError> {
+        let valid = self
+            .includes(&[])
+            .iter()
+            .all(|relation| includable.contains(&relation.as_str()));
+
+        match valid {
+            true => Ok(()),
+            false => Err(CustomHttpError::Invalid(vec![FieldError::new(
+                "include",
+                "invalid",
+                format!("Must be a list of {:?}.", includable),
+            )])),
+        }
+    }
+}
+
+pub fn to_value<T: Serialize>(resource: T) -> Result<Value, CustomHttpError> {
+    serde_json::to_value(resource).or(Err(CustomHttpError::Unknown))
+}
+
+/// Removes every attribute not listed in `fields` from an object, or from each object of an array.
+/// `uuid` and the `keep` keys, which hold included relations, are never removed.
+/// Nothing is removed when `fields` is `None`.
+pub fn retain_fields(value: Option<&mut Value>, fields: Option<&str>, keep: &[&str]) {
+    if let (Some(value), Some(fields)) = (value, fields) {
+        let fields: Vec<&str> = fields.split(',').map(|field| field.trim()).collect();
+        retain(value, &fields, keep);
+    }
+}
+
+fn retain(value: &mut Value, fields: &[&str], keep: &[&str]) {
+    match value {
+        Value::Object(object) => {
+            let removed: Vec<String> = object
+                .keys()
+                .filter(|key| {
+                    *key != "uuid" && !fields.contains(&key.as_str()) && !keep.contains(&key.as_str())
+                })
+                .cloned()
+                .collect();
+
+            for key in removed {
+                object.remove(&key);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                retain(item, fields, keep);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Applies `fields` to each item of a listing.
+pub fn sparse_items<T: Serialize>(
+    items: Vec<T>,
+    fields: Option<&str>,
+) -> Result<Vec<Value>, CustomHttpError> {
+    let mut items = to_value(items)?;
+    retain_fields(Some(&mut items), fields, &[]);
+
+    match items {
+        Value::Array(items) => Ok(items),
+        _ => Err(CustomHttpError::Unknown),
+    }
+}
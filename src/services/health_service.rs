@@ -0,0 +1,109 @@
+use actix_web::error::BlockingError;
+use actix_web::web;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::models::DbPool;
+use crate::services::migration_service;
+use crate::services::template_service::Templates;
+
+/// The paths a load balancer or orchestrator probes, which are neither logged nor rate limited.
+pub const PROBES: &[&str] = &["/healthz", "/readyz"];
+
+/// How long readiness waits for a connection. Probes give up after a second or so, well before the pool's own timeout.
+const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(2);
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Marks when the server started, which uptime is counted from.
+pub fn mark_started() {
+    STARTED.get_or_init(Instant::now);
+}
+
+pub fn uptime() -> Duration {
+    STARTED.get_or_init(Instant::now).elapsed()
+}
+
+/// Whether the server can serve requests, and what failed if it can't.
+/// Each check is `ok` or says what's wrong.
+#[derive(Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: String,
+    pub migrations: String,
+    pub templates: String,
+}
+
+/// Checks that a connection can be checked out of the pool, that every migration has run and that templates are loaded.
+pub async fn readiness(pool: DbPool, templates: &Templates) -> Readiness {
+    let checked = web::block(move || {
+        let db = pool.get_timeout(CHECKOUT_TIMEOUT).map_err(|e| e.to_string())?;
+        Ok::<_, String>(migration_service::status(&db).map_err(|e| e.to_string()))
+    })
+    .await;
+
+    let (database, migrations) = match checked {
+        Ok(Ok(status)) => {
+            let pending = status.iter().filter(|status| !status.applied).count();
+            match pending {
+                0 => (Ok(()), Ok(())),
+                pending => (Ok(()), Err(format!("{} pending", pending))),
+            }
+        }
+        Ok(Err(e)) => (Ok(()), Err(e)),
+        Err(BlockingError::Error(e)) => (Err(e), Err("not checked without a connection".to_string())),
+        Err(BlockingError::Canceled) => (
+            Err("the check was canceled".to_string()),
+            Err("not checked without a connection".to_string()),
+        ),
+    };
+
+    let templates = match templates.current().get_templates().is_empty() {
+        true => Err("none loaded".to_string()),
+        false => Ok(()),
+    };
+
+    let describe = |check: Result<(), String>| check.err().unwrap_or_else(|| "ok".to_string());
+
+    Readiness {
+        ready: database.is_ok() && migrations.is_ok() && templates.is_ok(),
+        database: describe(database),
+        migrations: describe(migrations),
+        templates: describe(templates),
+    }
+}
+
+/// What `GET /v1/system/status` reports.
+#[derive(Serialize)]
+pub struct SystemStatus {
+    pub version: &'static str,
+    pub uptime_seconds: u64,
+    pub database: &'static str,
+    pub pool: PoolStats,
+    pub templates: usize,
+}
+
+#[derive(Serialize)]
+pub struct PoolStats {
+    pub max_size: u32,
+    /// Open connections, both in use and idle.
+    pub connections: u32,
+    pub idle_connections: u32,
+}
+
+pub fn status(pool: &DbPool, templates: &Templates) -> SystemStatus {
+    let state = pool.state();
+
+    SystemStatus {
+        version: env!("CARGO_PKG_VERSION"),
+        uptime_seconds: uptime().as_secs(),
+        database: pool.backend().name(),
+        pool: PoolStats {
+            max_size: pool.max_size(),
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        },
+        templates: templates.current().get_templates().len(),
+    }
+}
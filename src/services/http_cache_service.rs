@@ -0,0 +1,240 @@
+use actix_web::body::{Body, MessageBody, ResponseBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{
+    self, ETag, EntityTag, Header, HttpDate, IfMatch, IfModifiedSince, IfNoneMatch, LastModified,
+};
+use actix_web::http::{HeaderValue, Method, StatusCode};
+use actix_web::web::{BytesMut, Data};
+use actix_web::{Error, HttpRequest};
+use chrono::NaiveDateTime;
+use futures::future::{ok, LocalBoxFuture, Ready};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::models::config_models::LocalConfig;
+use crate::services::config_service::Settings;
+use crate::services::errors_service::CustomHttpError;
+
+/// Converts a database timestamp into the date used by `Last-Modified`.
+pub fn http_date(time: NaiveDateTime) -> HttpDate {
+    (UNIX_EPOCH + Duration::from_secs(time.timestamp().max(0) as u64)).into()
+}
+
+/// Sets `Last-Modified` on a response.
+pub fn last_modified(time: NaiveDateTime) -> LastModified {
+    LastModified(http_date(time))
+}
+
+/// The `ETag` of a versioned resource, which is its version.
+pub fn version_tag(version: i32) -> ETag {
+    ETag(EntityTag::strong(version.to_string()))
+}
+
+/// Reads the version a client is changing from `If-Match`.
+/// `If-Match: *` gives `None`, meaning any version may be overwritten.
+/// A missing header is `PreconditionRequired`, and a tag that isn't a version can never match.
+pub fn if_match(req: &HttpRequest) -> Result<Option<i32>, CustomHttpError> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Err(CustomHttpError::PreconditionRequired);
+    }
+
+    match IfMatch::parse(req).or(Err(CustomHttpError::BadRequest))? {
+        IfMatch::Any => Ok(None),
+        IfMatch::Items(tags) => tags
+            .iter()
+            .find_map(|tag| tag.tag().parse::<i32>().ok())
+            .map(Some)
+            .ok_or(CustomHttpError::PreconditionFailed),
+    }
+}
+
+/// Adds validators to successful `GET` responses and answers conditional requests with `304 Not Modified`.
+///
+/// Responses get a strong `ETag` hashed from their body unless they already set one,
+/// such as static assets or versioned resources.
+/// `If-None-Match` is checked first. `If-Modified-Since` is only used when the request has no `If-None-Match`
+/// and the response set `Last-Modified`.
+///
+/// `Cache-Control` is taken from the longest matching path prefix in `http_cache_control`,
+/// unless the response already set its own. The rules follow the config as it's reloaded.
+pub struct HttpCache {
+    settings: Data<Settings>,
+}
+
+impl HttpCache {
+    pub fn new(settings: Data<Settings>) -> Self {
+        Self { settings }
+    }
+}
+
+/// Reads rules in the form `/blog=public, max-age=600;/v1=no-cache`.
+fn rules(conf: &LocalConfig) -> Vec<(String, HeaderValue)> {
+    let mut rules: Vec<(String, HeaderValue)> = conf
+        .http_cache_control
+        .as_deref()
+        .unwrap_or_default()
+        .split(';')
+        .filter_map(|rule| {
+            let (prefix, value) = rule.split_once('=')?;
+            let value = HeaderValue::from_str(value.trim()).ok()?;
+            Some((prefix.trim().to_string(), value))
+        })
+        .collect();
+
+    // longest prefix first, so the first match is the most specific one.
+    rules.sort_by_key(|(prefix, _)| Reverse(prefix.len()));
+    rules
+}
+
+impl<S, B> Transform<S> for HttpCache
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = HttpCacheMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let conf = self.settings.current();
+
+        ok(HttpCacheMiddleware {
+            service,
+            settings: self.settings.clone(),
+            rules: rules(&conf),
+            conf,
+        })
+    }
+}
+
+pub struct HttpCacheMiddleware<S> {
+    service: S,
+    settings: Data<Settings>,
+    /// The config `rules` were read from, to tell when it's been reloaded.
+    conf: Arc<LocalConfig>,
+    rules: Vec<(String, HeaderValue)>,
+}
+
+impl<S, B> Service for HttpCacheMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let cacheable = req.method() == Method::GET || req.method() == Method::HEAD;
+        // an absent `If-None-Match` parses as an empty list, so it's only read when it was sent.
+        let if_none_match = if req.headers().contains_key(header::IF_NONE_MATCH) {
+            IfNoneMatch::parse(&req).ok()
+        } else {
+            None
+        };
+        let if_modified_since = IfModifiedSince::parse(&req).ok();
+
+        let conf = self.settings.current();
+        if !Arc::ptr_eq(&conf, &self.conf) {
+            self.rules = rules(&conf);
+            self.conf = conf;
+        }
+
+        let cache_control = self
+            .rules
+            .iter()
+            .find(|(prefix, _)| req.path().starts_with(prefix.as_str()))
+            .map(|(_, value)| value.clone());
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            if !cacheable || res.status() != StatusCode::OK {
+                return Ok(res.map_body(|_, body| ResponseBody::Other(Body::from_message(body))));
+            }
+
+            if let Some(cache_control) = cache_control {
+                if !res.headers().contains_key(header::CACHE_CONTROL) {
+                    res.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+                }
+            }
+
+            // a response's own `ETag` is used as is, so its body doesn't need to be read.
+            let own_etag = res
+                .headers()
+                .get(header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<EntityTag>().ok());
+
+            let (etag, bytes) = match own_etag {
+                Some(etag) => (etag, None),
+                None => {
+                    let mut body = res.take_body();
+                    let mut bytes = BytesMut::new();
+                    while let Some(chunk) = body.next().await {
+                        bytes.extend_from_slice(&chunk?);
+                    }
+
+                    let etag = EntityTag::strong(hash(&bytes));
+                    res.headers_mut()
+                        .insert(header::ETAG, HeaderValue::from_str(&etag.to_string())?);
+
+                    (etag, Some(bytes))
+                }
+            };
+
+            let not_modified = match if_none_match {
+                Some(IfNoneMatch::Any) => true,
+                Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+                None => {
+                    let modified = res
+                        .headers()
+                        .get(header::LAST_MODIFIED)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.parse::<HttpDate>().ok());
+
+                    match (if_modified_since, modified) {
+                        (Some(since), Some(modified)) => modified <= since.0,
+                        _ => false,
+                    }
+                }
+            };
+
+            if not_modified {
+                *res.response_mut().status_mut() = StatusCode::NOT_MODIFIED;
+                res.headers_mut().remove(header::CONTENT_LENGTH);
+                return Ok(res.map_body(|_, _| ResponseBody::Body(Body::Empty)));
+            }
+
+            Ok(match bytes {
+                Some(bytes) => res.map_body(|_, _| ResponseBody::Body(Body::from(bytes.freeze()))),
+                None => res.map_body(|_, body| ResponseBody::Other(Body::from_message(body))),
+            })
+        })
+    }
+}
+
+/// The first 128 bits of the body's SHA-256, which every build and every server agrees on,
+/// so an unchanged body keeps its `ETag` across deploys and behind a load balancer.
+fn hash(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+
+    digest[..16].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
@@ -0,0 +1,124 @@
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::{Migration, MigrationConnection, RunMigrationsError};
+
+use crate::models::backend::{on_connection, Backend};
+use crate::models::{DbConnection, RunQueryDsl};
+
+/// A migration compiled into the binary by `build.rs`, from `migrations/<database>`.
+pub struct EmbeddedMigration {
+    pub version: &'static str,
+    /// The name of the migration's folder, such as `2021-08-23-120000_create_redirects`.
+    pub name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+impl Migration for EmbeddedMigration {
+    fn version(&self) -> &str {
+        self.version
+    }
+
+    fn run(&self, conn: &dyn SimpleConnection) -> Result<(), RunMigrationsError> {
+        Ok(conn.batch_execute(self.up)?)
+    }
+
+    fn revert(&self, conn: &dyn SimpleConnection) -> Result<(), RunMigrationsError> {
+        Ok(conn.batch_execute(self.down)?)
+    }
+}
+
+#[cfg(feature = "mysql")]
+const MYSQL_MIGRATIONS: &[EmbeddedMigration] = include!(concat!(env!("OUT_DIR"), "/migrations_mysql.rs"));
+#[cfg(feature = "postgres")]
+const POSTGRES_MIGRATIONS: &[EmbeddedMigration] = include!(concat!(env!("OUT_DIR"), "/migrations_postgres.rs"));
+#[cfg(feature = "sqlite")]
+const SQLITE_MIGRATIONS: &[EmbeddedMigration] = include!(concat!(env!("OUT_DIR"), "/migrations_sqlite.rs"));
+
+/// Every migration of a database, oldest first.
+pub fn migrations(backend: Backend) -> &'static [EmbeddedMigration] {
+    match backend {
+        #[cfg(feature = "mysql")]
+        Backend::Mysql => MYSQL_MIGRATIONS,
+        #[cfg(feature = "postgres")]
+        Backend::Postgres => POSTGRES_MIGRATIONS,
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => SQLITE_MIGRATIONS,
+    }
+}
+
+// where diesel keeps the versions of the migrations that ran.
+table! {
+    __diesel_schema_migrations (version) {
+        version -> Varchar,
+        run_on -> Timestamp,
+    }
+}
+
+/// A migration and whether it ran on this database.
+pub struct MigrationStatus {
+    pub migration: &'static EmbeddedMigration,
+    pub applied: bool,
+}
+
+/// Runs the migrations that haven't run yet, oldest first, each in a transaction of its own.
+/// Returns the ones that ran.
+pub fn run(db: &DbConnection) -> Result<Vec<&'static EmbeddedMigration>, RunMigrationsError> {
+    let pending: Vec<_> = status(db)?
+        .into_iter()
+        .filter(|status| !status.applied)
+        .map(|status| status.migration)
+        .collect();
+
+    on_connection!(db, |db| diesel_migrations::run_migrations(
+        db,
+        pending.iter().map(|migration| *migration as &dyn Migration),
+        &mut std::io::sink(),
+    ))?;
+
+    Ok(pending)
+}
+
+/// Reverts the latest migration that ran, if any, by running its `down.sql`.
+pub fn revert_latest(db: &DbConnection) -> Result<Option<&'static EmbeddedMigration>, RunMigrationsError> {
+    use self::__diesel_schema_migrations::dsl::*;
+
+    on_connection!(db, |db| diesel_migrations::setup_database(db))?;
+
+    let latest = match on_connection!(db, |db| db.latest_run_migration_version())? {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+
+    let migration = migrations(db.backend())
+        .iter()
+        .find(|migration| migration.version == latest)
+        .ok_or(diesel_migrations::MigrationError::UnknownMigrationVersion(latest))?;
+
+    db.transaction(|| {
+        migration.revert(db)?;
+        diesel::delete(__diesel_schema_migrations.filter(version.eq(migration.version))).execute(db)?;
+
+        Ok(Some(migration))
+    })
+}
+
+/// Every migration of the database, and whether it ran.
+/// It only reads, so readiness probes can call it on a read-only database. Without diesel's table, nothing has run.
+pub fn status(db: &DbConnection) -> Result<Vec<MigrationStatus>, RunMigrationsError> {
+    use self::__diesel_schema_migrations::dsl::*;
+
+    let applied: Vec<String> = match __diesel_schema_migrations.select(version).load(db) {
+        Ok(applied) => applied,
+        Err(diesel::result::Error::DatabaseError(_, info)) if db.backend().is_missing_table(info.message()) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(migrations(db.backend())
+        .iter()
+        .map(|migration| MigrationStatus {
+            migration,
+            applied: applied.iter().any(|applied| applied == migration.version),
+        })
+        .collect())
+}
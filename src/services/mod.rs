@@ -1,2 +1,16 @@
 pub mod errors_service;
-pub mod auth_service;
\ No newline at end of file
+pub mod auth_service;
+pub mod cache_service;
+pub mod config_service;
+pub mod cors_service;
+pub mod fieldset_service;
+pub mod health_service;
+pub mod http_cache_service;
+pub mod migration_service;
+pub mod pagination_service;
+pub mod rate_limit_service;
+pub mod render_service;
+pub mod seed_service;
+pub mod template_service;
+pub mod transfer_service;
+pub mod validation_service;
@@ -0,0 +1,58 @@
+use actix_web::{http::header, HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use crate::models::pagination::{ListQuery, Paginated};
+
+/// Sends one page of a listing as a JSON array.
+/// The number of matching rows is in `X-Total-Count`, and the neighbouring pages are in `Link`.
+///
+/// Listings that use `offset` get `first`, `prev` and `next` offset links.
+/// Otherwise `next` continues from a cursor, which doesn't skip or repeat rows when rows are added in between.
+pub fn paginated_response<T: Serialize>(
+    req: &HttpRequest,
+    query: &ListQuery,
+    page: Paginated<T>,
+) -> HttpResponse {
+    let mut links = Vec::new();
+
+    if query.offset.is_some() && query.cursor.is_none() {
+        let offset = query.offset();
+        let limit = query.limit();
+
+        links.push(link(req, "offset", "0", "first"));
+        if offset > 0 {
+            links.push(link(req, "offset", &(offset - limit).max(0).to_string(), "prev"));
+        }
+        if offset + limit < page.total {
+            links.push(link(req, "offset", &(offset + limit).to_string(), "next"));
+        }
+    } else if let Some(cursor) = &page.next_cursor {
+        links.push(link(req, "cursor", cursor, "next"));
+    }
+
+    let mut res = HttpResponse::Ok();
+    res.header("X-Total-Count", page.total.to_string());
+
+    if !links.is_empty() {
+        res.header(header::LINK, links.join(", "));
+    }
+
+    res.json(page.items)
+}
+
+/// Links to the same listing with the paging parameter replaced. Every other parameter is kept as sent.
+fn link(req: &HttpRequest, key: &str, value: &str, rel: &str) -> String {
+    let mut params: Vec<&str> = req
+        .query_string()
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default();
+            !param.is_empty() && name != "offset" && name != "cursor"
+        })
+        .collect();
+
+    let paging = format!("{}={}", key, value);
+    params.push(&paging);
+
+    format!("<{}?{}>; rel=\"{}\"", req.path(), params.join("&"), rel)
+}
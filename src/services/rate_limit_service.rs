@@ -0,0 +1,493 @@
+use actix_web::body::{Body, MessageBody, ResponseBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, HeaderMap, HeaderName, HeaderValue};
+use actix_web::web::Data;
+use actix_web::{Error, ResponseError};
+use diesel::prelude::*;
+use futures::future::{ok, ready, LocalBoxFuture, Ready};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::models::config_models::LocalConfig;
+use crate::models::{run_query, DbPool, RunQueryDsl};
+use crate::services::auth_service::decrypt;
+use crate::services::config_service::{ConfigError, Settings};
+use crate::services::errors_service::CustomHttpError;
+use crate::services::health_service::PROBES;
+
+/// The policy of routes that `rate_limit_routes` gives no other, unless `rate_limits` names its own.
+pub const DEFAULT_POLICY: &str = "default";
+
+/// What `rate_limit_routes` gives routes that aren't limited, which aren't counted at all.
+pub const UNLIMITED: &str = "unlimited";
+
+/// Routes that aren't limited unless `rate_limit_routes` gives them a policy.
+const UNLIMITED_ROUTES: &[&str] = &["/assets"];
+
+/// How a policy tells clients apart. `User` and `Token` fall back to the address for requests without a valid token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClientKey {
+    Ip,
+    /// The user a token was issued to, so all of their logins share one budget.
+    User,
+    /// The token itself, so each login has its own.
+    Token,
+}
+
+/// A number of requests a client may make in a window of `seconds`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitPolicy {
+    pub name: String,
+    pub requests: u64,
+    pub seconds: u64,
+    pub key: ClientKey,
+}
+
+impl RateLimitPolicy {
+    /// Reads a policy in the form `10/300 by ip`. Without `by`, clients are told apart by address.
+    fn parse(name: &str, spec: &str) -> Option<Self> {
+        let mut words = spec.split_whitespace();
+        let (requests, seconds) = words.next()?.split_once('/')?;
+
+        let key = match (words.next(), words.next(), words.next()) {
+            (None, _, _) => ClientKey::Ip,
+            (Some("by"), Some("ip"), None) => ClientKey::Ip,
+            (Some("by"), Some("user"), None) => ClientKey::User,
+            (Some("by"), Some("token"), None) => ClientKey::Token,
+            _ => return None,
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            requests: requests.parse().ok()?,
+            seconds: seconds.parse().ok().filter(|seconds| *seconds > 0)?,
+            key,
+        })
+    }
+
+    /// The policy as `RateLimit-Policy` describes it, such as `10;w=300`.
+    fn describe(&self) -> String {
+        format!("{};w={}", self.requests, self.seconds)
+    }
+}
+
+/// The policies from the config and the routes they apply to.
+pub struct RateLimitRules {
+    policies: Vec<RateLimitPolicy>,
+    /// Path prefixes and the index of their policy, longest prefix first. `None` is unlimited.
+    routes: Vec<(String, Option<usize>)>,
+    default: usize,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl RateLimitRules {
+    pub fn from_config(conf: &LocalConfig) -> Result<Self, ConfigError> {
+        let mut policies = Vec::new();
+        for rule in rules(&conf.rate_limits) {
+            let policy = rule
+                .split_once('=')
+                .and_then(|(name, spec)| RateLimitPolicy::parse(name.trim(), spec))
+                .filter(|policy| policy.name != UNLIMITED)
+                .ok_or_else(|| {
+                    ConfigError::Invalid(
+                        "`rate_limits`".to_string(),
+                        format!(
+                            "`{}` isn't in the form `name=requests/seconds by ip|user|token`, with a name other than `{}`.",
+                            rule, UNLIMITED
+                        ),
+                    )
+                })?;
+
+            policies.push(policy);
+        }
+
+        // `max_req` keeps limiting every route that has no other policy.
+        if !policies.iter().any(|policy| policy.name == DEFAULT_POLICY) {
+            policies.push(RateLimitPolicy {
+                name: DEFAULT_POLICY.to_string(),
+                requests: u64::from(conf.max_req),
+                seconds: 60,
+                key: ClientKey::Ip,
+            });
+        }
+
+        let mut routes = Vec::new();
+        for rule in rules(&conf.rate_limit_routes) {
+            let (prefix, name) = rule.split_once('=').ok_or_else(|| {
+                ConfigError::Invalid(
+                    "`rate_limit_routes`".to_string(),
+                    format!("`{}` isn't in the form `/path=policy`.", rule),
+                )
+            })?;
+
+            let index = match name.trim() {
+                UNLIMITED => None,
+                name => Some(policies.iter().position(|policy| policy.name == name).ok_or_else(|| {
+                    ConfigError::Invalid(
+                        "`rate_limit_routes`".to_string(),
+                        format!("`{}` isn't a policy in `rate_limits` or `{}`.", name, UNLIMITED),
+                    )
+                })?),
+            };
+
+            routes.push((prefix.trim().to_string(), index));
+        }
+
+        for prefix in UNLIMITED_ROUTES {
+            if !routes.iter().any(|(route, _)| route == prefix) {
+                routes.push((prefix.to_string(), None));
+            }
+        }
+
+        // longest prefix first, so the first match is the most specific one.
+        routes.sort_by_key(|(prefix, _)| Reverse(prefix.len()));
+
+        let mut trusted_proxies = Vec::new();
+        for proxy in conf.trusted_proxies.iter().flatten() {
+            let proxy = proxy.trim().parse().map_err(|_| {
+                ConfigError::Invalid(
+                    "`trusted_proxies`".to_string(),
+                    format!("`{}` isn't an IP address.", proxy.trim()),
+                )
+            })?;
+
+            trusted_proxies.push(proxy);
+        }
+
+        Ok(Self {
+            default: policies
+                .iter()
+                .position(|policy| policy.name == DEFAULT_POLICY)
+                .unwrap_or_default(),
+            policies,
+            routes,
+            trusted_proxies,
+        })
+    }
+
+    /// The policy a path follows, or `None` if it isn't limited.
+    pub fn policy_for(&self, path: &str) -> Option<&RateLimitPolicy> {
+        let index = self
+            .routes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map_or(Some(self.default), |(_, index)| *index);
+
+        index.map(|index| &self.policies[index])
+    }
+
+    /// Who made the request, as the policy tells clients apart.
+    fn client(&self, req: &ServiceRequest, policy: &RateLimitPolicy) -> String {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|token| token.to_str().ok());
+
+        // a token that doesn't decrypt is counted by address, so made up tokens don't each get a budget of their own.
+        let claims = match policy.key {
+            ClientKey::Ip => None,
+            _ => token.and_then(|token| decrypt(&token.to_string()).ok()),
+        };
+
+        match (policy.key, token, claims) {
+            (ClientKey::User, _, Some(claims)) => format!("user:{}", claims.sub),
+            // tokens are hashed, so they aren't kept where the counts are.
+            // The digest is the same on every server, so servers sharing the database store count a token together.
+            (ClientKey::Token, Some(token), Some(_)) => format!("token:{:x}", Sha256::digest(token.as_bytes())),
+            _ => format!("ip:{}", self.client_ip(req)),
+        }
+    }
+
+    /// The address of the client. Behind trusted proxies it's read from `X-Forwarded-For`,
+    /// walking back from the proxy closest to the server to the first address that isn't one of them.
+    fn client_ip(&self, req: &ServiceRequest) -> String {
+        let mut ip = match req.peer_addr() {
+            Some(addr) => addr.ip(),
+            None => return String::new(),
+        };
+
+        let forwarded_for = req
+            .headers()
+            .get("x-forwarded-for")
+            .and_then(|forwarded_for| forwarded_for.to_str().ok())
+            .unwrap_or_default();
+
+        for hop in forwarded_for.rsplit(',') {
+            if !self.trusted_proxies.contains(&ip) {
+                break;
+            }
+
+            match hop.trim().parse() {
+                Ok(hop) => ip = hop,
+                Err(_) => break,
+            }
+        }
+
+        ip.to_string()
+    }
+}
+
+/// Splits a setting into its rules, which are separated by `;`.
+fn rules(setting: &Option<String>) -> impl Iterator<Item = &str> {
+    setting
+        .as_deref()
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+}
+
+/// Where requests are counted. Windows start at fixed times, so every server agrees on them.
+/// The app gets it from `web::Data<dyn RateLimitStore>`, set by `rate_limit_store`.
+pub trait RateLimitStore: Send + Sync {
+    /// Counts a request against `key` in the window that ends at `window_end`, in seconds since the epoch.
+    /// Returns the number of requests in the window, this one included.
+    fn hit(&self, key: String, window_end: u64) -> LocalBoxFuture<'static, Result<u64, CustomHttpError>>;
+}
+
+/// The store `rate_limit_store` asks for.
+pub fn store(conf: &LocalConfig, pool: &DbPool) -> Data<dyn RateLimitStore> {
+    match conf.rate_limit_store.as_deref() {
+        Some("database") => {
+            Data::from(Arc::new(DatabaseRateLimitStore::new(pool.clone())) as Arc<dyn RateLimitStore>)
+        }
+        _ => Data::from(Arc::new(MemoryRateLimitStore::default()) as Arc<dyn RateLimitStore>),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct Windows {
+    /// The end of each client's current window, and its requests in it.
+    clients: HashMap<String, (u64, u64)>,
+    last_pruned: u64,
+}
+
+/// Counts requests in memory, so each server has a budget of its own.
+#[derive(Default)]
+pub struct MemoryRateLimitStore {
+    windows: Mutex<Windows>,
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn hit(&self, key: String, window_end: u64) -> LocalBoxFuture<'static, Result<u64, CustomHttpError>> {
+        let mut windows = self.windows.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = now();
+
+        // forget the clients whose window is over, so there isn't one entry for every address ever seen.
+        if now >= windows.last_pruned + 60 {
+            windows.clients.retain(|_, (end, _)| *end > now);
+            windows.last_pruned = now;
+        }
+
+        let window = windows.clients.entry(key).or_insert((window_end, 0));
+        if window.0 != window_end {
+            *window = (window_end, 0);
+        }
+
+        window.1 += 1;
+        Box::pin(ready(Ok(window.1)))
+    }
+}
+
+/// Counts requests in the `rate_limit_windows` table, so every server on the database shares one budget.
+pub struct DatabaseRateLimitStore {
+    pool: Data<DbPool>,
+    last_pruned: Arc<Mutex<u64>>,
+}
+
+impl DatabaseRateLimitStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            pool: Data::new(pool),
+            last_pruned: Arc::new(Mutex::new(0)),
+        }
+    }
+}
+
+impl RateLimitStore for DatabaseRateLimitStore {
+    fn hit(&self, key: String, window_end: u64) -> LocalBoxFuture<'static, Result<u64, CustomHttpError>> {
+        use crate::schema::rate_limit_windows::dsl::{self, rate_limit_windows, requests};
+
+        let last_pruned = self.last_pruned.clone();
+        let end = window_end as i64;
+
+        Box::pin(run_query(self.pool.clone(), move |db| {
+            let window = rate_limit_windows.find((&key, end));
+
+            // another server may add the row between the update and the insert, so a taken key is updated after all.
+            let added = diesel::update(window).set(requests.eq(requests + 1)).execute(db)? > 0
+                || diesel::insert_into(rate_limit_windows)
+                    .values((dsl::client_key.eq(&key), dsl::window_end.eq(end), requests.eq(1)))
+                    .execute(db)
+                    .is_ok();
+            if !added {
+                diesel::update(window).set(requests.eq(requests + 1)).execute(db)?;
+            }
+
+            // the windows that are over are removed at most once a minute by each server.
+            let now = now();
+            let mut pruned = last_pruned.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if now >= *pruned + 60 {
+                *pruned = now;
+                diesel::delete(rate_limit_windows.filter(dsl::window_end.le(now as i64))).execute(db)?;
+            }
+
+            let count: i32 = window.select(requests).first(db)?;
+            Ok(count as u64)
+        }))
+    }
+}
+
+/// Limits requests by the policy of their route, and answers the ones over it with `429 Too Many Requests` and `Retry-After`.
+/// Responses describe the policy in `RateLimit-Limit`, `RateLimit-Remaining`, `RateLimit-Reset` and `RateLimit-Policy`.
+/// A request the store fails to count is let through, and probes aren't counted. The policies follow the config as it's reloaded.
+pub struct RateLimit {
+    settings: Data<Settings>,
+    store: Data<dyn RateLimitStore>,
+}
+
+impl RateLimit {
+    pub fn new(settings: Data<Settings>, store: Data<dyn RateLimitStore>) -> Self {
+        Self { settings, store }
+    }
+}
+
+impl<S, B> Transform<S> for RateLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let conf = self.settings.current();
+
+        ok(RateLimitMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            settings: self.settings.clone(),
+            store: self.store.clone(),
+            rules: Rc::new(rules_or_default(&conf)),
+            conf,
+        })
+    }
+}
+
+/// The rules of `conf`. The server checks its config before it's used, so only a config built in code falls back to the defaults.
+fn rules_or_default(conf: &LocalConfig) -> RateLimitRules {
+    RateLimitRules::from_config(conf).unwrap_or_else(|e| {
+        log::error!("Rate limits not read, using the defaults: {}", e);
+        RateLimitRules::from_config(&LocalConfig::default()).expect("the default rate limits read")
+    })
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    settings: Data<Settings>,
+    store: Data<dyn RateLimitStore>,
+    /// The config `rules` were read from, to tell when it's been reloaded.
+    conf: Arc<LocalConfig>,
+    rules: Rc<RateLimitRules>,
+}
+
+impl<S, B> Service for RateLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + Unpin + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let conf = self.settings.current();
+        if !Arc::ptr_eq(&conf, &self.conf) {
+            self.rules = Rc::new(rules_or_default(&conf));
+            self.conf = conf;
+        }
+
+        // a probe that's turned away would restart or unroute a server that's fine.
+        // Unlimited routes are passed on before the store is asked, so they cost it nothing.
+        let policy = match self.rules.policy_for(req.path()) {
+            Some(policy) if !PROBES.contains(&req.path()) => policy.clone(),
+            _ => {
+                let fut = self.service.borrow_mut().call(req);
+                return Box::pin(async move {
+                    Ok(fut.await?.map_body(|_, body| ResponseBody::Other(Body::from_message(body))))
+                });
+            }
+        };
+        let client = self.rules.client(&req, &policy);
+        let now = now();
+        let window_end = (now / policy.seconds + 1) * policy.seconds;
+        let reset = window_end - now;
+
+        let counted = self.store.hit(format!("{}:{}", policy.name, client), window_end);
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let requests = match counted.await {
+                Ok(requests) => Some(requests),
+                Err(e) => {
+                    log::error!("Rate limit not checked, letting the request through: {}", e);
+                    None
+                }
+            };
+
+            if requests.is_some_and(|requests| requests > policy.requests) {
+                log::info!("Rate limit `{}` exceeded by {}", policy.name, client);
+
+                let mut res = CustomHttpError::TooManyRequests.error_response();
+                set_headers(res.headers_mut(), &policy, 0, reset);
+                res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(reset));
+                return Ok(req.into_response(res));
+            }
+
+            let fut = service.borrow_mut().call(req);
+            let mut res = fut.await?;
+            if let Some(requests) = requests {
+                set_headers(res.headers_mut(), &policy, policy.requests - requests, reset);
+            }
+
+            Ok(res.map_body(|_, body| ResponseBody::Other(Body::from_message(body))))
+        })
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, policy: &RateLimitPolicy, remaining: u64, reset: u64) {
+    for (name, value) in [
+        ("ratelimit-limit", policy.requests.to_string()),
+        ("ratelimit-remaining", remaining.to_string()),
+        ("ratelimit-reset", reset.to_string()),
+        ("ratelimit-policy", policy.describe()),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use actix_web::{error::BlockingError, http::StatusCode, web, HttpResponse};
+use handlebars::Handlebars;
+use serde::Serialize;
+use serde_json::json;
+use std::cell::RefCell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::models::config_models::LocalConfig;
+use crate::services::template_service::Templates;
+
+thread_local! {
+    /// The pages listed by the render running on this thread, if it keeps track of them.
+    static LISTED_PAGES: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Called by helpers that show other pages, such as menus and `pages_by_term`,
+/// so the page being rendered is dropped from the cache when one of them changes.
+pub fn listed_page(page_uuid: &str) {
+    LISTED_PAGES.with(|pages| {
+        if let Some(pages) = pages.borrow_mut().as_mut() {
+            pages.push(page_uuid.to_string());
+        }
+    });
+}
+
+/// Renders a template, turning both render errors and panics inside helpers into an `Err`.
+fn render_now<T: Serialize>(
+    registry: &Handlebars<'_>,
+    template: &str,
+    data: &T,
+) -> Result<String, String> {
+    match catch_unwind(AssertUnwindSafe(|| registry.render(template, data))) {
+        Ok(Ok(s)) => Ok(s),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(panic) => Err(panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| String::from("Template rendering panicked."))),
+    }
+}
+
+/// Renders a template on the blocking thread pool, since helpers may query the database.
+pub async fn render<T: Serialize + Send + 'static>(
+    templates: web::Data<Templates>,
+    template: String,
+    data: T,
+) -> Result<String, String> {
+    let registry = templates.current();
+
+    web::block(move || render_now(&registry, &template, &data))
+        .await
+        .map_err(blocking_error)
+}
+
+/// Renders a page like `render`, along with the uuids of the other pages it lists.
+pub async fn render_page<T: Serialize + Send + 'static>(
+    templates: web::Data<Templates>,
+    template: String,
+    data: T,
+) -> Result<(String, Vec<String>), String> {
+    let registry = templates.current();
+
+    web::block(move || {
+        LISTED_PAGES.with(|pages| *pages.borrow_mut() = Some(Vec::new()));
+        let body = render_now(&registry, &template, &data);
+        let listed = LISTED_PAGES.with(|pages| pages.borrow_mut().take().unwrap_or_default());
+
+        body.map(|body| (body, listed))
+    })
+    .await
+    .map_err(blocking_error)
+}
+
+fn blocking_error(e: BlockingError<String>) -> String {
+    match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => String::from("Template rendering was canceled."),
+    }
+}
+
+/// Renders the error template for a status code.
+/// `410` falls back to `404` and anything else falls back to `500`.
+/// Error details are only passed to the template in development mode.
+pub async fn error_page(
+    templates: web::Data<Templates>,
+    status: StatusCode,
+    details: Option<String>,
+    conf: &LocalConfig,
+) -> HttpResponse {
+    let template = match status.as_u16() {
+        404 => "404",
+        410 if templates.current().get_template("410").is_some() => "410",
+        410 => "404",
+        _ => "500",
+    };
+
+    let data = json!({
+        "code": status.as_u16(),
+        "error": details.filter(|_| conf.development_mode.unwrap_or(false)),
+    });
+
+    let body = render(templates, template.to_string(), data)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Failed to render `{}` error template: {}", template, e);
+            String::from(status.canonical_reason().unwrap_or("Error"))
+        });
+
+    HttpResponse::build(status)
+        .content_type("text/html")
+        .body(body)
+}
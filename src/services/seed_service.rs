@@ -0,0 +1,207 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::auth_service::encrypt_password;
+use super::errors_service::CustomHttpError;
+use super::validation_service::validate;
+use crate::models::fixture_models::{Fixture, FixtureModule, FixturePage};
+use crate::models::module_models::{Module, ModuleCategory, MutCategory, MutModule};
+use crate::models::page_models::{MutPage, Page};
+use crate::models::user_models::{MutUser, User};
+use crate::models::{DbConnection, Model};
+
+const FIXTURE_DIR: &str = "./fixtures";
+const FIXTURE_EXTENSIONS: &[&str] = &["yaml", "yml", "json"];
+
+#[derive(Error, Debug)]
+pub enum SeedError {
+    #[error("There is no fixture set named `{0}` in {}.", FIXTURE_DIR)]
+    NotFound(String),
+    #[error("Failed to read fixture `{0}`: {1}")]
+    Unreadable(String, String),
+    #[error("Fixture `{0}` was not loaded: {1}")]
+    Rejected(String, String),
+}
+
+/// Rows added by seeding.
+#[derive(Debug, Default, PartialEq)]
+pub struct SeedReport {
+    pub pages: usize,
+    pub categories: usize,
+    pub modules: usize,
+    pub users: usize,
+}
+
+impl fmt::Display for SeedReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} pages, {} categories, {} modules and {} users added",
+            self.pages, self.categories, self.modules, self.users
+        )
+    }
+}
+
+/// Splits a list of fixture sets such as `root,demo`.
+pub fn fixture_names(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Loads the fixture sets in `names`, in order. A name is looked up in the fixtures folder,
+/// unless it ends in `.yaml`, `.yml` or `.json`, in which case it's the path to the file.
+///
+/// Every set is read before anything is written, and they're all written in one transaction.
+/// Pages whose url is taken and users whose username is taken are skipped with everything in them,
+/// so seeding the same sets again adds nothing.
+pub fn seed(names: &[String], db: &DbConnection) -> Result<SeedReport, SeedError> {
+    let fixtures = names
+        .iter()
+        .map(|name| Ok((name, read_fixture(&find_fixture(name)?, name)?)))
+        .collect::<Result<Vec<_>, SeedError>>()?;
+
+    let mut report = SeedReport::default();
+    let mut failed = None;
+
+    db.transaction::<_, CustomHttpError, _>(|| {
+        for (name, fixture) in &fixtures {
+            failed = Some(name.to_string());
+            apply(fixture, &mut report, db)?;
+        }
+
+        Ok(())
+    })
+    .map_err(|e| SeedError::Rejected(failed.unwrap_or_default(), e.describe()))?;
+
+    Ok(report)
+}
+
+fn find_fixture(name: &str) -> Result<PathBuf, SeedError> {
+    let path = Path::new(name);
+    let is_file = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some(extension) if FIXTURE_EXTENSIONS.contains(&extension)
+    );
+
+    if is_file {
+        return Ok(path.to_path_buf());
+    }
+
+    FIXTURE_EXTENSIONS
+        .iter()
+        .map(|extension| Path::new(FIXTURE_DIR).join(format!("{}.{}", name, extension)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| SeedError::NotFound(name.to_string()))
+}
+
+fn read_fixture(path: &Path, name: &str) -> Result<Fixture, SeedError> {
+    let unreadable = |e: String| SeedError::Unreadable(name.to_string(), e);
+    let text = std::fs::read_to_string(path).map_err(|e| unreadable(e.to_string()))?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => serde_json::from_str(&text).map_err(|e| unreadable(e.to_string())),
+        _ => serde_yaml::from_str(&text).map_err(|e| unreadable(e.to_string())),
+    }
+}
+
+fn apply(fixture: &Fixture, report: &mut SeedReport, db: &DbConnection) -> Result<(), CustomHttpError> {
+    for page in &fixture.pages {
+        apply_page(page, report, db)?;
+    }
+
+    for user in &fixture.users {
+        match User::read_one(user.username.clone(), db) {
+            Ok(_) => continue,
+            Err(diesel::result::Error::NotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut new_user = MutUser {
+            uuid: Some(Uuid::new_v4().to_string()),
+            username: user.username.clone(),
+            password: Some(user.password.clone()),
+            token: None,
+            role: None,
+        };
+
+        if !user.password.is_empty() {
+            validate(&new_user, None, db)?;
+            new_user.password = Some(encrypt_password(&user.password)?);
+        }
+
+        User::create(&new_user, db)?;
+        report.users += 1;
+    }
+
+    Ok(())
+}
+
+fn apply_page(page: &FixturePage, report: &mut SeedReport, db: &DbConnection) -> Result<(), CustomHttpError> {
+    let page_uuid = Uuid::new_v4().to_string();
+    let new_page = MutPage {
+        uuid: Some(page_uuid.clone()),
+        page_name: page.page_name.clone(),
+        page_url: page.page_url.clone(),
+        page_title: page.page_title.clone(),
+        cache_control: page.cache_control.clone().map(Some),
+    };
+
+    // the only conflict a new page can have is its url, which means it was seeded before.
+    match validate(&new_page, None, db) {
+        Err(CustomHttpError::Conflict(_)) => return Ok(()),
+        result => result?,
+    }
+
+    Page::create(&new_page, db)?;
+    report.pages += 1;
+
+    for module in &page.modules {
+        apply_module(module, &page_uuid, None, report, db)?;
+    }
+
+    for category in &page.categories {
+        let category_uuid = Uuid::new_v4().to_string();
+        let new_category = MutCategory {
+            uuid: Some(category_uuid.clone()),
+            title: category.title.clone(),
+            page_uuid: page_uuid.clone(),
+        };
+
+        validate(&new_category, None, db)?;
+        ModuleCategory::create(&new_category, db)?;
+        report.categories += 1;
+
+        for module in &category.modules {
+            apply_module(module, &page_uuid, Some(&category_uuid), report, db)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_module(
+    module: &FixtureModule,
+    page_uuid: &str,
+    category_uuid: Option<&str>,
+    report: &mut SeedReport,
+    db: &DbConnection,
+) -> Result<(), CustomHttpError> {
+    let new_module = MutModule {
+        uuid: Some(Uuid::new_v4().to_string()),
+        title: module.title.clone(),
+        page_uuid: page_uuid.to_string(),
+        category_uuid: category_uuid.map(String::from),
+        content: module.content.clone(),
+    };
+
+    validate(&new_module, None, db)?;
+    Module::create(&new_module, db)?;
+    report.modules += 1;
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use arc_swap::ArcSwap;
+use handlebars::{Handlebars, Template};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::helpers;
+use crate::models::DbPool;
+
+const TEMPLATE_DIR: &str = "./templates";
+
+/// The template registry shared by every render.
+///
+/// Renders take a snapshot of the current registry and never wait on each other or on a reload.
+/// A reload builds a complete new registry and only swaps it in if every template compiled,
+/// so a half-saved template never takes the site down.
+pub struct Templates {
+    current: ArcSwap<Handlebars<'static>>,
+    pool: DbPool,
+}
+
+impl Templates {
+    pub fn load(pool: DbPool) -> Result<Self, String> {
+        let registry = build_registry(&pool)?;
+
+        Ok(Self {
+            current: ArcSwap::from_pointee(registry),
+            pool,
+        })
+    }
+
+    /// Returns the registry to render with. Renders already in progress keep the registry they started with.
+    pub fn current(&self) -> Arc<Handlebars<'static>> {
+        self.current.load_full()
+    }
+
+    /// Rebuilds the registry from the templates directory, keeping the current one if any template fails to compile.
+    pub fn reload(&self) -> Result<(), String> {
+        let registry = build_registry(&self.pool)?;
+        self.current.store(Arc::new(registry));
+
+        Ok(())
+    }
+}
+
+fn build_registry(pool: &DbPool) -> Result<Handlebars<'static>, String> {
+    let mut handlebars = Handlebars::new();
+
+    handlebars
+        .register_templates_directory(".hbs", TEMPLATE_DIR)
+        .map_err(|e| e.to_string())?;
+
+    helpers::default::register_helpers(&mut handlebars);
+    helpers::collections::register_helpers(&mut handlebars, pool.clone());
+    helpers::taxonomy::register_helpers(&mut handlebars, pool.clone());
+    helpers::menus::register_helpers(&mut handlebars, pool.clone());
+
+    Ok(handlebars)
+}
+
+/// Compiles every template in the templates directory without loading them, for `radical check-templates`.
+/// Returns how many there are, or an error for each one that doesn't compile rather than only the first.
+pub fn check() -> Result<usize, Vec<String>> {
+    let files = template_files(Path::new(TEMPLATE_DIR)).map_err(|e| vec![format!("{}: {}", TEMPLATE_DIR, e)])?;
+
+    let errors: Vec<String> = files
+        .iter()
+        .filter_map(|path| {
+            let name = path.display().to_string();
+
+            match std::fs::read_to_string(path) {
+                Ok(source) => Template::compile(source).err().map(|e| e.in_template(name).to_string()),
+                Err(e) => Some(format!("{}: {}", name, e)),
+            }
+        })
+        .collect();
+
+    match errors.is_empty() {
+        true => Ok(files.len()),
+        false => Err(errors),
+    }
+}
+
+fn template_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            files.extend(template_files(&path)?);
+        } else if path.extension().and_then(|extension| extension.to_str()) == Some("hbs") {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,192 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+use super::errors_service::CustomHttpError;
+use super::migration_service;
+use crate::models::collection_models::{Collection, CollectionEntry};
+use crate::models::menu_models::{Menu, MenuItem};
+use crate::models::module_models::{Module, ModuleCategory};
+use crate::models::page_models::Page;
+use crate::models::redirect_models::Redirect;
+use crate::models::taxonomy_models::{PageTerm, Term, Vocabulary};
+#[cfg(feature = "sqlite")]
+use crate::models::backend::Backend;
+use crate::models::{DbConnection, RunQueryDsl};
+use crate::schema::{
+    collection_entries, collections, menu_items, menus, module_category, modules, page_terms, pages, redirects,
+    terms, vocabularies,
+};
+
+/// Inserts rows one at a time, since SQLite can't insert several in one statement.
+macro_rules! insert_all {
+    ($table:expr, $rows:expr, $db:expr) => {
+        for row in $rows {
+            diesel::insert_into($table).values(row).execute($db)?;
+        }
+    };
+}
+
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("The export was made at migration {0}, but this database is at {1}. Migrate one of them so they match.")]
+    Migration(String, String),
+    #[error("Failed to read the migrations: {0}")]
+    Migrations(#[from] diesel_migrations::RunMigrationsError),
+    #[error("Nothing was imported: {0}")]
+    Rejected(String),
+}
+
+impl From<diesel::result::Error> for TransferError {
+    fn from(e: diesel::result::Error) -> Self {
+        Self::Rejected(CustomHttpError::from(e).describe())
+    }
+}
+
+/// The content of a site, as written by `radical export` and read by `radical import`.
+/// Rows keep their uuids, so references between them hold. Users are left out, since they carry passwords.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Export {
+    /// The latest migration of the database it came from. Only a database at the same migration can import it.
+    pub migration: String,
+    pub pages: Vec<Page>,
+    pub categories: Vec<ModuleCategory>,
+    pub modules: Vec<Module>,
+    pub collections: Vec<Collection>,
+    pub collection_entries: Vec<CollectionEntry>,
+    pub vocabularies: Vec<Vocabulary>,
+    /// Parents come before their children.
+    pub terms: Vec<Term>,
+    pub page_terms: Vec<PageTerm>,
+    pub menus: Vec<Menu>,
+    /// Parents come before their children.
+    pub menu_items: Vec<MenuItem>,
+    pub redirects: Vec<Redirect>,
+}
+
+impl Export {
+    pub fn rows(&self) -> usize {
+        self.pages.len()
+            + self.categories.len()
+            + self.modules.len()
+            + self.collections.len()
+            + self.collection_entries.len()
+            + self.vocabularies.len()
+            + self.terms.len()
+            + self.page_terms.len()
+            + self.menus.len()
+            + self.menu_items.len()
+            + self.redirects.len()
+    }
+}
+
+/// Reads every row of content in the database.
+pub fn export(db: &DbConnection) -> Result<Export, TransferError> {
+    Ok(Export {
+        migration: latest_migration(db)?,
+        pages: pages::table.order(pages::uuid).load(db)?,
+        categories: module_category::table.order(module_category::uuid).load(db)?,
+        modules: modules::table.order(modules::uuid).load(db)?,
+        collections: collections::table.order(collections::uuid).load(db)?,
+        collection_entries: collection_entries::table.order(collection_entries::uuid).load(db)?,
+        vocabularies: vocabularies::table.order(vocabularies::uuid).load(db)?,
+        terms: parents_first(terms::table.order(terms::uuid).load(db)?, |term: &Term| {
+            (&term.uuid, &term.parent_uuid)
+        }),
+        page_terms: page_terms::table
+            .order((page_terms::page_uuid, page_terms::term_uuid))
+            .load(db)?,
+        menus: menus::table.order(menus::uuid).load(db)?,
+        menu_items: parents_first(menu_items::table.order(menu_items::uuid).load(db)?, |item: &MenuItem| {
+            (&item.uuid, &item.parent_uuid)
+        }),
+        redirects: redirects::table.order(redirects::uuid).load(db)?,
+    })
+}
+
+/// Adds every row of `export` to the database, in one transaction.
+/// A row whose uuid, url or name is already taken stops the import, and nothing is added.
+pub fn import(export: &Export, db: &DbConnection) -> Result<usize, TransferError> {
+    let migration = latest_migration(db)?;
+    if export.migration != migration {
+        return Err(TransferError::Migration(export.migration.clone(), migration));
+    }
+
+    db.transaction::<_, TransferError, _>(|| {
+        insert_all!(pages::table, &export.pages, db);
+        insert_all!(module_category::table, &export.categories, db);
+        insert_all!(modules::table, &export.modules, db);
+        insert_all!(collections::table, &export.collections, db);
+        insert_all!(collection_entries::table, &export.collection_entries, db);
+        insert_all!(vocabularies::table, &export.vocabularies, db);
+        insert_all!(terms::table, &export.terms, db);
+        insert_all!(page_terms::table, &export.page_terms, db);
+        insert_all!(menus::table, &export.menus, db);
+        insert_all!(menu_items::table, &export.menu_items, db);
+        insert_all!(redirects::table, &export.redirects, db);
+
+        // SQLite's insert triggers stamp `time_updated` over the exported one, and its update triggers leave a set one alone.
+        #[cfg(feature = "sqlite")]
+        if db.backend() == Backend::Sqlite {
+            restore_time_updated(export, db)?;
+        }
+
+        Ok(export.rows())
+    })
+}
+
+#[cfg(feature = "sqlite")]
+fn restore_time_updated(export: &Export, db: &DbConnection) -> Result<(), diesel::result::Error> {
+    for page in &export.pages {
+        diesel::update(pages::table.find(&page.uuid))
+            .set(pages::time_updated.eq(page.time_updated))
+            .execute(db)?;
+    }
+
+    for module in &export.modules {
+        diesel::update(modules::table.find(&module.uuid))
+            .set(modules::time_updated.eq(module.time_updated))
+            .execute(db)?;
+    }
+
+    Ok(())
+}
+
+/// The latest migration that ran, which an export has to match to be imported.
+fn latest_migration(db: &DbConnection) -> Result<String, TransferError> {
+    Ok(migration_service::status(db)?
+        .into_iter()
+        .rev()
+        .find(|status| status.applied)
+        .map(|status| status.migration.version.to_string())
+        .unwrap_or_default())
+}
+
+/// Orders rows that can point at a parent of the same table so no row comes before its parent,
+/// since the foreign key is checked as each row is inserted.
+fn parents_first<T>(rows: Vec<T>, key: impl Fn(&T) -> (&String, &Option<String>)) -> Vec<T> {
+    let mut placed: HashSet<String> = HashSet::new();
+    let mut ordered = Vec::with_capacity(rows.len());
+    let mut remaining = rows;
+
+    while !remaining.is_empty() {
+        let (ready, waiting): (Vec<T>, Vec<T>) = remaining.into_iter().partition(|row| match key(row).1 {
+            Some(parent) => placed.contains(parent),
+            None => true,
+        });
+
+        // a parent that isn't there can't be waited for, so the rest go in as they are.
+        if ready.is_empty() {
+            ordered.extend(waiting);
+            break;
+        }
+
+        placed.extend(ready.iter().map(|row| key(row).0.clone()));
+        ordered.extend(ready);
+        remaining = waiting;
+    }
+
+    ordered
+}
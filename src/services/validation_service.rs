@@ -0,0 +1,117 @@
+use std::borrow::Cow;
+
+use validator::{Validate, ValidationError};
+
+use super::errors_service::CustomHttpError;
+use crate::models::collection_models::CollectionField;
+use crate::models::user_models::ROLES;
+use crate::models::{DbConnection, ValidateRows};
+
+/// Largest value a MySQL `TEXT` column holds, in bytes.
+pub const MAX_TEXT_BYTES: usize = 65_535;
+
+/// Checks `new` before it's written, first against its `#[validate]` rules and then against the database.
+/// `id` is the row being updated, or `None` for a new row.
+///
+/// Broken rules are sent back as a 422 listing each field, and taken unique values as a 409.
+pub fn validate<T: Validate + ValidateRows>(
+    new: &T,
+    id: Option<&str>,
+    db: &DbConnection,
+) -> Result<(), CustomHttpError> {
+    new.validate()?;
+    new.validate_rows(id, db)
+}
+
+fn error(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::from(message));
+    error
+}
+
+/// An absolute path on this site, such as `/blog/first-post`.
+pub fn validate_path(path: &str) -> Result<(), ValidationError> {
+    let valid = path.starts_with('/')
+        && !path.contains(|c: char| c.is_whitespace() || c == '?' || c == '#');
+
+    match valid {
+        true => Ok(()),
+        false => Err(error(
+            "path",
+            "Must start with `/` and can't contain spaces, `?` or `#`.",
+        )),
+    }
+}
+
+/// A template name, which is a path inside the templates folder without the extension.
+pub fn validate_template_name(name: &str) -> Result<(), ValidationError> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '/')
+        && !name.starts_with('/')
+        && !name.contains("//");
+
+    match valid {
+        true => Ok(()),
+        false => Err(error(
+            "template_name",
+            "Must be a template name made of letters, numbers, `_`, `-` and `/`.",
+        )),
+    }
+}
+
+/// A name used in urls, such as a collection's `blog` in `/v1/collections/blog/entries`.
+pub fn validate_slug(slug: &str) -> Result<(), ValidationError> {
+    let valid = !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+
+    match valid {
+        true => Ok(()),
+        false => Err(error(
+            "slug",
+            "Must be lowercase letters, numbers, `_` and `-`.",
+        )),
+    }
+}
+
+/// The field definitions of a collection, as a JSON array of `CollectionField`.
+pub fn validate_collection_fields(fields: &str) -> Result<(), ValidationError> {
+    match serde_json::from_str::<Vec<CollectionField>>(fields) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(error(
+            "fields",
+            "Must be a list of fields, each with a `name` and a `field_type` of `text`, `number` or `boolean`.",
+        )),
+    }
+}
+
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
+    let valid = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '@');
+
+    match valid {
+        true => Ok(()),
+        false => Err(error(
+            "username",
+            "Can only contain letters, numbers, `_`, `-`, `.` and `@`.",
+        )),
+    }
+}
+
+pub fn validate_role(role: &str) -> Result<(), ValidationError> {
+    match ROLES.contains(&role) {
+        true => Ok(()),
+        false => Err(error("role", "Must be `admin` or `editor`.")),
+    }
+}
+
+/// Fits in a `TEXT` column. Lengths elsewhere are counted in characters, but MySQL limits `TEXT` by bytes.
+pub fn validate_text_size(text: &str) -> Result<(), ValidationError> {
+    match text.len() <= MAX_TEXT_BYTES {
+        true => Ok(()),
+        false => Err(error("length", "Must be at most 65535 bytes.")),
+    }
+}
@@ -0,0 +1,95 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn batches_apply_every_operation() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app
+        .post(
+            "/v1/batch",
+            json!({ "operations": [
+                { "resource": "page", "op": "create", "data": { "page_name": "index", "page_url": "/about", "page_title": "About" } },
+                { "resource": "module", "op": "create", "data": { "title": "extra", "page_uuid": page_uuid, "content": "More." } },
+                { "resource": "page", "op": "update", "id": page_uuid, "version": 1,
+                  "data": { "page_name": "index", "page_url": "/", "page_title": "Welcome" } }
+            ] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+    let results = res.json();
+    assert_eq!(results[0]["status"], 201);
+    assert_eq!(results[1]["status"], 201);
+    assert_eq!(results[2]["status"], 200);
+    assert_eq!(results[2]["version"], 2);
+
+    let res = app.get("/v1/pages?sort=page_url").await;
+    assert_eq!(res.json()[0]["page_title"], "Welcome");
+    assert_eq!(res.json()[1]["page_url"], "/about");
+}
+
+#[actix_rt::test]
+async fn a_failed_operation_rolls_back_the_batch() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app
+        .post(
+            "/v1/batch",
+            json!({ "operations": [
+                { "resource": "page", "op": "create", "data": { "page_name": "index", "page_url": "/about", "page_title": "About" } },
+                { "resource": "page", "op": "delete", "id": page_uuid, "version": 9 }
+            ] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(res.json()["operation"], 1);
+
+    let res = app.get("/v1/pages").await;
+    assert_eq!(res.header("x-total-count").as_deref(), Some("1"));
+}
+
+#[actix_rt::test]
+async fn operations_refer_to_rows_created_earlier_in_the_batch() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post(
+            "/v1/batch",
+            json!({ "operations": [
+                { "resource": "page", "op": "create", "data": { "page_name": "index", "page_url": "/about", "page_title": "About" } },
+                { "resource": "category", "op": "create", "data": { "title": "team", "page_uuid": "$ref:0" } },
+                { "resource": "module", "op": "create",
+                  "data": { "title": "intro", "page_uuid": "$ref:0", "category_uuid": "$ref:1", "content": "Hello." } }
+            ] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+    let results = res.json();
+    let res = app.get(&format!("/v1/modules/{}", results[2]["uuid"].as_str().unwrap())).await;
+    assert_eq!(res.json()["page_uuid"], results[0]["uuid"]);
+    assert_eq!(res.json()["category_uuid"], results[1]["uuid"]);
+
+    // a reference can only go back to an operation that has already been applied.
+    let res = app
+        .post(
+            "/v1/batch",
+            json!({ "operations": [
+                { "resource": "page", "op": "create", "data": { "page_name": "index", "page_url": "/team", "page_title": "Team" } },
+                { "resource": "module", "op": "create", "data": { "title": "intro", "page_uuid": "$ref:2", "content": "Hello." } }
+            ] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["operation"], 1);
+    assert_eq!(res.json()["errors"][0]["field"], "data.page_uuid");
+    assert_eq!(app.get("/v1/pages").await.header("x-total-count").as_deref(), Some("2"));
+}
@@ -0,0 +1,133 @@
+use actix_web::http::StatusCode;
+use chrono::Utc;
+use serde_json::json;
+use std::time::Duration;
+
+use super::spawn;
+use crate::services::cache_service::{PageCache, RenderedPage};
+
+#[actix_rt::test]
+async fn rendered_pages_are_cached_until_they_change() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    app.get("/").await;
+    app.get("/").await;
+
+    let stats = app.get("/v1/cache").await.json();
+    assert_eq!(stats["entries"], 1);
+    assert_eq!(stats["misses"], 1);
+    assert_eq!(stats["hits"], 1);
+
+    let res = app
+        .post("/v1/modules", json!({ "title": "small", "page_uuid": page_uuid, "content": "Changed." }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let stats = app.get("/v1/cache").await.json();
+    assert_eq!(stats["entries"], 0);
+    assert_eq!(stats["invalidations"], 1);
+}
+
+#[actix_rt::test]
+async fn the_cache_is_cleared_on_request() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    app.get("/").await;
+
+    let res = app.delete("/v1/cache").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()["entries"], 0);
+}
+
+#[actix_rt::test]
+async fn cache_stats_need_a_token() {
+    let mut app = spawn().await;
+
+    assert_eq!(app.get("/v1/cache").await.status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_rt::test]
+async fn moving_a_page_refreshes_the_menus_on_other_pages() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About us").await;
+    app.post("/v1/menus", json!({ "name": "main", "title": "Main" })).await;
+    let res = app
+        .post("/v1/menus/main/items", json!({ "title": "About", "page_uuid": uuid }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    assert!(app.get("/").await.text().contains("href=\"/about\""));
+
+    let page = json!({ "page_name": "index", "page_url": "/team", "page_title": "Our team" });
+    let res = app.put_versioned(&format!("/v1/pages/{}", uuid), "\"1\"", page).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+    let home = app.get("/").await.text();
+    assert!(home.contains("href=\"/team\""), "{}", home);
+
+    app.delete_versioned(&format!("/v1/pages/{}", uuid), "\"2\"").await;
+    assert!(!app.get("/").await.text().contains("href=\"/team\""));
+}
+
+#[actix_rt::test]
+async fn changing_a_page_keeps_the_pages_that_dont_show_it() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let about = app.create_page("/about", "About us").await;
+    let contact = app.create_page("/contact", "Contact").await;
+    app.post("/v1/menus", json!({ "name": "main", "title": "Main" })).await;
+    app.post("/v1/menus/main/items", json!({ "title": "About", "page_uuid": about })).await;
+
+    for url in &["/", "/about", "/contact"] {
+        app.get(url).await;
+    }
+    assert_eq!(app.get("/v1/cache").await.json()["entries"], 3);
+
+    // the contact page isn't in a menu, so only its own render goes.
+    let page = json!({ "page_name": "index", "page_url": "/contact", "page_title": "Contact", "cache_control": "no-cache" });
+    let res = app.put_versioned(&format!("/v1/pages/{}", contact), "\"1\"", page).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+    let stats = app.get("/v1/cache").await.json();
+    assert_eq!(stats["entries"], 2);
+    assert_eq!(stats["invalidations"], 1);
+
+    // the about page is in the menu on the home page, so that render goes with it.
+    app.delete_versioned(&format!("/v1/pages/{}", about), "\"1\"").await;
+
+    let stats = app.get("/v1/cache").await.json();
+    assert_eq!(stats["entries"], 0);
+    assert_eq!(stats["invalidations"], 3);
+
+    app.get("/contact").await;
+    app.get("/").await;
+    let page = json!({ "page_name": "index", "page_url": "/contact", "page_title": "Get in touch" });
+    app.put_versioned(&format!("/v1/pages/{}", contact), "\"2\"", page).await;
+    assert_eq!(app.get("/v1/cache").await.json()["entries"], 0);
+}
+
+#[test]
+fn a_render_from_before_an_invalidation_is_not_cached() {
+    let cache = PageCache::new(10, Duration::from_secs(60));
+    let page = RenderedPage {
+        body: "old".to_string(),
+        last_modified: Utc::now().naive_utc(),
+        cache_control: None,
+    };
+
+    // the page is read, then changed and invalidated before its render is inserted.
+    let generation = cache.generation();
+    cache.invalidate_page("page");
+    cache.insert("/", "page", &[], &page, generation);
+    assert!(cache.get("/").is_none());
+
+    let generation = cache.generation();
+    cache.insert("/", "page", &[], &page, generation);
+    assert_eq!(cache.get("/").unwrap().body, "old");
+}
@@ -0,0 +1,67 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn categories_hold_modules() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app
+        .post("/v1/category", json!({ "title": "links", "page_uuid": page_uuid }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .post(
+            "/v1/modules",
+            json!({ "title": "docs", "page_uuid": page_uuid, "category_uuid": uuid, "content": "/docs" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let res = app.get(&format!("/v1/category/{}", uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()["title"], "links");
+
+    let res = app.get(&format!("/v1/modules/category/{}", uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()[0]["content"], "/docs");
+}
+
+#[actix_rt::test]
+async fn categories_are_updated_and_deleted_by_version() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app
+        .post("/v1/category", json!({ "title": "links", "page_uuid": page_uuid }))
+        .await;
+    let path = format!("/v1/category/{}", res.json()["uuid"].as_str().unwrap());
+    let body = json!({ "title": "more links", "page_uuid": page_uuid });
+
+    assert_eq!(app.put(&path, body.clone()).await.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let res = app.put_versioned(&path, "\"1\"", body.clone()).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get(&path).await.json()["title"], "more links");
+
+    assert_eq!(app.delete_versioned(&path, "\"1\"").await.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(app.delete_versioned(&path, "\"2\"").await.status, StatusCode::OK);
+    assert_eq!(app.get(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn categories_need_an_existing_page() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/category", json!({ "title": "links", "page_uuid": "nowhere" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,142 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::{database, start};
+use crate::cli::user;
+use crate::models::menu_models::{Menu, MenuItem, MutMenu, MutMenuItem};
+use crate::models::Model;
+use crate::services::errors_service::CustomHttpError;
+use crate::services::seed_service::{fixture_names, seed};
+use crate::services::{migration_service, template_service, transfer_service};
+
+#[test]
+fn migrations_are_reverted_and_run_again() {
+    let (pool, _) = database();
+    let db = pool.get().unwrap();
+    let latest = migration_service::migrations(pool.backend()).last().unwrap();
+
+    assert!(migration_service::status(&db).unwrap().iter().all(|status| status.applied));
+
+    let reverted = migration_service::revert_latest(&db).unwrap().unwrap();
+    assert_eq!(reverted.version, latest.version);
+
+    let status = migration_service::status(&db).unwrap();
+    assert!(!status.last().unwrap().applied);
+    assert!(status[0].applied);
+
+    let ran = migration_service::run(&db).unwrap();
+    assert_eq!(ran.iter().map(|migration| migration.version).collect::<Vec<_>>(), vec![latest.version]);
+    assert!(migration_service::run(&db).unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn users_are_managed_from_the_command_line() {
+    let (pool, conf) = database();
+    let db = pool.get().unwrap();
+    user::create("ops", "correct horse", "admin", &db).unwrap();
+
+    match user::create("short", "horse", "admin", &db) {
+        Err(e @ CustomHttpError::Invalid(_)) => assert!(e.describe().contains("`password`"), "{}", e.describe()),
+        other => panic!("Expected the password to be rejected, got {:?}", other),
+    }
+    assert!(matches!(user::set_role("ops", "owner", &db), Err(CustomHttpError::Invalid(_))));
+    assert!(matches!(user::set_role("nobody", "editor", &db), Err(CustomHttpError::NotFound)));
+
+    let mut app = start(pool.clone(), conf).await;
+    assert_eq!(app.login("ops", "correct horse").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/user").await.status, StatusCode::OK);
+
+    // a reset logs the user out.
+    user::reset_password("ops", "battery staple", &db).unwrap();
+    assert_eq!(app.get("/v1/user").await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login("ops", "correct horse").await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login("ops", "battery staple").await.status, StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn only_admins_manage_users() {
+    let (pool, conf) = database();
+    let db = pool.get().unwrap();
+    user::create("ops", "correct horse", "admin", &db).unwrap();
+    user::create("writer", "correct horse", "editor", &db).unwrap();
+
+    let mut app = start(pool.clone(), conf).await;
+    let new_user = json!({ "username": "guest", "password": "correct horse" });
+
+    app.login("writer", "correct horse").await;
+    let res = app.post("/v1/user", new_user.clone()).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.json()["code"], "forbidden");
+    assert_eq!(app.delete("/v1/user/ops").await.status, StatusCode::FORBIDDEN);
+
+    // editors still manage content.
+    let res = app
+        .post("/v1/pages", json!({ "page_name": "index", "page_url": "/a", "page_title": "A" }))
+        .await;
+    assert!(res.status.is_success(), "{}", res.text());
+
+    user::set_role("writer", "admin", &db).unwrap();
+    assert_eq!(app.post("/v1/user", new_user).await.status, StatusCode::CREATED);
+    assert!(app.delete("/v1/user/guest").await.status.is_success());
+}
+
+#[test]
+fn content_is_exported_and_imported() {
+    let (from, _) = database();
+    let from_db = from.get().unwrap();
+    seed(&fixture_names("root, demo"), &from_db).unwrap();
+
+    // the child's uuid sorts first, so the export has to put its parent before it.
+    let menu = MutMenu {
+        uuid: Some("menu".to_string()),
+        name: "main".to_string(),
+        title: "Main".to_string(),
+    };
+    Menu::create(&menu, &from_db).unwrap();
+    for (uuid, parent_uuid) in &[("b-parent", None), ("a-child", Some("b-parent".to_string()))] {
+        let item = MutMenuItem {
+            uuid: uuid.to_string(),
+            menu_uuid: "menu".to_string(),
+            parent_uuid: parent_uuid.clone(),
+            position: 0,
+            title: uuid.to_string(),
+            page_uuid: None,
+            url: Some("https://example.com".to_string()),
+        };
+        MenuItem::create(&item, &from_db).unwrap();
+    }
+
+    let mut export = transfer_service::export(&from_db).unwrap();
+    // an older export, so a database stamping its own time would show.
+    let long_ago = chrono::NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0);
+    export.pages[0].time_updated = long_ago;
+    export.modules[0].time_updated = long_ago;
+    assert_eq!(export.pages.len(), 1);
+    assert_eq!(export.menu_items[0].uuid, "b-parent");
+
+    let (to, _) = database();
+    let to_db = to.get().unwrap();
+    assert_eq!(transfer_service::import(&export, &to_db).unwrap(), export.rows());
+
+    let imported = transfer_service::export(&to_db).unwrap();
+    assert_eq!(serde_json::to_value(&imported).unwrap(), serde_json::to_value(&export).unwrap());
+
+    // everything is already there, so nothing more is added.
+    assert!(matches!(
+        transfer_service::import(&export, &to_db),
+        Err(transfer_service::TransferError::Rejected(_))
+    ));
+    assert_eq!(transfer_service::export(&to_db).unwrap().rows(), export.rows());
+
+    let mut older = export;
+    older.migration = "20201218181841".to_string();
+    assert!(matches!(
+        transfer_service::import(&older, &to_db),
+        Err(transfer_service::TransferError::Migration(_, _))
+    ));
+}
+
+#[test]
+fn templates_are_checked() {
+    assert_eq!(template_service::check(), Ok(3));
+}
@@ -0,0 +1,200 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn collections_are_created_updated_and_deleted() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let fields = json!([{ "name": "title", "field_type": "text", "required": true }]);
+    let res = app
+        .post("/v1/collections", json!({ "name": "posts", "title": "Posts", "fields": fields }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let res = app.get("/v1/collections/posts").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()["fields"][0]["name"], "title");
+
+    let res = app
+        .put("/v1/collections/posts", json!({ "name": "posts", "title": "Blog", "fields": fields }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get("/v1/collections").await.json()[0]["title"], "Blog");
+
+    assert_eq!(app.delete("/v1/collections/posts").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/collections/posts").await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn entries_follow_their_collection_fields() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let fields = json!([
+        { "name": "title", "field_type": "text", "required": true },
+        { "name": "rating", "field_type": "number" }
+    ]);
+    app.post("/v1/collections", json!({ "name": "posts", "title": "Posts", "fields": fields }))
+        .await;
+
+    let res = app
+        .post(
+            "/v1/collections/posts/entries",
+            json!({ "slug": "first", "status": "published", "content": { "title": "First", "rating": 5 } }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .post(
+            "/v1/collections/posts/entries",
+            json!({ "slug": "second", "status": "published", "content": { "rating": "five" } }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = app
+        .post(
+            "/v1/collections/posts/entries",
+            json!({ "slug": "draft", "status": "draft", "content": { "title": "Draft" } }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let res = app.get("/v1/collections/posts/entries?status=published").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json().as_array().unwrap().len(), 1);
+    assert_eq!(res.json()[0]["content"]["title"], "First");
+
+    let path = format!("/v1/collections/posts/entries/{}", uuid);
+    let res = app
+        .put(&path, json!({ "slug": "first", "status": "archived", "content": { "title": "First" } }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get(&path).await.json()["status"], "archived");
+
+    assert_eq!(app.delete(&path).await.status, StatusCode::OK);
+    assert_eq!(app.get(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn entries_belong_to_one_collection() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let fields = json!([{ "name": "title", "field_type": "text" }]);
+    app.post("/v1/collections", json!({ "name": "posts", "title": "Posts", "fields": fields }))
+        .await;
+    app.post("/v1/collections", json!({ "name": "events", "title": "Events", "fields": fields }))
+        .await;
+
+    let res = app
+        .post(
+            "/v1/collections/posts/entries",
+            json!({ "slug": "first", "status": "published", "content": { "title": "First" } }),
+        )
+        .await;
+    let uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app.get(&format!("/v1/collections/events/entries/{}", uuid)).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn collections_need_a_slug_and_field_definitions() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let fields = json!([{ "name": "title", "field_type": "text" }]);
+    let invalid = [
+        (json!({ "name": "Blog Posts", "title": "Posts", "fields": fields }), "name"),
+        (json!({ "name": "", "title": "Posts", "fields": fields }), "name"),
+        (json!({ "name": "posts", "title": "Posts", "fields": { "title": "text" } }), "fields"),
+        (json!({ "name": "posts", "title": "Posts", "fields": [{ "name": "title", "field_type": "date" }] }), "fields"),
+        (json!({ "name": "posts", "title": "Posts", "fields": [{ "field_type": "text" }] }), "fields"),
+    ];
+
+    for (body, field) in invalid.iter() {
+        let res = app.post("/v1/collections", body.clone()).await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        assert_eq!(res.json()["errors"][0]["field"], *field, "{}", body);
+    }
+    assert_eq!(app.get("/v1/collections").await.json(), json!([]));
+
+    app.post("/v1/collections", json!({ "name": "posts", "title": "Posts", "fields": fields }))
+        .await;
+    let res = app
+        .put("/v1/collections/posts", json!({ "name": "posts", "title": "Posts", "fields": "title" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(app.get("/v1/collections/posts").await.json()["fields"], fields);
+}
+
+#[actix_rt::test]
+async fn updates_keep_the_collection_uuid() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let fields = json!([{ "name": "title", "field_type": "text" }]);
+    let res = app
+        .post("/v1/collections", json!({ "name": "posts", "title": "Posts", "fields": fields }))
+        .await;
+    let uuid = res.json()["uuid"].clone();
+
+    let res = app
+        .put(
+            "/v1/collections/posts",
+            json!({ "uuid": "taken-over", "name": "posts", "title": "Blog", "fields": fields }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get("/v1/collections/posts").await.json()["uuid"], uuid);
+}
+
+#[actix_rt::test]
+async fn entries_are_paged_and_sorted() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let fields = json!([{ "name": "rating", "field_type": "number" }]);
+    app.post("/v1/collections", json!({ "name": "posts", "title": "Posts", "fields": fields }))
+        .await;
+    for (slug, rating) in &[("b", 3), ("c", 1), ("a", 2)] {
+        app.post(
+            "/v1/collections/posts/entries",
+            json!({ "slug": slug, "status": "published", "content": { "rating": rating } }),
+        )
+        .await;
+    }
+
+    let next = |res: &super::TestResponse| {
+        let link = res.header("link")?;
+        let start = link.find('<')? + 1;
+        Some(link[start..link.find('>')?].to_string())
+    };
+
+    // columns are sorted and paged by the database, content fields after reading the collection.
+    for (sort, expected) in &[("slug", ["a", "b", "c"]), ("-rating", ["b", "a", "c"])] {
+        let res = app.get(&format!("/v1/collections/posts/entries?sort={}&limit=2", sort)).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+        assert_eq!(res.header("x-total-count").as_deref(), Some("3"));
+        let slugs: Vec<_> = res.json().as_array().unwrap().iter().map(|e| e["slug"].clone()).collect();
+        assert_eq!(slugs, vec![json!(expected[0]), json!(expected[1])], "{}", sort);
+
+        let res = app.get(&next(&res).unwrap()).await;
+        assert_eq!(res.json().as_array().unwrap().len(), 1, "{}", sort);
+        assert_eq!(res.json()[0]["slug"], expected[2], "{}", sort);
+        assert!(next(&res).is_none(), "{}", sort);
+    }
+
+    let res = app.get("/v1/collections/posts/entries?sort=slug&offset=1&limit=1").await;
+    assert_eq!(res.json()[0]["slug"], "b");
+
+    let res = app.get("/v1/collections/posts/entries?sort=title").await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["errors"][0]["field"], "sort");
+}
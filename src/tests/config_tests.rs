@@ -0,0 +1,154 @@
+use actix_web::http::StatusCode;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::{database, start};
+use crate::models::backend::Backend;
+use crate::models::{check_connection_string, establish_database_connection};
+use crate::services::config_service::{load_from, validate_for_serving, ConfigError, Settings};
+
+/// Writes a config file of its own for a test, which is removed when it's dropped.
+struct ConfigFile(PathBuf);
+
+impl ConfigFile {
+    fn new(toml: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("radical-test-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, toml).unwrap();
+
+        Self(path)
+    }
+
+    fn write(&self, toml: &str) {
+        std::fs::write(&self.0, toml).unwrap();
+    }
+
+    fn load(&self, vars: &[(&str, &str)]) -> Result<crate::models::config_models::LocalConfig, ConfigError> {
+        let vars = vars.iter().map(|(name, value)| (name.to_string(), value.to_string()));
+        load_from(Some(&self.0), vars)
+    }
+}
+
+impl Drop for ConfigFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).ok();
+    }
+}
+
+#[test]
+fn environment_variables_override_the_file() {
+    let file = ConfigFile::new("bind_port = 9000\nmax_req = 50\ndevelopment_mode = true\n");
+    let conf = file
+        .load(&[("APP_MAX_REQ", "70"), ("PATH", "/usr/bin"), ("APP_CACHE_TTL", "5")])
+        .unwrap();
+
+    assert_eq!(conf.bind_port, 9000);
+    assert_eq!(conf.max_req, 70);
+    assert_eq!(conf.development_mode, Some(true));
+    assert_eq!(conf.cache_ttl, Some(5));
+
+    // left out of both, so they keep their defaults.
+    assert_eq!(conf.bind_address, "127.0.0.1");
+    assert_eq!(conf.pool_size, None);
+}
+
+#[test]
+fn bad_settings_are_named() {
+    let file = ConfigFile::new("bind_port = \"eighty\"\n");
+    let e = file.load(&[]).err().unwrap().to_string();
+    assert!(e.contains(&format!("`bind_port` in {}", file.0.display())), "{}", e);
+    assert!(e.contains("`eighty`"), "{}", e);
+
+    file.write("bind_prot = 8080\n");
+    assert!(matches!(file.load(&[]), Err(ConfigError::UnknownKey(key, _)) if key == "bind_prot"));
+
+    file.write("[database]\nurl = \"radical.db\"\n");
+    assert!(matches!(file.load(&[]), Err(ConfigError::UnknownKey(key, _)) if key == "database"));
+
+    file.write("seed = { root = true }\n");
+    assert!(matches!(file.load(&[]), Err(ConfigError::Invalid(_, _))));
+
+    file.write("max_req = \n");
+    assert!(matches!(file.load(&[]), Err(ConfigError::Malformed(_, _))));
+
+    file.write("");
+    let e = file.load(&[("APP_DEVELOPMENT_MODE", "yes")]).err().unwrap().to_string();
+    assert!(e.contains("`APP_DEVELOPMENT_MODE`"), "{}", e);
+
+    let missing = std::env::temp_dir().join(format!("radical-test-{}.toml", Uuid::new_v4()));
+    assert!(matches!(load_from(Some(&missing), vec![]), Err(ConfigError::Unreadable(_, _))));
+}
+
+#[test]
+fn the_server_needs_a_jwt_key() {
+    let file = ConfigFile::new("");
+    let conf = file.load(&[]).unwrap();
+    assert!(matches!(validate_for_serving(&conf), Err(ConfigError::Missing("jwt_key", _))));
+
+    let conf = file.load(&[("APP_JWT_KEY", "secret")]).unwrap();
+    assert!(validate_for_serving(&conf).is_ok());
+}
+
+#[test]
+fn the_database_settings_are_named() {
+    let file = ConfigFile::new("");
+    let connect = |vars: &[(&str, &str)]| establish_database_connection(file.load(vars).unwrap()).err();
+
+    assert!(matches!(connect(&[]), Some(ConfigError::Missing("database_url", _))));
+    assert!(matches!(
+        connect(&[("APP_MYSQL_URL", "localhost")]),
+        Some(ConfigError::Missing("mysql_port", _))
+    ));
+    assert!(matches!(
+        connect(&[("APP_DATABASE_URL", "unknown://localhost/radical")]),
+        Some(ConfigError::Invalid(key, _)) if key == "`database_url`"
+    ));
+}
+
+#[test]
+fn the_database_is_picked_by_its_url() {
+    assert_eq!(check_connection_string("radical.db").unwrap(), Backend::Sqlite);
+    assert_eq!(check_connection_string("file:radical?mode=memory&cache=shared").unwrap(), Backend::Sqlite);
+    assert!(check_connection_string("unknown://localhost/radical").is_err());
+
+    let (pool, _) = database();
+    assert_eq!(pool.backend(), Backend::Sqlite);
+    assert_eq!(pool.get().unwrap().backend(), Backend::Sqlite);
+}
+
+#[test]
+fn a_reload_applies_reloadable_settings_only() {
+    let file = ConfigFile::new("bind_port = 9000\nmax_req = 50\ncache_ttl = 60\n");
+    let settings = Settings::new(file.load(&[]).unwrap(), Some(file.0.clone()));
+
+    file.write("bind_port = 9001\nmax_req = 80\ncache_ttl = 120\n");
+    let needs_restart = settings.apply(&file.load(&[]).unwrap());
+
+    let current = settings.current();
+    assert_eq!(current.max_req, 80);
+    assert_eq!(current.cache_ttl, Some(120));
+    assert_eq!(current.bind_port, 9000);
+    assert_eq!(needs_restart, vec!["bind_port".to_string()]);
+}
+
+#[actix_rt::test]
+async fn the_rate_limit_follows_a_reload() {
+    let (pool, mut conf) = database();
+    // an hour long window, so it doesn't start over during the test.
+    conf.rate_limits = Some("default=2/3600".to_string());
+    let mut app = start(pool, conf.clone()).await;
+
+    assert_eq!(app.get("/v1/pages").await.header("ratelimit-remaining").unwrap(), "1");
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+
+    let res = app.get("/v1/pages").await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.header("ratelimit-limit").unwrap(), "2");
+
+    // requests already made still count against the new limit.
+    conf.rate_limits = Some("default=5/3600".to_string());
+    app.settings.apply(&conf);
+
+    let res = app.get("/v1/pages").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("ratelimit-remaining").unwrap(), "1");
+}
@@ -0,0 +1,168 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+
+use super::{database, start};
+use crate::services::config_service::{validate_for_serving, ConfigError};
+
+fn preflight(origin: &str, method: &str, headers: &str) -> TestRequest {
+    TestRequest::with_uri("/v1/pages/index")
+        .method(actix_web::http::Method::OPTIONS)
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", method)
+        .header("Access-Control-Request-Headers", headers)
+}
+
+#[actix_rt::test]
+async fn any_origin_reads_but_none_writes_by_default() {
+    let (pool, conf) = database();
+    let mut app = start(pool, conf).await;
+
+    let res = app
+        .send(TestRequest::with_uri("/v1/pages").header("Origin", "https://reader.test"))
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("access-control-allow-origin").unwrap(), "https://reader.test");
+    assert!(res.header("access-control-expose-headers").unwrap().contains("etag"));
+    assert_eq!(res.header("access-control-allow-credentials"), None);
+    assert_eq!(res.header("vary").unwrap(), "Origin");
+
+    let res = app.send(preflight("https://reader.test", "GET", "if-none-match")).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    assert_eq!(res.header("access-control-allow-methods").unwrap(), "GET, HEAD");
+    assert_eq!(res.header("access-control-max-age").unwrap(), "3600");
+
+    // public reads don't need a login, so they can't send one.
+    let res = app.send(preflight("https://reader.test", "GET", "authorization")).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+
+    let res = app.send(preflight("https://reader.test", "PUT", "authorization")).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.header("access-control-allow-origin"), None);
+
+    // the request is served, but without the headers that would let the page read the answer.
+    let res = app
+        .send(TestRequest::post().uri("/v1/user/login").header("Origin", "https://reader.test"))
+        .await;
+    assert_eq!(res.header("access-control-allow-origin"), None);
+}
+
+#[actix_rt::test]
+async fn writes_follow_their_own_policy() {
+    let (pool, mut conf) = database();
+    conf.cors_read_origins = Some(vec!["https://example.com".to_string()]);
+    conf.cors_write_origins = Some(vec!["https://*.example.com".to_string()]);
+    conf.cors_write_methods = Some(vec!["PUT".to_string(), "delete".to_string()]);
+    conf.cors_write_credentials = Some(true);
+    conf.cors_write_max_age = Some(60);
+    let mut app = start(pool, conf.clone()).await;
+
+    let res = app
+        .send(preflight("https://admin.example.com", "PUT", "Authorization, If-Match"))
+        .await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    assert_eq!(res.header("access-control-allow-origin").unwrap(), "https://admin.example.com");
+    assert_eq!(res.header("access-control-allow-methods").unwrap(), "PUT, DELETE");
+    assert_eq!(res.header("access-control-allow-headers").unwrap(), "authorization, content-type, if-match");
+    assert_eq!(res.header("access-control-allow-credentials").unwrap(), "true");
+    assert_eq!(res.header("access-control-max-age").unwrap(), "60");
+
+    for (origin, method, headers) in &[
+        // a wildcard only stands for subdomains.
+        ("https://example.com", "PUT", ""),
+        ("https://admin.example.com.evil.test", "PUT", ""),
+        ("http://admin.example.com", "PUT", ""),
+        ("https://admin.example.com", "POST", ""),
+        ("https://admin.example.com", "PUT", "x-secret"),
+    ] {
+        let res = app.send(preflight(origin, method, headers)).await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN, "{} {} {}", origin, method, headers);
+    }
+
+    // reads are listed apart from writes.
+    let res = app
+        .send(TestRequest::with_uri("/v1/pages").header("Origin", "https://admin.example.com"))
+        .await;
+    assert_eq!(res.header("access-control-allow-origin"), None);
+    let res = app
+        .send(TestRequest::with_uri("/v1/pages").header("Origin", "https://example.com"))
+        .await;
+    assert_eq!(res.header("access-control-allow-origin").unwrap(), "https://example.com");
+
+    // a reload applies to the next request.
+    conf.cors_read_origins = Some(vec!["https://other.test".to_string()]);
+    app.settings.apply(&conf);
+    let res = app
+        .send(TestRequest::with_uri("/v1/pages").header("Origin", "https://example.com"))
+        .await;
+    assert_eq!(res.header("access-control-allow-origin"), None);
+}
+
+#[actix_rt::test]
+async fn reads_that_need_a_login_follow_the_write_policy() {
+    let (pool, mut conf) = database();
+    conf.cors_write_origins = Some(vec!["https://admin.example.com".to_string()]);
+    let mut app = start(pool, conf).await;
+
+    for uri in &["/v1/user/root", "/v1/system/status", "/v1/cache"] {
+        let res = app
+            .send(TestRequest::with_uri(uri).header("Origin", "https://reader.test"))
+            .await;
+        assert_eq!(res.header("access-control-allow-origin"), None, "{}", uri);
+
+        let res = app
+            .send(TestRequest::with_uri(uri).header("Origin", "https://admin.example.com"))
+            .await;
+        assert_eq!(res.header("access-control-allow-origin").unwrap(), "https://admin.example.com", "{}", uri);
+
+        let res = app
+            .send(
+                TestRequest::with_uri(uri)
+                    .method(actix_web::http::Method::OPTIONS)
+                    .header("Origin", "https://admin.example.com")
+                    .header("Access-Control-Request-Method", "GET")
+                    .header("Access-Control-Request-Headers", "authorization"),
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::NO_CONTENT, "{}", uri);
+    }
+
+    // content reads still follow the read policy.
+    let res = app
+        .send(TestRequest::with_uri("/v1/pages").header("Origin", "https://reader.test"))
+        .await;
+    assert_eq!(res.header("access-control-allow-origin").unwrap(), "https://reader.test");
+}
+
+#[test]
+fn credentials_need_listed_origins() {
+    let (_, mut conf) = database();
+    conf.cors_read_credentials = Some(true);
+
+    assert!(matches!(
+        validate_for_serving(&conf),
+        Err(ConfigError::Conflict("cors_read_credentials", "cors_read_origins"))
+    ));
+
+    conf.cors_read_origins = Some(vec!["https://example.com".to_string()]);
+    assert!(validate_for_serving(&conf).is_ok());
+}
+
+#[actix_rt::test]
+async fn rate_limited_answers_are_readable_and_preflights_are_not_counted() {
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("default=1/3600".to_string());
+    let mut app = start(pool, conf).await;
+
+    for _ in 0..3 {
+        let res = app.send(preflight("https://reader.test", "GET", "if-none-match")).await;
+        assert_eq!(res.status, StatusCode::NO_CONTENT);
+    }
+
+    let read = || TestRequest::with_uri("/v1/pages").header("Origin", "https://reader.test");
+    assert_eq!(app.send(read()).await.status, StatusCode::OK);
+
+    let res = app.send(read()).await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.header("access-control-allow-origin").unwrap(), "https://reader.test");
+    assert!(res.header("access-control-expose-headers").unwrap().contains("retry-after"));
+}
@@ -0,0 +1,66 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn menus_hold_items() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app.post("/v1/menus", json!({ "name": "main", "title": "Main" })).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let res = app
+        .post("/v1/menus/main/items", json!({ "title": "Home", "page_uuid": page_uuid }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let home_uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .post(
+            "/v1/menus/main/items",
+            json!({ "title": "Source", "url": "https://github.com/Rust-CMS/radical", "position": 1 }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    // an item links to a page or a url, not both.
+    let res = app
+        .post("/v1/menus/main/items", json!({ "title": "Both", "page_uuid": page_uuid, "url": "/" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = app.get("/v1/menus/main").await;
+    assert_eq!(res.status, StatusCode::OK);
+    let items = res.json()["items"].clone();
+    assert_eq!(items[0]["title"], "Home");
+    assert_eq!(items[0]["url"], "/");
+    assert_eq!(items[1]["title"], "Source");
+
+    let path = format!("/v1/menus/main/items/{}", home_uuid);
+    let res = app
+        .put(&path, json!({ "title": "Start", "page_uuid": page_uuid }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get("/v1/menus/main").await.json()["items"][0]["title"], "Start");
+
+    assert_eq!(app.delete(&path).await.status, StatusCode::OK);
+    assert_eq!(app.delete(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn menus_are_renamed_and_deleted() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    app.post("/v1/menus", json!({ "name": "main", "title": "Main" })).await;
+
+    let res = app.put("/v1/menus/main", json!({ "name": "main", "title": "Top" })).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/menus").await.json()[0]["title"], "Top");
+
+    assert_eq!(app.delete("/v1/menus/main").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/menus/main").await.status, StatusCode::NOT_FOUND);
+}
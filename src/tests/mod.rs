@@ -0,0 +1,296 @@
+//! Tests that send requests through the whole app, with its routers, middleware, templates and helpers.
+//! Each test gets a SQLite database of its own in memory, so they run in parallel without seeing each other's rows.
+//!
+//! They're built for SQLite only: `cargo test --no-default-features --features sqlite`.
+
+mod batch_tests;
+mod cache_tests;
+mod category_tests;
+mod cli_tests;
+mod collection_tests;
+mod config_tests;
+mod cors_tests;
+mod menu_tests;
+mod module_tests;
+mod page_tests;
+mod rate_limit_tests;
+mod redirect_tests;
+mod render_tests;
+mod repository_tests;
+mod seed_tests;
+mod system_tests;
+mod taxonomy_tests;
+mod user_tests;
+
+use actix_http::Request;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::{HeaderMap, Method, StatusCode};
+use actix_web::{test, web, Error};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::config_models::LocalConfig;
+use crate::models::DbPool;
+use crate::repositories::Repositories;
+use crate::services::cache_service::PageCache;
+use crate::services::config_service::Settings;
+use crate::services::rate_limit_service;
+use crate::services::seed_service;
+use crate::services::template_service::Templates;
+
+/// The JWT key is read from the environment, which every test shares.
+const JWT_KEY: &str = "radical-test-key";
+
+/// The app as the tests see it, with the token of whoever logged in last.
+pub struct TestApp<S> {
+    service: S,
+    token: Option<String>,
+    /// The app's config, which tests can reload.
+    pub settings: web::Data<Settings>,
+}
+
+/// What came back from a request, with the body already read.
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: web::Bytes,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!("Response isn't JSON ({}): {}", e, self.text())
+        })
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+}
+
+fn config(database_url: &str) -> LocalConfig {
+    serde_json::from_value(json!({
+        "database_url": database_url,
+        "bind_address": "127.0.0.1",
+        "bind_port": 0,
+        "max_req": 10000,
+        "jwt_key": JWT_KEY,
+        "development_mode": true,
+        "pool_size": 4,
+    }))
+    .unwrap()
+}
+
+/// A new database, with the migrations run.
+/// Its name is unique, so it's shared by the pool's connections and by nothing else, and it's dropped with the pool.
+pub fn database() -> (DbPool, LocalConfig) {
+    let database_url = format!("file:radical-test-{}?mode=memory&cache=shared", Uuid::new_v4());
+    let conf = config(&database_url);
+
+    let pool = crate::models::establish_database_connection(conf.clone()).unwrap();
+    crate::services::migration_service::run(&pool.get().unwrap()).unwrap();
+
+    (pool, conf)
+}
+
+/// Starts the app on a new database seeded with the `root` user and the `demo` site, which most tests build on.
+pub async fn spawn() -> TestApp<
+    impl Service<
+        Request = Request,
+        Response = ServiceResponse<impl MessageBody + Unpin>,
+        Error = Error,
+    >,
+> {
+    spawn_seeded(&["root", "demo"]).await
+}
+
+/// Starts the app on a new database seeded with the fixture sets in `seeds`, the same way `main` does it.
+pub async fn spawn_seeded(seeds: &[&str]) -> TestApp<
+    impl Service<
+        Request = Request,
+        Response = ServiceResponse<impl MessageBody + Unpin>,
+        Error = Error,
+    >,
+> {
+    let (pool, conf) = database();
+    let seeds: Vec<String> = seeds.iter().map(|seed| seed.to_string()).collect();
+    seed_service::seed(&seeds, &pool.get().unwrap()).unwrap();
+
+    start(pool, conf).await
+}
+
+/// Starts the app on a database from `database`, for tests that also change it directly.
+pub async fn start(pool: DbPool, conf: LocalConfig) -> TestApp<
+    impl Service<
+        Request = Request,
+        Response = ServiceResponse<impl MessageBody + Unpin>,
+        Error = Error,
+    >,
+> {
+    let repositories = Repositories::diesel(pool.clone());
+    start_with_repositories(pool, conf, repositories).await
+}
+
+/// Starts the app with its controllers reading and writing through `repositories`.
+/// The template helpers, such as menus and collections, still read from `pool`.
+pub async fn start_with_repositories(pool: DbPool, conf: LocalConfig, repositories: Repositories) -> TestApp<
+    impl Service<
+        Request = Request,
+        Response = ServiceResponse<impl MessageBody + Unpin>,
+        Error = Error,
+    >,
+> {
+    std::env::set_var("APP_JWT_KEY", JWT_KEY);
+
+    let templates = web::Data::new(Templates::load(pool.clone()).unwrap());
+    let page_cache = web::Data::new(PageCache::from_config(&conf));
+    let rate_limits = rate_limit_service::store(&conf, &pool);
+    let settings = web::Data::new(Settings::new(conf, None));
+
+    let service = test::init_service(app!(settings, pool, templates, page_cache, repositories, rate_limits)).await;
+
+    TestApp {
+        service,
+        token: None,
+        settings,
+    }
+}
+
+impl<S, B> TestApp<S>
+where
+    S: Service<Request = Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody + Unpin,
+{
+    /// Sends `req` with the token from the last login, if there was one.
+    pub async fn send(&mut self, req: test::TestRequest) -> TestResponse {
+        // the rate limiter tells clients apart by their address.
+        let mut req = req.peer_addr("127.0.0.1:41000".parse().unwrap());
+        if let Some(token) = &self.token {
+            req = req.header("Authorization", token.as_str());
+        }
+
+        let res = test::call_service(&mut self.service, req.to_request()).await;
+        let status = res.status();
+        let headers = res.headers().clone();
+        let body = test::read_body(res).await;
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn request(&mut self, method: Method, path: &str, body: Option<Value>) -> TestResponse {
+        let mut req = test::TestRequest::with_uri(path).method(method);
+        if let Some(body) = body {
+            req = req.set_json(&body);
+        }
+
+        self.send(req).await
+    }
+
+    pub async fn get(&mut self, path: &str) -> TestResponse {
+        self.request(Method::GET, path, None).await
+    }
+
+    pub async fn post(&mut self, path: &str, body: Value) -> TestResponse {
+        self.request(Method::POST, path, Some(body)).await
+    }
+
+    pub async fn put(&mut self, path: &str, body: Value) -> TestResponse {
+        self.request(Method::PUT, path, Some(body)).await
+    }
+
+    pub async fn delete(&mut self, path: &str) -> TestResponse {
+        self.request(Method::DELETE, path, None).await
+    }
+
+    /// Sends `body` with `If-Match: version`, as updates to versioned resources need.
+    pub async fn put_versioned(&mut self, path: &str, version: &str, body: Value) -> TestResponse {
+        let req = test::TestRequest::put()
+            .uri(path)
+            .header("If-Match", version)
+            .set_json(&body);
+
+        self.send(req).await
+    }
+
+    pub async fn delete_versioned(&mut self, path: &str, version: &str) -> TestResponse {
+        let req = test::TestRequest::delete()
+            .uri(path)
+            .header("If-Match", version);
+
+        self.send(req).await
+    }
+
+    /// Logs in and keeps the token for the requests after it.
+    pub async fn login(&mut self, username: &str, password: &str) -> TestResponse {
+        let res = self
+            .post("/v1/user/login", json!({ "username": username, "password": password }))
+            .await;
+
+        if res.status.is_success() {
+            let cookie = res.header("set-cookie").expect("Login didn't set a cookie.");
+            let token = cookie
+                .trim_start_matches("auth=")
+                .split(';')
+                .next()
+                .unwrap()
+                .to_string();
+
+            self.token = Some(token);
+        }
+
+        res
+    }
+
+    /// Logs in as the seeded `root` user, whose first login needs no password.
+    pub async fn login_root(&mut self) {
+        let res = self.login("root", "").await;
+        assert_eq!(res.status, StatusCode::ACCEPTED, "{}", res.text());
+    }
+
+    pub fn logout(&mut self) {
+        self.token = None;
+    }
+
+    pub fn set_token(&mut self, token: &str) {
+        self.token = Some(token.to_string());
+    }
+
+    /// The uuid of the seeded index page at `/`.
+    pub async fn index_uuid(&mut self) -> String {
+        let pages = self.get("/v1/pages").await.json();
+
+        pages
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|page| page["page_url"] == "/")
+            .expect("The index page isn't seeded.")["uuid"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Creates a page and returns its uuid.
+    pub async fn create_page(&mut self, url: &str, title: &str) -> String {
+        let res = self
+            .post(
+                "/v1/pages",
+                json!({ "page_name": "index", "page_url": url, "page_title": title }),
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+        res.json()["uuid"].as_str().unwrap().to_string()
+    }
+}
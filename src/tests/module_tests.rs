@@ -0,0 +1,97 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn modules_are_created_and_read() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.create_page("/about", "About").await;
+
+    let res = app
+        .post("/v1/modules", json!({ "title": "intro", "page_uuid": page_uuid, "content": "Hello." }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app.get(&format!("/v1/modules/{}", uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()["content"], "Hello.");
+    assert_eq!(res.header("etag").as_deref(), Some("\"1\""));
+
+    let res = app.get(&format!("/v1/modules?page_uuid={}", page_uuid)).await;
+    assert_eq!(res.header("x-total-count").as_deref(), Some("1"));
+    assert_eq!(res.json()[0]["uuid"], uuid.as_str());
+}
+
+#[actix_rt::test]
+async fn modules_are_listed_by_title() {
+    let mut app = spawn().await;
+
+    let res = app.get("/v1/modules?title=githublink&sort=-title").await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let titles: Vec<_> = res.json().as_array().unwrap().iter().map(|m| m["title"].clone()).collect();
+    assert_eq!(titles, vec![json!("githublink_tooling"), json!("githublink")]);
+
+    // modules in a category are listed with their category instead.
+    let res = app.get("/v1/modules?title=color").await;
+    assert_eq!(res.header("x-total-count").as_deref(), Some("0"));
+
+    let res = app.get("/v1/modules?sort=content").await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+async fn modules_are_updated_and_deleted_by_version() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app
+        .post("/v1/modules", json!({ "title": "extra", "page_uuid": page_uuid, "content": "One" }))
+        .await;
+    let path = format!("/v1/modules/{}", res.json()["uuid"].as_str().unwrap());
+    let body = json!({ "title": "extra", "page_uuid": page_uuid, "content": "Two" });
+
+    assert_eq!(app.put(&path, body.clone()).await.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let res = app.put_versioned(&path, "\"1\"", body.clone()).await;
+    assert!(res.status.is_success(), "{}", res.text());
+    assert_eq!(res.header("etag").as_deref(), Some("\"2\""));
+    assert_eq!(app.get(&path).await.json()["content"], "Two");
+
+    assert_eq!(app.put_versioned(&path, "\"1\"", body).await.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(app.delete(&path).await.status, StatusCode::PRECONDITION_REQUIRED);
+
+    assert!(app.delete_versioned(&path, "\"2\"").await.status.is_success());
+    assert_eq!(app.get(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn modules_need_an_existing_page() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/modules", json!({ "title": "lost", "page_uuid": "nowhere", "content": "" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.text().contains("page_uuid"));
+}
+
+#[actix_rt::test]
+async fn deleting_a_page_deletes_its_modules() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.create_page("/about", "About").await;
+
+    let res = app
+        .post("/v1/modules", json!({ "title": "intro", "page_uuid": page_uuid, "content": "Hello." }))
+        .await;
+    let path = format!("/v1/modules/{}", res.json()["uuid"].as_str().unwrap());
+
+    app.delete_versioned(&format!("/v1/pages/{}", page_uuid), "*").await;
+    assert_eq!(app.get(&path).await.status, StatusCode::NOT_FOUND);
+}
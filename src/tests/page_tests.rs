@@ -0,0 +1,147 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn pages_are_created_read_and_listed() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About").await;
+
+    let res = app.get(&format!("/v1/pages/{}", uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()["page_title"], "About");
+    assert_eq!(res.header("etag").as_deref(), Some("\"1\""));
+
+    let res = app.get("/v1/pages?sort=page_url").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("x-total-count").as_deref(), Some("2"));
+
+    let urls: Vec<_> = res.json().as_array().unwrap().iter().map(|p| p["page_url"].clone()).collect();
+    assert_eq!(urls, vec![json!("/"), json!("/about")]);
+}
+
+#[actix_rt::test]
+async fn pages_include_their_modules() {
+    let mut app = spawn().await;
+    let uuid = app.index_uuid().await;
+
+    let res = app.get(&format!("/v1/pages/{}/modules", uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let page = res.json();
+    let modules = page["fields"]["modules"].as_array().unwrap();
+    assert!(modules.iter().any(|m| m["content"] == "Welcome to Radical."));
+    assert_eq!(page["fields"]["categories"][0]["title"], "colors");
+}
+
+#[actix_rt::test]
+async fn pages_are_updated_by_version() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About").await;
+    let path = format!("/v1/pages/{}", uuid);
+    let body = json!({ "page_name": "index", "page_url": "/about", "page_title": "About us" });
+
+    assert_eq!(app.put(&path, body.clone()).await.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let res = app.put_versioned(&path, "\"1\"", body.clone()).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(res.header("etag").as_deref(), Some("\"2\""));
+
+    // someone else saved version 2 first.
+    let res = app.put_versioned(&path, "\"1\"", body).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+
+    assert_eq!(app.get(&path).await.json()["page_title"], "About us");
+}
+
+#[actix_rt::test]
+async fn a_pages_cache_control_is_set_kept_and_cleared() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About").await;
+    let path = format!("/v1/pages/{}", uuid);
+    let body = |cache_control: Option<serde_json::Value>| {
+        let mut body = json!({ "page_name": "index", "page_url": "/about", "page_title": "About" });
+        if let Some(cache_control) = cache_control {
+            body["cache_control"] = cache_control;
+        }
+        body
+    };
+
+    let res = app.put_versioned(&path, "*", body(Some(json!("max-age=600")))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get("/about").await.header("cache-control").as_deref(), Some("max-age=600"));
+
+    // leaving it out keeps it.
+    app.put_versioned(&path, "*", body(None)).await;
+    assert_eq!(app.get(&path).await.json()["cache_control"], "max-age=600");
+
+    app.put_versioned(&path, "*", body(Some(json!(null)))).await;
+    assert_eq!(app.get(&path).await.json()["cache_control"], json!(null));
+    assert_ne!(app.get("/about").await.header("cache-control").as_deref(), Some("max-age=600"));
+}
+
+#[actix_rt::test]
+async fn moving_a_page_redirects_its_old_url() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About").await;
+    let body = json!({ "page_name": "index", "page_url": "/about-us", "page_title": "About" });
+    let res = app.put_versioned(&format!("/v1/pages/{}", uuid), "*", body).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.get("/about").await;
+    assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.header("location").as_deref(), Some("/about-us"));
+}
+
+#[actix_rt::test]
+async fn pages_are_validated() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/pages", json!({ "page_name": "index", "page_url": "no-slash", "page_title": "A" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.header("content-type").as_deref(), Some("application/problem+json"));
+    assert!(res.text().contains("page_url"));
+
+    let res = app
+        .post("/v1/pages", json!({ "page_name": "index", "page_url": "/", "page_title": "Home again" }))
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let res = app.post("/v1/pages", json!({ "page_name": "index" })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn deleted_pages_are_gone() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About").await;
+    let path = format!("/v1/pages/{}", uuid);
+
+    assert_eq!(app.delete_versioned(&path, "\"7\"").await.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(app.delete_versioned(&path, "\"1\"").await.status, StatusCode::OK);
+    assert_eq!(app.get(&path).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.delete_versioned(&path, "*").await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn deleting_a_page_needs_a_token() {
+    let mut app = spawn().await;
+    let uuid = app.index_uuid().await;
+
+    let res = app.delete_versioned(&format!("/v1/pages/{}", uuid), "*").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
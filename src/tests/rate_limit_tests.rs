@@ -0,0 +1,205 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+
+use super::{database, start};
+use crate::models::RunQueryDsl;
+use crate::services::config_service::{validate_for_serving, ConfigError};
+use crate::services::seed_service;
+
+// the windows are an hour long, so none of them starts over during a test.
+
+#[actix_rt::test]
+async fn routes_follow_their_policy() {
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("login=2/3600; api=3/3600 by user".to_string());
+    conf.rate_limit_routes = Some("/v1/user/login=login; /v1=api".to_string());
+    let mut app = start(pool, conf).await;
+
+    for _ in 0..2 {
+        let res = app.login("nobody", "wrong").await;
+        assert_ne!(res.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.header("ratelimit-policy").unwrap(), "2;w=3600");
+    }
+
+    let res = app.login("nobody", "wrong").await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.json()["code"], "rate_limited");
+    assert_eq!(res.header("ratelimit-remaining").unwrap(), "0");
+    let retry_after: u64 = res.header("retry-after").unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 3600, "{}", retry_after);
+
+    // the rest of the api has a budget of its own, and pages the default one.
+    let res = app.get("/v1/pages").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("ratelimit-remaining").unwrap(), "2");
+    assert_eq!(app.get("/").await.header("ratelimit-limit").unwrap(), "10000");
+}
+
+#[actix_rt::test]
+async fn users_have_budgets_of_their_own() {
+    let (pool, mut conf) = database();
+    seed_service::seed(&["root".to_string()], &pool.get().unwrap()).unwrap();
+    conf.rate_limits = Some("api=2/3600 by user".to_string());
+    conf.rate_limit_routes = Some("/v1/pages=api".to_string());
+    let mut app = start(pool, conf).await;
+
+    app.login_root().await;
+    for _ in 0..2 {
+        assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+    }
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+
+    // requests without a token are counted by address, from the same one.
+    app.logout();
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+
+    // a token that doesn't decrypt counts against the address too.
+    app.set_token("not-a-token");
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_rt::test]
+async fn forged_tokens_count_against_the_address() {
+    let (pool, mut conf) = database();
+    seed_service::seed(&["root".to_string()], &pool.get().unwrap()).unwrap();
+    conf.rate_limits = Some("api=2/3600 by token".to_string());
+    conf.rate_limit_routes = Some("/v1/pages=api".to_string());
+    let mut app = start(pool, conf).await;
+
+    for forged in &["forged-1", "forged-2"] {
+        app.set_token(forged);
+        assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+    }
+
+    // a new made up token doesn't get a new budget.
+    app.set_token("forged-3");
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+    app.logout();
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+
+    // a real login has a budget of its own.
+    app.login_root().await;
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn forwarded_addresses_are_only_read_from_trusted_proxies() {
+    let forwarded = |client: &str| TestRequest::with_uri("/v1/pages").header("X-Forwarded-For", client);
+
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("default=1/3600".to_string());
+    conf.trusted_proxies = Some(vec!["127.0.0.1".to_string(), "10.0.0.1".to_string()]);
+    let mut app = start(pool, conf.clone()).await;
+
+    assert_eq!(app.send(forwarded("192.0.2.1")).await.status, StatusCode::OK);
+    assert_eq!(app.send(forwarded("192.0.2.1")).await.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(app.send(forwarded("192.0.2.2")).await.status, StatusCode::OK);
+
+    // hops through trusted proxies are skipped, and the first untrusted one is the client.
+    assert_eq!(app.send(forwarded("192.0.2.3, 10.0.0.1")).await.status, StatusCode::OK);
+    assert_eq!(app.send(forwarded("192.0.2.3")).await.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        app.send(forwarded("192.0.2.4, 192.0.2.3")).await.status,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // without trusted proxies, the header is ignored, so changing it doesn't get a new budget.
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("default=1/3600".to_string());
+    let mut app = start(pool, conf).await;
+
+    assert_eq!(app.send(forwarded("192.0.2.1")).await.status, StatusCode::OK);
+    assert_eq!(app.send(forwarded("192.0.2.2")).await.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_rt::test]
+async fn servers_on_one_database_share_a_budget() {
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("default=3/3600".to_string());
+    conf.rate_limit_store = Some("database".to_string());
+    let mut first = start(pool.clone(), conf.clone()).await;
+    let mut second = start(pool, conf).await;
+
+    assert_eq!(first.get("/v1/pages").await.status, StatusCode::OK);
+    assert_eq!(first.get("/v1/pages").await.status, StatusCode::OK);
+
+    let res = second.get("/v1/pages").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("ratelimit-remaining").unwrap(), "0");
+
+    assert_eq!(second.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(first.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_rt::test]
+async fn tokens_are_counted_under_a_digest_every_server_agrees_on() {
+    use crate::schema::rate_limit_windows::dsl::{client_key, rate_limit_windows};
+
+    let (pool, mut conf) = database();
+    seed_service::seed(&["root".to_string()], &pool.get().unwrap()).unwrap();
+    conf.rate_limits = Some("api=5/3600 by token".to_string());
+    conf.rate_limit_routes = Some("/v1/pages=api".to_string());
+    conf.rate_limit_store = Some("database".to_string());
+    let mut app = start(pool.clone(), conf).await;
+
+    app.login_root().await;
+    app.get("/v1/pages").await;
+
+    let token = app.token.clone().unwrap();
+    let keys: Vec<String> = rate_limit_windows.select(client_key).load(&pool.get().unwrap()).unwrap();
+    assert!(keys.contains(&format!("api:token:{:x}", Sha256::digest(token.as_bytes()))), "{:?}", keys);
+}
+
+#[actix_rt::test]
+async fn unlimited_routes_are_not_counted() {
+    use crate::schema::rate_limit_windows::dsl::rate_limit_windows;
+
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("default=1/3600".to_string());
+    conf.rate_limit_routes = Some("/v1/pages=unlimited".to_string());
+    conf.rate_limit_store = Some("database".to_string());
+    let mut app = start(pool.clone(), conf).await;
+
+    // static files aren't limited unless a route says so.
+    for path in &["/v1/pages", "/v1/pages", "/assets/missing.css", "/assets/missing.css"] {
+        let res = app.get(path).await;
+        assert_ne!(res.status, StatusCode::TOO_MANY_REQUESTS, "{}", path);
+        assert_eq!(res.header("ratelimit-limit"), None, "{}", path);
+    }
+
+    let windows: i64 = rate_limit_windows.count().get_result(&pool.get().unwrap()).unwrap();
+    assert_eq!(windows, 0);
+
+    assert_eq!(app.get("/v1/menus").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/menus").await.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[test]
+fn bad_rules_are_named() {
+    let invalid = |setting: &str, value: &str| {
+        let (_, mut conf) = database();
+        match setting {
+            "rate_limits" => conf.rate_limits = Some(value.to_string()),
+            "rate_limit_routes" => conf.rate_limit_routes = Some(value.to_string()),
+            "rate_limit_store" => conf.rate_limit_store = Some(value.to_string()),
+            _ => conf.trusted_proxies = Some(vec![value.to_string()]),
+        }
+
+        match validate_for_serving(&conf) {
+            Err(ConfigError::Invalid(key, _)) => key == format!("`{}`", setting),
+            _ => false,
+        }
+    };
+
+    assert!(invalid("rate_limits", "login=ten/60"));
+    assert!(invalid("rate_limits", "login=10/0"));
+    assert!(invalid("rate_limits", "login=10/60 by cookie"));
+    assert!(invalid("rate_limits", "unlimited=10/60"));
+    assert!(invalid("rate_limit_routes", "/v1/user/login=login"));
+    assert!(invalid("rate_limit_routes", "/v1/user/login"));
+    assert!(invalid("trusted_proxies", "proxy.local"));
+    assert!(invalid("rate_limit_store", "redis"));
+}
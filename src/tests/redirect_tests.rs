@@ -0,0 +1,152 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn redirects_send_visitors_on() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/redirects", json!({ "source_path": "/old", "target": "/", "status_code": 302 }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let path = format!("/v1/redirects/{}", res.json()["uuid"].as_str().unwrap());
+
+    let res = app.get("/old?ref=mail").await;
+    assert_eq!(res.status, StatusCode::FOUND);
+    assert_eq!(res.header("location").as_deref(), Some("/?ref=mail"));
+    assert_eq!(app.get(&path).await.json()["hits"], 1);
+
+    let res = app.put(&path, json!({ "source_path": "/old", "status_code": 410 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get("/old").await.status, StatusCode::GONE);
+
+    assert_eq!(app.delete(&path).await.status, StatusCode::OK);
+    assert_eq!(app.get("/old").await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn redirects_never_hide_a_page() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    app.post("/v1/redirects", json!({ "source_path": "/", "target": "/elsewhere" }))
+        .await;
+
+    assert_eq!(app.get("/").await.status, StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn redirects_are_validated() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/redirects", json!({ "source_path": "/loop", "target": "/loop" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    for source_path in &["old", "/old page", "/old?page=2", "/old#top"] {
+        let res = app
+            .post("/v1/redirects", json!({ "source_path": source_path, "target": "/" }))
+            .await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", source_path);
+        assert_eq!(res.json()["errors"][0]["field"], "source_path");
+    }
+}
+
+#[actix_rt::test]
+async fn updates_keep_the_redirect_uuid() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app.post("/v1/redirects", json!({ "source_path": "/old", "target": "/" })).await;
+    let uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .put(
+            &format!("/v1/redirects/{}", uuid),
+            json!({ "uuid": "taken-over", "source_path": "/old", "target": "/about" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+    let res = app.get(&format!("/v1/redirects/{}", uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()["target"], "/about");
+}
+
+#[actix_rt::test]
+async fn redirects_cant_lead_back_to_themselves() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    for (source, target) in &[("/a", "/b"), ("/b", "/c")] {
+        let res = app.post("/v1/redirects", json!({ "source_path": source, "target": target })).await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    }
+
+    let res = app.post("/v1/redirects", json!({ "source_path": "/c", "target": "/a" })).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["errors"][0]["code"], "loop");
+
+    // pointing `/b` back at `/a` replaces where it led, which was the only way on.
+    let uuid = app.get("/v1/redirects").await.json()[1]["uuid"].as_str().unwrap().to_string();
+    let res = app
+        .put(&format!("/v1/redirects/{}", uuid), json!({ "source_path": "/b", "target": "/a" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = app
+        .put(&format!("/v1/redirects/{}", uuid), json!({ "source_path": "/b", "target": "/about" }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let res = app.post("/v1/redirects", json!({ "source_path": "/c", "target": "/a" })).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+}
+
+#[actix_rt::test]
+async fn redirects_are_imported_from_csv() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let csv = "source_path,target,status_code\n/a,/,301\n/b,/,302\n/a,/about,301\n";
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/redirects/import")
+        .header("content-type", "text/csv")
+        .set_payload(csv);
+    let res = app.send(req).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let redirects = app.get("/v1/redirects").await.json();
+    assert_eq!(redirects.as_array().unwrap().len(), 2);
+
+    // the later row for `/a` replaced the first.
+    let res = app.get("/a").await;
+    assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.header("location").as_deref(), Some("/about"));
+}
+
+#[actix_rt::test]
+async fn imported_redirects_default_to_301_and_cant_loop() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let import = |csv: &'static str| {
+        actix_web::test::TestRequest::post()
+            .uri("/v1/redirects/import")
+            .header("content-type", "text/csv")
+            .set_payload(csv)
+    };
+
+    let res = app.send(import("source_path,target,status_code\n/old,/,\n")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    assert_eq!(app.get("/old").await.status, StatusCode::MOVED_PERMANENTLY);
+
+    // rows are checked against the rows before them, and nothing is imported when one loops.
+    let res = app.send(import("source_path,target,status_code\n/a,/b,\n/b,/a,\n")).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    assert_eq!(app.get("/v1/redirects").await.json().as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,95 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn the_seeded_index_page_renders() {
+    let mut app = spawn().await;
+
+    let res = app.get("/").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("content-type").as_deref(), Some("text/html"));
+    assert!(res.header("last-modified").is_some());
+
+    let html = res.text();
+    assert!(html.contains("<title>Home</title>"));
+    assert!(html.contains("Welcome to Radical."));
+    // the `colors` category, through `getarray`.
+    assert!(html.contains("red") && html.contains("blue") && html.contains("green"));
+}
+
+#[actix_rt::test]
+async fn pages_render_their_menus_and_changes() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    app.post("/v1/menus", json!({ "name": "main", "title": "Main" })).await;
+    app.post("/v1/menus/main/items", json!({ "title": "Start here", "page_uuid": page_uuid }))
+        .await;
+
+    let uuid = app.create_page("/about", "About").await;
+    app.post("/v1/modules", json!({ "title": "title", "page_uuid": uuid, "content": "All about us." }))
+        .await;
+
+    let html = app.get("/about").await.text();
+    assert!(html.contains("<title>About</title>"));
+    assert!(html.contains("All about us."));
+    assert!(html.contains("Start here"));
+}
+
+#[actix_rt::test]
+async fn unknown_paths_get_the_not_found_page() {
+    let mut app = spawn().await;
+
+    let res = app.get("/nowhere").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert!(res.text().contains("This content wasn't found."));
+
+    let res = app.get("/v1/nowhere").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn unknown_resources_are_problem_documents() {
+    let mut app = spawn().await;
+
+    let res = app.get("/v1/pages/nowhere").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.header("content-type").as_deref(), Some("application/problem+json"));
+    assert_eq!(res.json()["status"], 404);
+}
+
+#[actix_rt::test]
+async fn assets_are_served() {
+    let mut app = spawn().await;
+
+    let res = app.get("/assets/style.css").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.header("content-type").unwrap().starts_with("text/css"));
+}
+
+#[actix_rt::test]
+async fn unchanged_pages_are_not_sent_again() {
+    let mut app = spawn().await;
+
+    let etag = app.get("/").await.header("etag").unwrap();
+    let req = actix_web::test::TestRequest::get()
+        .uri("/")
+        .header("If-None-Match", etag.as_str());
+
+    assert_eq!(app.send(req).await.status, StatusCode::NOT_MODIFIED);
+}
+
+#[actix_rt::test]
+async fn etags_are_the_same_on_every_server() {
+    let res = spawn().await.get("/").await;
+    let digest: String = Sha256::digest(&res.body)[..16].iter().map(|byte| format!("{:02x}", byte)).collect();
+    assert_eq!(res.header("etag").unwrap(), format!("\"{}\"", digest));
+
+    // another server with the same content sends the same tag, so its clients' copies stay valid.
+    let etag = spawn().await.get("/").await.header("etag");
+    assert_eq!(etag, res.header("etag"));
+}
@@ -0,0 +1,173 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::{database, start_with_repositories};
+use crate::models::page_models::Page;
+use crate::models::redirect_models::Redirect;
+use crate::models::Model;
+use crate::repositories::memory_repository::MemoryRepository;
+use crate::repositories::{RedirectRepository, Repository};
+use crate::services::seed_service;
+
+#[actix_rt::test]
+async fn pages_and_modules_are_kept_in_the_repository() {
+    let (pool, conf) = database();
+    seed_service::seed(&["root".to_string()], &pool.get().unwrap()).unwrap();
+    let repository = Arc::new(MemoryRepository::new());
+    let mut app = start_with_repositories(pool.clone(), conf, repository.repositories(pool.clone())).await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About us").await;
+    let res = app
+        .post(
+            "/v1/modules",
+            json!({ "page_uuid": uuid, "category_uuid": null, "title": "title", "content": "Hello from memory." }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let res = app.post("/v1/category", json!({ "page_uuid": uuid, "title": "colors" })).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let category = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .post(
+            "/v1/modules",
+            json!({ "page_uuid": uuid, "category_uuid": category, "title": "red", "content": "#f00" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let page = app.get(&format!("/v1/pages/{}/modules", uuid)).await.json();
+    assert_eq!(page["fields"]["modules"][0]["content"], "Hello from memory.");
+    assert_eq!(page["fields"]["categories"][0]["modules"][0]["content"], "#f00");
+
+    let pages = app.get("/v1/pages").await.json();
+    assert_eq!(pages.as_array().unwrap().len(), 1);
+    assert_eq!(repository.read_page(&uuid).unwrap().page_title, "About us");
+
+    // nothing was written to the database.
+    assert!(Page::read_all(&pool.get().unwrap()).unwrap().is_empty());
+
+    let res = app.get("/about").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.text().contains("<title>About us</title>"));
+    assert!(res.text().contains("Hello from memory."));
+
+    assert_eq!(app.get("/missing").await.status, StatusCode::NOT_FOUND);
+
+    let res = app
+        .post("/v1/redirects", json!({ "source_path": "/about-us", "target": "/about", "status_code": 302 }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    assert_eq!(repository.list_redirects().unwrap().len(), 1);
+
+    let res = app.get("/about-us").await;
+    assert_eq!(res.status, StatusCode::FOUND);
+    assert_eq!(res.header("location").unwrap(), "/about");
+}
+
+#[actix_rt::test]
+async fn moved_pages_redirect_and_stale_writes_fail() {
+    let (pool, conf) = database();
+    seed_service::seed(&["root".to_string()], &pool.get().unwrap()).unwrap();
+    let repository = Arc::new(MemoryRepository::new());
+    let mut app = start_with_repositories(pool.clone(), conf, repository.repositories(pool)).await;
+    app.login_root().await;
+
+    let uuid = app.create_page("/about", "About us").await;
+    let page = json!({ "page_name": "index", "page_url": "/team", "page_title": "Our team" });
+
+    let res = app.put_versioned(&format!("/v1/pages/{}", uuid), "\"1\"", page.clone()).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(res.header("etag").unwrap(), "\"2\"");
+
+    let res = app.put_versioned(&format!("/v1/pages/{}", uuid), "\"1\"", page).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+
+    let res = app.get("/about").await;
+    assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.header("location").unwrap(), "/team");
+    assert!(app.get("/team").await.text().contains("<title>Our team</title>"));
+
+    let res = app.delete_versioned(&format!("/v1/pages/{}", uuid), "\"2\"").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(app.get("/team").await.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn batches_and_redirect_imports_are_all_or_nothing() {
+    let (pool, conf) = database();
+    seed_service::seed(&["root".to_string()], &pool.get().unwrap()).unwrap();
+    let repository = Arc::new(MemoryRepository::new());
+    let mut app = start_with_repositories(pool.clone(), conf, repository.repositories(pool)).await;
+    app.login_root().await;
+
+    let res = app
+        .post(
+            "/v1/batch",
+            json!({ "operations": [
+                { "resource": "page", "op": "create", "data": { "page_name": "index", "page_url": "/about", "page_title": "About" } },
+                { "resource": "category", "op": "create", "data": { "title": "team", "page_uuid": "$ref:0" } },
+                { "resource": "module", "op": "create",
+                  "data": { "title": "intro", "page_uuid": "$ref:0", "category_uuid": "$ref:1", "content": "Hello." } }
+            ] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let results = res.json();
+    let page_uuid = results[0]["uuid"].as_str().unwrap().to_string();
+    let category_uuid = results[1]["uuid"].as_str().unwrap().to_string();
+    let module_uuid = results[2]["uuid"].as_str().unwrap().to_string();
+    assert_eq!(repository.read_category_modules(&category_uuid).unwrap().len(), 1);
+
+    // the module and the new page are dropped with the failed delete.
+    let res = app
+        .post(
+            "/v1/batch",
+            json!({ "operations": [
+                { "resource": "page", "op": "create", "data": { "page_name": "index", "page_url": "/team", "page_title": "Team" } },
+                { "resource": "module", "op": "create", "data": { "title": "more", "page_uuid": page_uuid, "content": "More." } },
+                { "resource": "category", "op": "delete", "id": category_uuid, "version": 9 }
+            ] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(res.json()["operation"], 2);
+    assert_eq!(app.get("/v1/pages").await.json().as_array().unwrap().len(), 1);
+    assert!(repository.read_page_with_modules(&page_uuid).unwrap().fields.modules.is_empty());
+
+    // deleting the category takes its modules with it.
+    let res = app.delete_versioned(&format!("/v1/category/{}", category_uuid), "\"1\"").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert!(repository.read_module(&module_uuid).is_err());
+
+    // the second row loops back to the first, so neither is imported.
+    let import = |csv: &'static str| {
+        actix_web::test::TestRequest::post()
+            .uri("/v1/redirects/import")
+            .header("content-type", "text/csv")
+            .set_payload(csv)
+    };
+    let res = app.send(import("source_path,target\n/a,/b\n/b,/a\n")).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    assert!(repository.list_redirects().unwrap().is_empty());
+
+    let res = app.send(import("source_path,target\n/a,/about\n/b,/about\n")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let redirects: Vec<Redirect> = serde_json::from_value(app.get("/v1/redirects").await.json()).unwrap();
+    assert_eq!(redirects.len(), 2);
+
+    let res = app
+        .put(
+            &format!("/v1/redirects/{}", redirects[0].uuid),
+            json!({ "source_path": "/b", "target": "/about" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let res = app.delete(&format!("/v1/redirects/{}", redirects[1].uuid)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(repository.list_redirects().unwrap(), vec![redirects[0].clone()]);
+}
@@ -0,0 +1,85 @@
+use actix_web::http::StatusCode;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::{database, spawn_seeded};
+use crate::services::seed_service::{fixture_names, seed, SeedError, SeedReport};
+
+/// Writes `text` to a fixture file of its own, since tests run in parallel.
+fn fixture_file(extension: &str, text: &str) -> String {
+    let path: PathBuf = std::env::temp_dir().join(format!("radical-fixture-{}.{}", Uuid::new_v4(), extension));
+    std::fs::write(&path, text).unwrap();
+
+    path.to_str().unwrap().to_string()
+}
+
+#[actix_rt::test]
+async fn migrations_add_no_rows() {
+    let mut app = spawn_seeded(&[]).await;
+
+    let res = app.get("/v1/pages").await;
+    assert_eq!(res.header("x-total-count").as_deref(), Some("0"));
+    assert_eq!(app.get("/").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.login("root", "").await.status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn seeding_again_adds_nothing() {
+    let (pool, _) = database();
+    let db = pool.get().unwrap();
+    let names = fixture_names("root, demo");
+
+    let report = seed(&names, &db).unwrap();
+    assert_eq!(
+        report,
+        SeedReport {
+            pages: 1,
+            categories: 1,
+            modules: 7,
+            users: 1
+        }
+    );
+
+    assert_eq!(seed(&names, &db).unwrap(), SeedReport::default());
+}
+
+#[test]
+fn fixtures_are_read_from_yaml_and_json_files() {
+    let (pool, _) = database();
+    let db = pool.get().unwrap();
+
+    let yaml = fixture_file(
+        "yaml",
+        "pages:\n  - page_name: index\n    page_url: /blog\n    page_title: Blog\n    modules:\n      - title: title\n        content: Posts\n",
+    );
+    let json = fixture_file("json", r#"{ "users": [{ "username": "editor", "password": "correct horse" }] }"#);
+
+    let report = seed(&[yaml, json], &db).unwrap();
+    assert_eq!(report.pages, 1);
+    assert_eq!(report.modules, 1);
+    assert_eq!(report.users, 1);
+}
+
+#[test]
+fn a_bad_fixture_adds_nothing() {
+    let (pool, _) = database();
+    let db = pool.get().unwrap();
+
+    let invalid = fixture_file("json", r#"{ "pages": [{ "page_name": "index", "page_url": "blog", "page_title": "Blog" }] }"#);
+    let names = vec!["demo".to_string(), invalid.clone()];
+
+    match seed(&names, &db) {
+        Err(SeedError::Rejected(name, message)) => {
+            assert_eq!(name, invalid);
+            assert!(message.contains("page_url"), "{}", message);
+        }
+        other => panic!("Expected the fixture to be rejected, got {:?}", other),
+    }
+
+    // the demo set went with it.
+    assert_eq!(seed(&fixture_names("demo"), &db).unwrap().pages, 1);
+
+    let unknown_field = fixture_file("yaml", "pages: []\nposts: []\n");
+    assert!(matches!(seed(&[unknown_field], &db), Err(SeedError::Unreadable(_, _))));
+    assert!(matches!(seed(&fixture_names("nowhere"), &db), Err(SeedError::NotFound(_))));
+}
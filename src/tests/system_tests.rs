@@ -0,0 +1,102 @@
+use actix_web::http::StatusCode;
+
+use super::{database, spawn, start};
+use crate::services::migration_service;
+
+#[actix_rt::test]
+async fn probes_answer_without_being_limited() {
+    let (pool, mut conf) = database();
+    conf.rate_limits = Some("default=1/3600".to_string());
+    let mut app = start(pool, conf).await;
+
+    for _ in 0..3 {
+        let res = app.get("/healthz").await;
+        assert_eq!(res.status, StatusCode::OK);
+        assert_eq!(res.json()["status"], "ok");
+        assert_eq!(res.header("ratelimit-remaining"), None);
+
+        let res = app.get("/readyz").await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+        assert_eq!(res.json()["ready"], true);
+        assert_eq!(res.json()["migrations"], "ok");
+        assert_eq!(res.header("cache-control").unwrap(), "no-store");
+    }
+
+    // everything else still counts.
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/pages").await.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_rt::test]
+async fn pending_migrations_are_not_ready() {
+    let (pool, conf) = database();
+    migration_service::revert_latest(&pool.get().unwrap()).unwrap();
+    let mut app = start(pool, conf).await;
+
+    let res = app.get("/readyz").await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.json()["ready"], false);
+    assert_eq!(res.json()["database"], "ok");
+    assert_eq!(res.json()["migrations"], "1 pending");
+    assert_eq!(res.json()["templates"], "ok");
+
+    // the server is still alive, only not ready.
+    assert_eq!(app.get("/healthz").await.status, StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn the_status_needs_a_login() {
+    let mut app = spawn().await;
+    assert_eq!(app.get("/v1/system/status").await.status, StatusCode::UNAUTHORIZED);
+
+    app.login_root().await;
+    let res = app.get("/v1/system/status").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+
+    let status = res.json();
+    assert_eq!(status["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(status["database"], "SQLite");
+    assert_eq!(status["pool"]["max_size"], 4);
+    assert!(status["pool"]["connections"].as_u64().unwrap() >= 1);
+    assert!(status["templates"].as_u64().unwrap() > 0);
+    assert!(status["uptime_seconds"].is_u64());
+}
+
+#[actix_rt::test]
+async fn a_busy_pool_is_unavailable_rather_than_a_bad_request() {
+    let (pool, mut conf) = database();
+    conf.pool_size = Some(1);
+    conf.pool_timeout = Some(1);
+    // a second pool on the same database, with one connection that's held for the whole test.
+    let busy = crate::models::establish_database_connection(conf.clone()).unwrap();
+    let _held = busy.get().unwrap();
+    let mut app = start(busy, conf).await;
+
+    // through the repository, and through `run_query`.
+    for path in &["/v1/pages", "/v1/menus"] {
+        let res = app.get(path).await;
+        assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE, "{}", path);
+        assert_eq!(res.json()["code"], "unavailable");
+        assert_eq!(res.header("retry-after").unwrap(), "5");
+    }
+
+    // the first pool keeps the in-memory database alive until here.
+    drop(pool);
+}
+
+#[test]
+fn migration_status_only_reads() {
+    use crate::services::migration_service::__diesel_schema_migrations;
+    use diesel::connection::SimpleConnection;
+    use diesel::prelude::*;
+    use crate::models::RunQueryDsl;
+
+    let (pool, _) = database();
+    let db = pool.get().unwrap();
+    assert!(migration_service::status(&db).unwrap().iter().all(|status| status.applied));
+
+    // without diesel's table every migration is pending, and checking doesn't make the table.
+    db.batch_execute("DROP TABLE __diesel_schema_migrations").unwrap();
+    assert!(migration_service::status(&db).unwrap().iter().all(|status| !status.applied));
+    assert!(__diesel_schema_migrations::table.count().get_result::<i64>(&db).is_err());
+}
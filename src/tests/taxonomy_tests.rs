@@ -0,0 +1,151 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn vocabularies_hold_a_tree_of_terms() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/taxonomy/vocabularies", json!({ "name": "topics", "title": "Topics" }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let vocabulary_uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .post(
+            "/v1/taxonomy/terms",
+            json!({ "vocabulary_uuid": vocabulary_uuid, "slug": "code", "title": "Code" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let parent_uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let res = app
+        .post(
+            "/v1/taxonomy/terms",
+            json!({ "vocabulary_uuid": vocabulary_uuid, "parent_uuid": parent_uuid, "slug": "rust", "title": "Rust" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let res = app.get("/v1/taxonomy/vocabularies/topics/terms").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json()[0]["slug"], "code");
+    assert_eq!(res.json()[0]["children"][0]["slug"], "rust");
+
+    let res = app
+        .put("/v1/taxonomy/vocabularies/topics", json!({ "name": "topics", "title": "Subjects" }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/taxonomy/vocabularies/topics").await.json()["title"], "Subjects");
+
+    // terms go with their vocabulary.
+    assert_eq!(app.delete("/v1/taxonomy/vocabularies/topics").await.status, StatusCode::OK);
+    let res = app.get(&format!("/v1/taxonomy/terms/{}", parent_uuid)).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn terms_are_assigned_to_pages() {
+    let mut app = spawn().await;
+    app.login_root().await;
+    let page_uuid = app.index_uuid().await;
+
+    let res = app
+        .post("/v1/taxonomy/vocabularies", json!({ "name": "topics", "title": "Topics" }))
+        .await;
+    let vocabulary_uuid = res.json()["uuid"].as_str().unwrap().to_string();
+    let res = app
+        .post(
+            "/v1/taxonomy/terms",
+            json!({ "vocabulary_uuid": vocabulary_uuid, "slug": "rust", "title": "Rust" }),
+        )
+        .await;
+    let term_uuid = res.json()["uuid"].as_str().unwrap().to_string();
+
+    let path = format!("/v1/pages/{}/terms/{}", page_uuid, term_uuid);
+    assert_eq!(app.put(&path, json!({})).await.status, StatusCode::OK);
+    // assigning twice changes nothing.
+    assert_eq!(app.put(&path, json!({})).await.status, StatusCode::OK);
+
+    let res = app.get(&format!("/v1/pages/{}/terms", page_uuid)).await;
+    assert_eq!(res.json().as_array().unwrap().len(), 1);
+    assert_eq!(res.json()[0]["slug"], "rust");
+
+    let res = app.get(&format!("/v1/taxonomy/terms/{}/pages", term_uuid)).await;
+    assert_eq!(res.json()[0]["page_url"], "/");
+
+    let res = app.get(&format!("/v1/pages/{}?include=tags", page_uuid)).await;
+    assert_eq!(res.json()["tags"][0]["title"], "Rust");
+
+    assert_eq!(app.delete(&path).await.status, StatusCode::OK);
+    let res = app.get(&format!("/v1/pages/{}/terms", page_uuid)).await;
+    assert!(res.json().as_array().unwrap().is_empty());
+
+    let res = app.put(&format!("/v1/pages/{}/terms/nowhere", page_uuid), json!({})).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn updates_keep_uuids_and_trees_together() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/taxonomy/vocabularies", json!({ "name": "Blog Topics", "title": "Topics" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let mut vocabularies = Vec::new();
+    for name in &["topics", "regions"] {
+        let res = app
+            .post("/v1/taxonomy/vocabularies", json!({ "name": name, "title": name }))
+            .await;
+        vocabularies.push(res.json()["uuid"].as_str().unwrap().to_string());
+    }
+
+    // a uuid in the body doesn't change the vocabulary's own.
+    let res = app
+        .put(
+            "/v1/taxonomy/vocabularies/topics",
+            json!({ "uuid": "taken-over", "name": "topics", "title": "Subjects" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(app.get("/v1/taxonomy/vocabularies/topics").await.json()["uuid"], vocabularies[0]);
+    let res = app
+        .put("/v1/taxonomy/vocabularies/topics", json!({ "name": "Topics!", "title": "Subjects" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let term = |parent_uuid: Option<&str>, slug: &str| {
+        json!({ "vocabulary_uuid": vocabularies[0], "parent_uuid": parent_uuid, "slug": slug, "title": slug })
+    };
+    let parent_uuid = app.post("/v1/taxonomy/terms", term(None, "code")).await.json()["uuid"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let child_uuid = app.post("/v1/taxonomy/terms", term(Some(&parent_uuid), "rust")).await.json()["uuid"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // the parent can't leave its children behind in the other vocabulary.
+    let mut moved = term(None, "code");
+    moved["vocabulary_uuid"] = json!(vocabularies[1]);
+    let res = app.put(&format!("/v1/taxonomy/terms/{}", parent_uuid), moved.clone()).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["errors"][0]["field"], "vocabulary_uuid");
+
+    // a term without children can move, and keeps its uuid.
+    let mut moved = term(None, "rust");
+    moved["vocabulary_uuid"] = json!(vocabularies[1]);
+    moved["uuid"] = json!("taken-over");
+    let res = app.put(&format!("/v1/taxonomy/terms/{}", child_uuid), moved).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let res = app.get(&format!("/v1/taxonomy/terms/{}", child_uuid)).await;
+    assert_eq!(res.json()["vocabulary_uuid"], vocabularies[1]);
+}
@@ -0,0 +1,85 @@
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+use super::spawn;
+
+#[actix_rt::test]
+async fn root_logs_in_once_without_a_password() {
+    let mut app = spawn().await;
+
+    app.login_root().await;
+    assert_eq!(app.get("/v1/user").await.status, StatusCode::OK);
+
+    // the passwordless login is only for setting up the first real user.
+    let res = app.login("root", "").await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn writes_need_a_token() {
+    let mut app = spawn().await;
+
+    let res = app
+        .post("/v1/pages", json!({ "page_name": "index", "page_url": "/a", "page_title": "A" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    app.set_token("not-a-token");
+    let res = app.delete("/v1/cache").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/v1/user").await.status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_rt::test]
+async fn users_log_in_with_their_password() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/user", json!({ "username": "editor", "password": "correct horse" }))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+
+    let user = app.get("/v1/user/editor").await;
+    assert_eq!(user.status, StatusCode::OK);
+    assert_eq!(user.json()["username"], "editor");
+
+    app.logout();
+    assert_eq!(app.login("editor", "wrong password").await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login("editor", "correct horse").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/user").await.status, StatusCode::OK);
+
+    // logging out expires the cookie.
+    let res = app.delete("/v1/user/logout").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.header("set-cookie").unwrap().starts_with("auth=;"));
+}
+
+#[actix_rt::test]
+async fn users_are_validated() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    let res = app
+        .post("/v1/user", json!({ "username": "editor", "password": "short" }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.text().contains("password"));
+
+    let res = app
+        .post("/v1/user", json!({ "username": "root", "password": "long enough" }))
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+}
+
+#[actix_rt::test]
+async fn users_can_be_deleted() {
+    let mut app = spawn().await;
+    app.login_root().await;
+
+    app.post("/v1/user", json!({ "username": "editor", "password": "correct horse" }))
+        .await;
+
+    assert_eq!(app.delete("/v1/user/editor").await.status, StatusCode::OK);
+    assert_eq!(app.get("/v1/user/editor").await.status, StatusCode::NOT_FOUND);
+}
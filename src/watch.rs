@@ -3,11 +3,15 @@ extern crate notify;
 use actix_web::web::Data;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::sync::mpsc::channel;
-use std::sync::Mutex;
 use std::time::Duration;
 
-/// Watches the templates directory and refreshes the templates in memory on update.
-pub fn watch(hb: Data<Mutex<handlebars::Handlebars<'_>>>) -> notify::Result<()> {
+use crate::services::cache_service::PageCache;
+use crate::services::template_service::Templates;
+
+/// Watches the templates directory and swaps in freshly compiled templates on update.
+/// Every cached page is dropped as well, since any of them may use the changed template.
+/// If a template fails to compile, the previous templates keep serving.
+pub fn watch(templates: Data<Templates>, cache: Data<PageCache>) -> notify::Result<()> {
     let (tx, rx) = channel();
 
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_secs(2))?;
@@ -16,13 +20,10 @@ pub fn watch(hb: Data<Mutex<handlebars::Handlebars<'_>>>) -> notify::Result<()>
 
     loop {
         match rx.recv() {
-            Ok(_) => {
-                hb.lock().unwrap().clear_templates();
-                hb.lock()
-                    .unwrap()
-                    .register_templates_directory(".hbs", "./templates")
-                    .unwrap();
-            }
+            Ok(_) => match templates.reload() {
+                Ok(_) => cache.clear(),
+                Err(e) => log::error!("Templates not reloaded, keeping the current ones: {}", e),
+            },
             Err(e) => println!("watch error: {:?}", e),
         }
     }